@@ -0,0 +1,67 @@
+#!/usr/bin/env bash
+# Benchmark parallel per-process collection (--jobs) on a host with many processes.
+#
+# Spawns CHILDREN idle child processes so the result does not depend on what
+# else runs on the machine, then compares serial and parallel collection.
+#
+# Usage: ./bench-parallel.sh [CHILDREN] [JOBS]   (defaults: 2000, number of CPUs)
+
+set -euo pipefail
+
+BINARY="./target/release/stop"
+CHILDREN="${1:-2000}"
+JOBS="${2:-$(nproc)}"
+
+echo "stop Parallel Collection Benchmark"
+echo "=================================="
+echo ""
+
+# Build release binary if needed
+if [ ! -f "$BINARY" ]; then
+    echo "Building release binary..."
+    cargo build --release
+    echo ""
+fi
+
+# Check if hyperfine is available
+if ! command -v hyperfine &> /dev/null; then
+    echo "hyperfine not found. Installing..."
+    cargo install hyperfine
+    echo ""
+fi
+
+# jq counts the processes in the snapshot
+if ! command -v jq &> /dev/null; then
+    echo "jq not found. Install it with your package manager (e.g. apt install jq)." >&2
+    exit 1
+fi
+
+# Spawn idle children; each holds a few fds so open file counting has work to do
+pids=()
+cleanup() {
+    if [ ${#pids[@]} -gt 0 ]; then
+        kill "${pids[@]}" 2> /dev/null || true
+        wait 2> /dev/null || true
+    fi
+}
+trap cleanup EXIT
+
+echo "Spawning $CHILDREN child processes..."
+for _ in $(seq "$CHILDREN"); do
+    sleep 3600 < /dev/null > /dev/null 2>&1 &
+    pids+=($!)
+done
+process_count=$($BINARY --json --top-n 1000000 | jq '.processes | length')
+echo "Process count tested: $process_count, CPUs: $(nproc)"
+echo ""
+
+for backend in sysinfo procfs; do
+    echo "Benchmarking verbose JSON, $backend backend..."
+    hyperfine --warmup 2 --runs 10 \
+        "$BINARY --backend $backend --json --verbose --top-n 1000000 --jobs 1" \
+        "$BINARY --backend $backend --json --verbose --top-n 1000000 --jobs $JOBS"
+    echo ""
+done
+
+echo "Note: Collection includes mandatory 200ms sleep for CPU accuracy."
+echo "Speedup applies to the time after the sleep."
@@ -0,0 +1,412 @@
+use crate::collector;
+use crate::{Args, CollectOptions, filter::FilterExpr, matches_search};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replacement for redacted command line arguments and environment values.
+const REDACTED: &str = "***";
+
+/// Directory of the captured `/proc` tree inside the bundle.
+const BUNDLE_PROC_DIR: &str = "proc";
+
+/// System-wide files copied from the proc root.
+const SYSTEM_FILES: &[&str] = &[
+    "stat",
+    "meminfo",
+    "loadavg",
+    "uptime",
+    "pressure/cpu",
+    "pressure/memory",
+    "pressure/io",
+    "sys/kernel/hostname",
+    "sys/kernel/osrelease",
+    "sys/kernel/ostype",
+];
+
+/// Per-process files copied from `<proc_root>/<pid>/`.
+const PROCESS_FILES: &[&str] = &[
+    "stat",
+    "status",
+    "io",
+    "cgroup",
+    "oom_score",
+    "oom_score_adj",
+];
+
+/// Argument names whose values are redacted by `--redact-cmdline secrets`.
+const SECRET_KEYWORDS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "key",
+    "auth",
+    "credential",
+];
+
+/// How command lines are redacted in a capture bundle.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CmdlineRedaction {
+    /// Keep command lines as they are
+    None,
+    /// Replace values of password/secret/token/key arguments
+    Secrets,
+    /// Keep only the program, dropping all arguments
+    Args,
+}
+
+/// Which process environments are included in a capture bundle.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvironCapture {
+    /// Leave environments out
+    None,
+    /// Variable names only, values redacted
+    Keys,
+    /// Names and values
+    Full,
+}
+
+/// Arguments of `stop capture`.
+#[derive(clap::Args, Debug)]
+pub struct CaptureArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Bundle to write (tar), or - for stdout"
+    )]
+    pub out: PathBuf,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "none",
+        help = "Redact command lines: none, secrets (password/token/key values) or args (program only)"
+    )]
+    pub redact_cmdline: CmdlineRedaction,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "none",
+        help = "Include process environments: none, keys (names only) or full"
+    )]
+    pub environ: EnvironCapture,
+}
+
+/// Description of a bundle, stored as `capture.json` next to the `/proc` tree.
+#[derive(Serialize, Debug)]
+struct Manifest<'a> {
+    /// Version of stop that wrote the bundle
+    version: &'static str,
+    /// ISO 8601 timestamp (RFC3339) of the capture
+    timestamp: &'a str,
+    /// Directory of the captured tree, to be passed to `--proc-root`
+    proc_root: &'static str,
+    /// Captured process IDs
+    pids: &'a [u32],
+    filter: Option<&'a str>,
+    search: Option<&'a str>,
+    redact_cmdline: CmdlineRedaction,
+    environ: EnvironCapture,
+}
+
+/// Writes a tar archive laid out like `/proc`, so it can be read with
+/// `--proc-root` after extraction.
+struct Bundle<W: Write> {
+    builder: tar::Builder<W>,
+    proc_root: PathBuf,
+    mtime: u64,
+}
+
+impl<W: Write> Bundle<W> {
+    fn header(&self, entry_type: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_mode(mode);
+        header.set_size(size);
+        header.set_mtime(self.mtime);
+        header
+    }
+
+    fn append_data(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        let mut header = self.header(tar::EntryType::Regular, 0o444, content.len() as u64);
+        self.builder.append_data(&mut header, path, content)
+    }
+
+    fn append_file(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.append_data(&format!("{BUNDLE_PROC_DIR}/{path}"), content)
+    }
+
+    fn append_dir(&mut self, path: &str) -> io::Result<()> {
+        let mut header = self.header(tar::EntryType::Directory, 0o755, 0);
+        self.builder.append_data(
+            &mut header,
+            format!("{BUNDLE_PROC_DIR}/{path}/"),
+            io::empty(),
+        )
+    }
+
+    /// Copies a file from the proc root, skipping it if it cannot be read
+    /// (e.g. `io` of another user's process).
+    fn copy_file(&mut self, path: &str) -> io::Result<()> {
+        match fs::read(self.proc_root.join(path)) {
+            Ok(content) => self.append_file(path, &content),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Copies the link targets of a directory such as `fd` or `ns` as symlinks.
+    fn copy_links(&mut self, dir: &str) -> io::Result<()> {
+        let Ok(entries) = fs::read_dir(self.proc_root.join(dir)) else {
+            return Ok(());
+        };
+        self.append_dir(dir)?;
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        for name in names {
+            let path = format!("{dir}/{name}");
+            let Ok(target) = fs::read_link(self.proc_root.join(&path)) else {
+                continue;
+            };
+            let mut header = self.header(tar::EntryType::Symlink, 0o777, 0);
+            self.builder
+                .append_link(&mut header, format!("{BUNDLE_PROC_DIR}/{path}"), target)?;
+        }
+        Ok(())
+    }
+
+    fn copy_process(&mut self, pid: u32, capture: &CaptureArgs) -> io::Result<()> {
+        for file in PROCESS_FILES {
+            self.copy_file(&format!("{pid}/{file}"))?;
+        }
+        if let Ok(cmdline) = fs::read(self.proc_root.join(format!("{pid}/cmdline"))) {
+            let cmdline = redact_cmdline(&cmdline, capture.redact_cmdline);
+            self.append_file(&format!("{pid}/cmdline"), &cmdline)?;
+        }
+        if capture.environ != EnvironCapture::None
+            && let Ok(environ) = fs::read(self.proc_root.join(format!("{pid}/environ")))
+        {
+            let environ = redact_environ(&environ, capture.environ);
+            self.append_file(&format!("{pid}/environ"), &environ)?;
+        }
+        self.copy_links(&format!("{pid}/fd"))?;
+        self.copy_links(&format!("{pid}/ns"))?;
+        if let Ok(entries) = fs::read_dir(self.proc_root.join(format!("{pid}/task"))) {
+            let mut tids: Vec<u32> = entries
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect();
+            tids.sort_unstable();
+            for tid in tids {
+                self.copy_file(&format!("{pid}/task/{tid}/stat"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits NUL-separated `cmdline`/`environ` contents into entries.
+fn split_nul(content: &[u8]) -> impl Iterator<Item = &[u8]> {
+    content.split(|&b| b == 0).filter(|entry| !entry.is_empty())
+}
+
+/// Joins entries back into NUL-terminated contents.
+fn join_nul(entries: impl IntoIterator<Item = Vec<u8>>) -> Vec<u8> {
+    entries
+        .into_iter()
+        .flat_map(|mut entry| {
+            entry.push(0);
+            entry
+        })
+        .collect()
+}
+
+fn is_secret_name(name: &str) -> bool {
+    let name = name.trim_start_matches('-').to_lowercase();
+    SECRET_KEYWORDS.iter().any(|keyword| name.contains(keyword))
+}
+
+/// Redacts the NUL-separated arguments of `/proc/<pid>/cmdline`.
+///
+/// In `Secrets` mode, `--token=abc` becomes `--token=***`, and the argument
+/// following a bare `--password` flag is replaced entirely.
+pub fn redact_cmdline(cmdline: &[u8], mode: CmdlineRedaction) -> Vec<u8> {
+    match mode {
+        CmdlineRedaction::None => cmdline.to_vec(),
+        CmdlineRedaction::Args => join_nul(split_nul(cmdline).take(1).map(<[u8]>::to_vec)),
+        CmdlineRedaction::Secrets => {
+            let mut redact_next = false;
+            join_nul(split_nul(cmdline).map(|arg| {
+                let arg = String::from_utf8_lossy(arg);
+                if std::mem::take(&mut redact_next) {
+                    return REDACTED.as_bytes().to_vec();
+                }
+                match arg.split_once('=') {
+                    Some((name, _)) if is_secret_name(name) => {
+                        format!("{name}={REDACTED}").into_bytes()
+                    }
+                    Some(_) => arg.as_bytes().to_vec(),
+                    None => {
+                        redact_next = arg.starts_with('-') && is_secret_name(&arg);
+                        arg.as_bytes().to_vec()
+                    }
+                }
+            }))
+        }
+    }
+}
+
+/// Redacts the NUL-separated `NAME=value` entries of `/proc/<pid>/environ`.
+pub fn redact_environ(environ: &[u8], mode: EnvironCapture) -> Vec<u8> {
+    match mode {
+        EnvironCapture::Full => environ.to_vec(),
+        EnvironCapture::None => Vec::new(),
+        EnvironCapture::Keys => join_nul(split_nul(environ).map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let name = entry.split_once('=').map_or(&*entry, |(name, _)| name);
+            format!("{name}={REDACTED}").into_bytes()
+        })),
+    }
+}
+
+/// Runs `stop capture`: copies the `/proc` files of all processes matching
+/// `--search`/`--filter` into a tar bundle.
+///
+/// # Errors
+///
+/// Returns error if collection fails or the bundle cannot be written. An
+/// invalid filter is reported to the user and exits with status 1.
+pub fn capture_mode(args: &Args, capture: &CaptureArgs) -> Result<(), Box<dyn Error>> {
+    let filter = match args.filter.as_deref().map(FilterExpr::parse) {
+        Some(Ok(filter)) => Some(filter),
+        Some(Err(e)) => {
+            eprintln!("Error: {e}");
+            eprintln!("Expression: {}", args.filter.as_deref().unwrap_or_default());
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    let mut collector = collector::from_args(args);
+    let snapshot = collector.collect(&CollectOptions::from_args(args))?;
+    let search = args.search.as_deref().map(str::to_lowercase);
+    let own_pid = std::process::id();
+    let pids: Vec<u32> = snapshot
+        .processes
+        .iter()
+        .filter(|p| p.pid != own_pid)
+        .filter(|p| search.as_deref().is_none_or(|term| matches_search(p, term)))
+        .filter(|p| filter.as_ref().is_none_or(|f| f.matches(p)))
+        .map(|p| p.pid)
+        .collect();
+
+    let to_stdout = capture.out == Path::new("-");
+    let writer: Box<dyn Write> = if to_stdout {
+        Box::new(io::stdout().lock())
+    } else {
+        let file = fs::File::create(&capture.out)
+            .map_err(|e| format!("cannot create {}: {e}", capture.out.display()))?;
+        Box::new(io::BufWriter::new(file))
+    };
+    let mut bundle = Bundle {
+        builder: tar::Builder::new(writer),
+        proc_root: collector.proc_root().to_path_buf(),
+        mtime: chrono::Utc::now().timestamp().max(0) as u64,
+    };
+
+    let mut sorted_pids = pids;
+    sorted_pids.sort_unstable();
+    let manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION"),
+        timestamp: &snapshot.timestamp,
+        proc_root: BUNDLE_PROC_DIR,
+        pids: &sorted_pids,
+        filter: args.filter.as_deref(),
+        search: args.search.as_deref(),
+        redact_cmdline: capture.redact_cmdline,
+        environ: capture.environ,
+    };
+    bundle.append_data("capture.json", &serde_json::to_vec_pretty(&manifest)?)?;
+    for file in SYSTEM_FILES {
+        bundle.copy_file(file)?;
+    }
+    for &pid in &sorted_pids {
+        bundle.copy_process(pid, capture)?;
+    }
+    bundle.builder.into_inner()?.flush()?;
+
+    if !to_stdout {
+        eprintln!(
+            "Captured {} processes to {}",
+            sorted_pids.len(),
+            capture.out.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmdline(args: &[&str]) -> Vec<u8> {
+        join_nul(args.iter().map(|arg| arg.as_bytes().to_vec()))
+    }
+
+    #[test]
+    fn test_redact_cmdline_secrets() {
+        let redacted = redact_cmdline(
+            &cmdline(&[
+                "psql",
+                "--host=db",
+                "--password",
+                "hunter2",
+                "--api-token=abc",
+                "PGPASSWORD=x",
+            ]),
+            CmdlineRedaction::Secrets,
+        );
+        assert_eq!(
+            redacted,
+            cmdline(&[
+                "psql",
+                "--host=db",
+                "--password",
+                "***",
+                "--api-token=***",
+                "PGPASSWORD=***",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_redact_cmdline_args() {
+        let original = cmdline(&["python3", "stress.py", "--token=s3cr3t"]);
+        assert_eq!(
+            redact_cmdline(&original, CmdlineRedaction::Args),
+            cmdline(&["python3"])
+        );
+        assert_eq!(redact_cmdline(&original, CmdlineRedaction::None), original);
+        assert_eq!(redact_cmdline(b"", CmdlineRedaction::Args), b"");
+    }
+
+    #[test]
+    fn test_redact_environ() {
+        let environ = cmdline(&["HOME=/root", "API_TOKEN=s3cr3t", "EMPTY="]);
+        assert_eq!(
+            redact_environ(&environ, EnvironCapture::Keys),
+            cmdline(&["HOME=***", "API_TOKEN=***", "EMPTY=***"])
+        );
+        assert_eq!(redact_environ(&environ, EnvironCapture::Full), environ);
+        assert!(redact_environ(&environ, EnvironCapture::None).is_empty());
+    }
+}
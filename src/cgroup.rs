@@ -0,0 +1,356 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Container runtime detected from a cgroup path.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    Containerd,
+    Crio,
+    /// Kubernetes pod whose CRI runtime cannot be told from the path (cgroupfs driver)
+    Kubernetes,
+    Lxc,
+}
+
+impl ContainerRuntime {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+            Self::Containerd => "containerd",
+            Self::Crio => "crio",
+            Self::Kubernetes => "kubernetes",
+            Self::Lxc => "lxc",
+        }
+    }
+}
+
+impl fmt::Display for ContainerRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Container a process runs in.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct ContainerInfo {
+    /// Detected container runtime
+    pub runtime: ContainerRuntime,
+    /// Full container ID (64 hex chars), or container name for LXC
+    pub id: String,
+}
+
+impl ContainerInfo {
+    /// Short form used for display and grouping, e.g. `docker:3f1a9c2b7d4e`.
+    pub fn short_name(&self) -> String {
+        format!("{}:{}", self.runtime, &self.id[..self.id.len().min(12)])
+    }
+}
+
+/// Cgroup membership of a process, parsed from `/proc/<pid>/cgroup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessCgroup {
+    /// Cgroup path (unified hierarchy, or the v1 `name=systemd` hierarchy)
+    pub path: String,
+    /// Container the process belongs to, if any
+    pub container: Option<ContainerInfo>,
+    /// All `(controllers, path)` entries, used to locate per-controller v1 hierarchies
+    hierarchies: Vec<(String, String)>,
+}
+
+impl ProcessCgroup {
+    /// Parses the contents of `/proc/<pid>/cgroup`.
+    ///
+    /// Lines have the form `hierarchy-id:controllers:path`. On pure cgroup v2
+    /// there is a single `0::/path` line. On v1 and hybrid systems the unified
+    /// line is often just `/`, so the `name=systemd` hierarchy is preferred.
+    /// Container detection looks at every hierarchy, since v1 controllers
+    /// may place a container differently.
+    pub fn parse(content: &str) -> Option<Self> {
+        let entries: Vec<(&str, &str)> = content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ':');
+                let _id = parts.next()?;
+                let controllers = parts.next()?;
+                let path = parts.next()?;
+                Some((controllers, path))
+            })
+            .collect();
+
+        let unified = entries.iter().find(|(c, _)| c.is_empty()).map(|(_, p)| *p);
+        let systemd = entries
+            .iter()
+            .find(|(c, _)| *c == "name=systemd")
+            .map(|(_, p)| *p);
+        let path = match (unified, systemd) {
+            (Some(unified), _) if unified != "/" || entries.len() == 1 => unified,
+            (_, Some(systemd)) => systemd,
+            (Some(unified), None) => unified,
+            (None, None) => entries.first()?.1,
+        };
+
+        let container = std::iter::once(path)
+            .chain(entries.iter().map(|(_, p)| *p))
+            .find_map(detect_container);
+
+        Some(Self {
+            path: path.to_string(),
+            container,
+            hierarchies: entries
+                .iter()
+                .map(|(c, p)| (c.to_string(), p.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Path in the cgroup v2 unified hierarchy, if listed.
+    pub fn unified_path(&self) -> Option<&str> {
+        self.hierarchies
+            .iter()
+            .find(|(c, _)| c.is_empty())
+            .map(|(_, p)| p.as_str())
+    }
+
+    /// Path in the cgroup v1 hierarchy that has `controller` attached (e.g. "memory").
+    pub fn controller_path(&self, controller: &str) -> Option<&str> {
+        self.hierarchies
+            .iter()
+            .find(|(c, _)| c.split(',').any(|name| name == controller))
+            .map(|(_, p)| p.as_str())
+    }
+}
+
+/// Mount point of the cgroup v2 unified hierarchy: `cgroup_root` itself on
+/// pure v2 systems, or its `unified` subdirectory on hybrid systems.
+pub fn unified_mount(cgroup_root: &Path) -> Option<PathBuf> {
+    [cgroup_root.to_path_buf(), cgroup_root.join("unified")]
+        .into_iter()
+        .find(|dir| dir.join("cgroup.controllers").exists())
+}
+
+/// Reads and parses `<proc_root>/<pid>/cgroup`.
+pub fn read_process_cgroup(proc_root: &Path, pid: u32) -> Option<ProcessCgroup> {
+    crate::procfs::read_pid_file(proc_root, pid, "cgroup")
+        .ok()
+        .and_then(|content| ProcessCgroup::parse(&content))
+}
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Detects the container runtime and ID from a cgroup path.
+///
+/// Recognizes the systemd driver naming (`docker-<id>.scope`, `libpod-<id>.scope`,
+/// `cri-containerd-<id>.scope`, `crio-<id>.scope`), the cgroupfs driver layout
+/// (`/docker/<id>`, `/kubepods/.../<id>`) and LXC (`/lxc.payload.<name>`, `/lxc/<name>`).
+pub fn detect_container(path: &str) -> Option<ContainerInfo> {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+
+    for (i, component) in components.iter().enumerate().rev() {
+        let name = component.strip_suffix(".scope").unwrap_or(component);
+        let prefixed = [
+            ("docker-", ContainerRuntime::Docker),
+            ("libpod-", ContainerRuntime::Podman),
+            ("cri-containerd-", ContainerRuntime::Containerd),
+            ("crio-", ContainerRuntime::Crio),
+        ];
+        for (prefix, runtime) in prefixed {
+            if let Some(id) = name.strip_prefix(prefix)
+                && is_container_id(id)
+            {
+                return Some(ContainerInfo {
+                    runtime,
+                    id: id.to_string(),
+                });
+            }
+        }
+
+        let ancestors = &components[..i];
+        if is_container_id(name) {
+            let runtime = if ancestors.contains(&"docker") {
+                ContainerRuntime::Docker
+            } else if ancestors.iter().any(|a| a.starts_with("kubepods")) {
+                ContainerRuntime::Kubernetes
+            } else if ancestors.contains(&"libpod_parent") {
+                ContainerRuntime::Podman
+            } else {
+                continue;
+            };
+            return Some(ContainerInfo {
+                runtime,
+                id: name.to_string(),
+            });
+        }
+
+        let lxc_name = name
+            .strip_prefix("lxc.payload.")
+            .or_else(|| (i == 1 && components[0] == "lxc").then_some(name));
+        if let Some(lxc_name) = lxc_name {
+            return Some(ContainerInfo {
+                runtime: ContainerRuntime::Lxc,
+                id: lxc_name.to_string(),
+            });
+        }
+    }
+    None
+}
+
+/// Derives the systemd unit (service, scope or slice) a cgroup path belongs to.
+///
+/// The deepest unit in the path wins, so `/system.slice/nginx.service` maps to
+/// `nginx.service` and a user's app scope maps to that scope rather than
+/// `user@1000.service`. Sub-cgroups created by the unit itself (e.g. podman's
+/// `.../libpod-<id>.scope/container`) map to the enclosing unit.
+pub fn systemd_unit(path: &str) -> Option<String> {
+    path.rsplit('/')
+        .find(|component| {
+            [".service", ".scope", ".slice"]
+                .iter()
+                .any(|suffix| component.len() > suffix.len() && component.ends_with(suffix))
+        })
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "3f1a9c2b7d4e5f60718293a4b5c6d7e8f90123456789abcdef0123456789abcd";
+
+    fn parse_fixture(content: &str) -> ProcessCgroup {
+        ProcessCgroup::parse(content).expect("fixture should parse")
+    }
+
+    fn assert_container(cgroup: &ProcessCgroup, runtime: ContainerRuntime) {
+        let container = cgroup.container.as_ref().expect("expected a container");
+        assert_eq!(container.runtime, runtime);
+        assert_eq!(container.id, ID);
+    }
+
+    #[test]
+    fn test_docker_systemd_driver() {
+        let cgroup = parse_fixture(include_str!(
+            "../tests/fixtures/cgroup/docker-systemd.cgroup"
+        ));
+        assert_eq!(cgroup.path, format!("/system.slice/docker-{ID}.scope"));
+        assert_container(&cgroup, ContainerRuntime::Docker);
+        assert_eq!(
+            cgroup.container.unwrap().short_name(),
+            "docker:3f1a9c2b7d4e"
+        );
+    }
+
+    #[test]
+    fn test_docker_cgroupfs_v1() {
+        let cgroup = parse_fixture(include_str!(
+            "../tests/fixtures/cgroup/docker-cgroupfs-v1.cgroup"
+        ));
+        // Hybrid hierarchy: the unified line is "/", so name=systemd wins
+        assert_eq!(cgroup.path, format!("/docker/{ID}"));
+        assert_container(&cgroup, ContainerRuntime::Docker);
+    }
+
+    #[test]
+    fn test_podman_rootless() {
+        let cgroup = parse_fixture(include_str!(
+            "../tests/fixtures/cgroup/podman-rootless.cgroup"
+        ));
+        assert_container(&cgroup, ContainerRuntime::Podman);
+    }
+
+    #[test]
+    fn test_kubernetes_runtimes() {
+        let containerd = parse_fixture(include_str!(
+            "../tests/fixtures/cgroup/k8s-containerd.cgroup"
+        ));
+        assert_container(&containerd, ContainerRuntime::Containerd);
+
+        let crio = parse_fixture(include_str!("../tests/fixtures/cgroup/k8s-crio.cgroup"));
+        assert_container(&crio, ContainerRuntime::Crio);
+
+        let cgroupfs = parse_fixture(include_str!(
+            "../tests/fixtures/cgroup/k8s-cgroupfs-v1.cgroup"
+        ));
+        assert_container(&cgroupfs, ContainerRuntime::Kubernetes);
+    }
+
+    #[test]
+    fn test_lxc() {
+        let cgroup = parse_fixture(include_str!("../tests/fixtures/cgroup/lxc.cgroup"));
+        let container = cgroup.container.unwrap();
+        assert_eq!(container.runtime, ContainerRuntime::Lxc);
+        assert_eq!(container.id, "web01");
+        assert_eq!(detect_container("/lxc/db02").unwrap().id, "db02");
+    }
+
+    #[test]
+    fn test_host_processes() {
+        for content in [
+            include_str!("../tests/fixtures/cgroup/host-service.cgroup"),
+            include_str!("../tests/fixtures/cgroup/host-user-scope.cgroup"),
+            include_str!("../tests/fixtures/cgroup/init.cgroup"),
+        ] {
+            let cgroup = parse_fixture(content);
+            assert!(
+                cgroup.container.is_none(),
+                "{} is not a container",
+                cgroup.path
+            );
+        }
+        assert_eq!(
+            parse_fixture(include_str!("../tests/fixtures/cgroup/init.cgroup")).path,
+            "/init.scope"
+        );
+        // A bare hex name outside any known runtime parent is not a container
+        assert!(detect_container(&format!("/custom/{ID}")).is_none());
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let unit = |content: &str| systemd_unit(&parse_fixture(content).path);
+        assert_eq!(
+            unit(include_str!("../tests/fixtures/cgroup/host-service.cgroup")).as_deref(),
+            Some("nginx.service")
+        );
+        assert_eq!(
+            unit(include_str!(
+                "../tests/fixtures/cgroup/host-user-scope.cgroup"
+            ))
+            .as_deref(),
+            Some("app-gnome-firefox-4242.scope")
+        );
+        assert_eq!(
+            unit(include_str!("../tests/fixtures/cgroup/init.cgroup")).as_deref(),
+            Some("init.scope")
+        );
+        assert_eq!(
+            unit(include_str!(
+                "../tests/fixtures/cgroup/podman-rootless.cgroup"
+            )),
+            Some(format!("libpod-{ID}.scope"))
+        );
+        assert_eq!(
+            systemd_unit("/user.slice/user-1000.slice").as_deref(),
+            Some("user-1000.slice")
+        );
+        // cgroupfs driver layouts and the root cgroup have no unit
+        assert_eq!(
+            unit(include_str!(
+                "../tests/fixtures/cgroup/k8s-cgroupfs-v1.cgroup"
+            )),
+            None
+        );
+        assert_eq!(systemd_unit("/"), None);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert!(ProcessCgroup::parse("").is_none());
+    }
+}
@@ -0,0 +1,527 @@
+use crate::cgroup::ProcessCgroup;
+use crate::limits::{self, CgroupLimits, LimitScope};
+use crate::procfs::{ProcStat, ProcStatus};
+use crate::procfs_collector::ProcfsCollector;
+use crate::psi::PressureStall;
+use crate::threads::{self, ThreadInfo};
+use crate::{
+    Args, CGROUP_ROOT, CPU_SAMPLE_INTERVAL_MS, CollectOptions, PROC_ROOT, ProcessInfo,
+    SCHEMA_VERSION, SchedInfo, SnapshotMeta, SystemMetrics, SystemSnapshot, cgroup, fds,
+    namespaces, procfs,
+};
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::time::Instant;
+use sysinfo::{System, ThreadKind};
+
+/// Source of the base process metrics, selected with `--backend`.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// The sysinfo crate (all platforms)
+    #[default]
+    Sysinfo,
+    /// Read /proc/<pid>/{stat,status,io,fd} directly (Linux)
+    Procfs,
+}
+
+/// Creates the collector selected by `--backend` and `--proc-root`.
+///
+/// A `--proc-root` is read with the procfs backend; `main` rejects it
+/// together with `--backend sysinfo`.
+pub fn from_args(args: &Args) -> Box<dyn Collector> {
+    match (&args.proc_root, args.backend.unwrap_or_default()) {
+        (Some(root), _) => Box::new(ProcfsCollector::captured(root)),
+        (None, Backend::Procfs) => Box::new(ProcfsCollector::new(Path::new(PROC_ROOT))),
+        (None, Backend::Sysinfo) => Box::new(SysinfoCollector::default()),
+    }
+}
+
+/// The cgroup filesystem matching the processes read, or `None` for a
+/// `--proc-root` capture, which holds no cgroup files of its own.
+pub fn cgroup_root(args: &Args) -> Option<&'static Path> {
+    args.proc_root.is_none().then(|| Path::new(CGROUP_ROOT))
+}
+
+/// Base metrics of one process as reported by a backend.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    /// CPU usage since the previous sample (100% = one core)
+    pub cpu_usage: f32,
+    /// Resident set size in bytes
+    pub memory_bytes: u64,
+    pub virtual_bytes: u64,
+    /// Numeric user ID, if known
+    pub user: Option<String>,
+    pub command: String,
+    pub thread_count: usize,
+    pub disk_read_bytes: u64,
+    pub disk_write_bytes: u64,
+    pub open_files: Option<usize>,
+    /// Individual threads, if `CollectOptions::threads` was requested
+    pub threads: Option<Vec<ThreadInfo>>,
+    /// A thread that the backend also lists as a process (sysinfo on Linux)
+    pub is_thread: bool,
+    /// Parsed `stat`, if the backend already read it
+    pub stat: Option<ProcStat>,
+    /// Parsed `status`, if the backend already read it
+    pub status: Option<ProcStatus>,
+}
+
+/// Base system-wide metrics and processes as reported by a backend.
+#[derive(Debug, Clone, Default)]
+pub struct SystemSample {
+    /// Global CPU usage (0-100%)
+    pub cpu_usage: f32,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub processes: Vec<ProcessSample>,
+}
+
+/// A source of process metrics.
+///
+/// Backends only provide the base metrics; the Linux details enabled in
+/// `CollectOptions` (counters, cgroups, namespaces, ...) are read from the
+/// backend's proc filesystem by `collect` for every backend alike, except
+/// for the `stat` and `status` files a backend already parsed.
+pub trait Collector: Send {
+    /// Samples the base metrics of the system and all processes.
+    ///
+    /// CPU usage is measured between two samples: the first call blocks for
+    /// `CPU_SAMPLE_INTERVAL_MS`, later calls measure since the previous call.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the process list cannot be read.
+    fn sample(&mut self, options: &CollectOptions) -> Result<SystemSample, Box<dyn Error>>;
+
+    /// Root of the proc filesystem details are read from.
+    fn proc_root(&self) -> &Path;
+
+    /// Collects a snapshot of system and process metrics.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if system information collection fails.
+    fn collect(&mut self, options: &CollectOptions) -> Result<SystemSnapshot, Box<dyn Error>> {
+        let started = Instant::now();
+        let sample = self.sample(options)?;
+        let mut snapshot = build_snapshot(sample, self.proc_root(), options);
+        snapshot.meta.collection_ms = started.elapsed().as_secs_f64() * 1000.0;
+        Ok(snapshot)
+    }
+}
+
+/// Collects base metrics through sysinfo, keeping one `System` across samples.
+#[derive(Default)]
+pub struct SysinfoCollector {
+    sys: Option<System>,
+}
+
+impl Collector for SysinfoCollector {
+    fn sample(&mut self, options: &CollectOptions) -> Result<SystemSample, Box<dyn Error>> {
+        let sys = match &mut self.sys {
+            Some(sys) => {
+                sys.refresh_all();
+                sys
+            }
+            None => {
+                let mut sys = System::new_all();
+                std::thread::sleep(std::time::Duration::from_millis(CPU_SAMPLE_INTERVAL_MS));
+                sys.refresh_all();
+                self.sys.insert(sys)
+            }
+        };
+
+        // On Linux, sysinfo also lists threads as processes; they are already
+        // accounted for in their process and would be counted twice when grouping
+        let sys = &*sys;
+        let listed: Vec<_> = sys
+            .processes()
+            .iter()
+            .filter(|(_, process)| {
+                !options.skip_threads || process.thread_kind() != Some(ThreadKind::Userland)
+            })
+            .collect();
+        // open_files() and tasks() read /proc on demand, so they run on the worker pool
+        let processes = parallel_map(listed, options.jobs, |(pid, process)| {
+            let cmd_vec: Vec<String> = process
+                .cmd()
+                .iter()
+                .map(|s| s.to_string_lossy().into_owned())
+                .collect();
+            let disk_usage = process.disk_usage();
+            ProcessSample {
+                pid: pid.as_u32(),
+                name: process.name().to_string_lossy().into_owned(),
+                cpu_usage: process.cpu_usage(),
+                memory_bytes: process.memory(),
+                virtual_bytes: process.virtual_memory(),
+                user: process.user_id().map(|uid| uid.to_string()),
+                command: cmd_vec.join(" "),
+                thread_count: process.tasks().map(|t| t.len()).unwrap_or(1),
+                disk_read_bytes: disk_usage.total_read_bytes,
+                disk_write_bytes: disk_usage.total_written_bytes,
+                open_files: process.open_files(),
+                threads: options
+                    .threads
+                    .then(|| threads::collect_threads(sys, process))
+                    .flatten(),
+                is_thread: process.thread_kind() == Some(ThreadKind::Userland),
+                stat: None,
+                status: None,
+            }
+        });
+
+        Ok(SystemSample {
+            cpu_usage: sys.global_cpu_usage(),
+            memory_total: sys.total_memory(),
+            memory_used: sys.used_memory(),
+            processes,
+        })
+    }
+
+    fn proc_root(&self) -> &Path {
+        Path::new(PROC_ROOT)
+    }
+}
+
+/// Reads the details enabled in `options` for one process.
+///
+/// Percentages are relative to the whole machine; cgroup limits are applied
+/// by the caller, which also gets the parsed cgroup for that.
+fn enrich_process(
+    process: ProcessSample,
+    proc_root: &Path,
+    options: &CollectOptions,
+    total_memory: u64,
+) -> (ProcessInfo, Option<ProcessCgroup>) {
+    let pid = process.pid;
+    // stat and status also carry the scheduling details; backends that
+    // parsed them already pass them along
+    let counters = options.counters || options.sched;
+    let stat = counters
+        .then(|| process.stat.or_else(|| procfs::read_stat(proc_root, pid)))
+        .flatten();
+    let status = counters
+        .then(|| {
+            process
+                .status
+                .or_else(|| procfs::read_status(proc_root, pid))
+        })
+        .flatten()
+        .unwrap_or_default();
+    let smaps = options
+        .smaps
+        .then(|| procfs::read_smaps_rollup(proc_root, pid))
+        .flatten();
+    let cgroup = options
+        .cgroups
+        .then(|| cgroup::read_process_cgroup(proc_root, pid))
+        .flatten();
+
+    let info = ProcessInfo {
+        pid,
+        name: process.name,
+        cpu_percent: process.cpu_usage,
+        memory_bytes: process.memory_bytes,
+        memory_percent: (process.memory_bytes as f64 / total_memory as f64 * 100.0) as f32,
+        virtual_bytes: process.virtual_bytes,
+        shared_bytes: status.shared_bytes,
+        pss_bytes: smaps.map(|s| s.pss_bytes),
+        uss_bytes: smaps.map(|s| s.uss_bytes),
+        user: process.user.unwrap_or_else(|| "unknown".to_string()),
+        command: process.command,
+        thread_count: process.thread_count,
+        disk_read_bytes: process.disk_read_bytes,
+        disk_write_bytes: process.disk_write_bytes,
+        open_files: process.open_files,
+        fd_types: options
+            .fd_types
+            .then(|| fds::count_fd_types(proc_root, pid))
+            .flatten(),
+        cpu_user_seconds: stat.map(|s| s.user_seconds),
+        cpu_system_seconds: stat.map(|s| s.system_seconds),
+        voluntary_ctx_switches: status.voluntary_ctx_switches,
+        involuntary_ctx_switches: status.involuntary_ctx_switches,
+        minor_faults: stat.map(|s| s.minor_faults),
+        major_faults: stat.map(|s| s.major_faults),
+        container: cgroup.as_ref().and_then(|c| c.container.clone()),
+        unit: cgroup.as_ref().and_then(|c| cgroup::systemd_unit(&c.path)),
+        cgroup: cgroup.as_ref().map(|c| c.path.clone()),
+        deltas: None,
+        sched: options
+            .sched
+            .then_some(stat)
+            .flatten()
+            .map(|stat| SchedInfo {
+                nice: stat.nice,
+                priority: stat.priority,
+                policy: stat.policy,
+                cpus_allowed: status.cpus_allowed_list.clone(),
+                oom_score: procfs::read_oom_value(proc_root, pid, "oom_score"),
+                oom_score_adj: procfs::read_oom_value(proc_root, pid, "oom_score_adj"),
+            }),
+        namespaces: options
+            .namespaces
+            .then(|| namespaces::read_namespaces(proc_root, pid))
+            .flatten(),
+        threads: process.threads,
+    };
+    (info, cgroup)
+}
+
+/// Total CPU usage and RSS of the processes within the `own` cgroup subtree.
+///
+/// Thread entries are left out: their CPU and memory are already part of
+/// their process.
+fn own_cgroup_usage(
+    processes: &[(ProcessInfo, Option<ProcessCgroup>, bool)],
+    own: &ProcessCgroup,
+) -> (f32, u64) {
+    processes
+        .iter()
+        .filter(|(_, cgroup, is_thread)| {
+            !is_thread
+                && cgroup
+                    .as_ref()
+                    .is_some_and(|cgroup| limits::is_within(&cgroup.path, &own.path))
+        })
+        .fold((0.0, 0), |(cpu, rss), (process, _, _)| {
+            (cpu + process.cpu_percent, rss + process.memory_bytes)
+        })
+}
+
+/// Maps `items` on up to `jobs` scoped threads, preserving their order.
+///
+/// Items are split into one contiguous chunk per thread; with `jobs <= 1`
+/// everything runs on the calling thread.
+pub fn parallel_map<T: Send, R: Send>(
+    items: Vec<T>,
+    jobs: usize,
+    f: impl Fn(T) -> R + Sync,
+) -> Vec<R> {
+    if jobs <= 1 || items.len() < 2 {
+        return items.into_iter().map(f).collect();
+    }
+    let chunk_size = items.len().div_ceil(jobs);
+    let mut items = items.into_iter();
+    let chunks: Vec<Vec<T>> = std::iter::from_fn(|| {
+        let chunk: Vec<T> = items.by_ref().take(chunk_size).collect();
+        (!chunk.is_empty()).then_some(chunk)
+    })
+    .collect();
+
+    let f = &f;
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || chunk.into_iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("collection worker panicked"))
+            .collect()
+    })
+}
+
+/// Builds a snapshot from a backend sample, reading the details enabled in
+/// `options` from `proc_root` and applying cgroup limits.
+pub fn build_snapshot(
+    sample: SystemSample,
+    proc_root: &Path,
+    options: &CollectOptions,
+) -> SystemSnapshot {
+    let total_memory = sample.memory_total;
+    let used_memory = sample.memory_used;
+    let mut memory_percent = (used_memory as f64 / total_memory as f64 * 100.0) as f32;
+
+    let mut global_cpu_usage = sample.cpu_usage;
+    let cgroup_root = Path::new(CGROUP_ROOT);
+
+    // Limits of stop's own cgroup, and of each process's cgroup if requested
+    let own_cgroup = options
+        .limits
+        .and_then(|_| cgroup::read_process_cgroup(proc_root, std::process::id()));
+    let own_limits = own_cgroup
+        .as_ref()
+        .map(|own| CgroupLimits::read(cgroup_root, own));
+    let mut limits_cache: HashMap<String, CgroupLimits> = HashMap::new();
+
+    // Reading the per-process files dominates collection time on hosts with
+    // many processes, so it runs on `options.jobs` threads
+    let enriched = parallel_map(sample.processes, options.jobs, |process| {
+        let is_thread = process.is_thread;
+        let (info, cgroup) = enrich_process(process, proc_root, options, total_memory);
+        (info, cgroup, is_thread)
+    });
+    // CPU and RSS of the processes sharing stop's own cgroup subtree
+    let (own_cpu_usage, own_rss) = own_cgroup
+        .as_ref()
+        .map_or((0.0, 0), |own| own_cgroup_usage(&enriched, own));
+
+    let processes: Vec<ProcessInfo> = enriched
+        .into_iter()
+        .map(|(mut process, cgroup, _)| {
+            let limits = match options.limits {
+                Some(LimitScope::Own) => own_limits.as_ref(),
+                Some(LimitScope::Process) => cgroup.as_ref().map(|cgroup| {
+                    &*limits_cache
+                        .entry(cgroup.path.clone())
+                        .or_insert_with(|| CgroupLimits::read(cgroup_root, cgroup))
+                }),
+                None => None,
+            };
+            if let Some(cores) = limits.and_then(|l| l.cpu_limit_cores) {
+                process.cpu_percent /= cores as f32;
+            }
+            if let Some(limit) = limits.and_then(|l| l.memory_limit_bytes) {
+                process.memory_percent =
+                    (process.memory_bytes as f64 / limit as f64 * 100.0) as f32;
+            }
+            process
+        })
+        .collect();
+
+    let meminfo = procfs::read_meminfo(proc_root);
+
+    // Relative to stop's own cgroup, system figures cover that cgroup only
+    let (mut memory_total, mut memory_used) = (total_memory, used_memory);
+    if let Some(limits) = &own_limits {
+        if let Some(cores) = limits.cpu_limit_cores {
+            global_cpu_usage = own_cpu_usage / cores as f32;
+        }
+        if let Some(limit) = limits.memory_limit_bytes {
+            memory_total = limit;
+            memory_used = limits.memory_usage_bytes.unwrap_or(own_rss);
+            memory_percent = (memory_used as f64 / memory_total as f64 * 100.0) as f32;
+        }
+    }
+
+    SystemSnapshot {
+        schema_version: SCHEMA_VERSION,
+        meta: host_meta(proc_root),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        system: SystemMetrics {
+            cpu_usage: global_cpu_usage,
+            memory_total,
+            memory_used,
+            memory_percent,
+            memory_available: meminfo.available,
+            cached: meminfo.cached,
+            buffers: meminfo.buffers,
+            dirty: meminfo.dirty,
+            shmem: meminfo.shmem,
+            hugepages: meminfo.hugepages,
+            pressure: PressureStall::read_system(proc_root),
+            limits: own_limits,
+        },
+        processes,
+    }
+}
+
+/// Tool and host details for the snapshot envelope.
+///
+/// Host name, kernel release and OS type come from `<proc_root>/sys/kernel`,
+/// so captured trees report the host they were taken on. For the live
+/// `/proc`, sysinfo fills whatever is missing.
+fn host_meta(proc_root: &Path) -> SnapshotMeta {
+    let read = |name: &str| {
+        std::fs::read_to_string(proc_root.join("sys/kernel").join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let mut meta = SnapshotMeta {
+        tool: env!("CARGO_BIN_NAME").to_string(),
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        hostname: read("hostname"),
+        os: read("ostype"),
+        kernel: read("osrelease"),
+        collection_ms: 0.0,
+    };
+    if proc_root == Path::new(PROC_ROOT) {
+        meta.hostname = meta.hostname.or_else(System::host_name);
+        meta.os = System::long_os_version().or(meta.os);
+        meta.kernel = meta.kernel.or_else(System::kernel_version);
+    }
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_map_preserves_order() {
+        let items: Vec<u32> = (0..103).collect();
+        let expected: Vec<u32> = items.iter().map(|i| i * 2).collect();
+        for jobs in [0, 1, 4, 200] {
+            assert_eq!(parallel_map(items.clone(), jobs, |i| i * 2), expected);
+        }
+        assert!(parallel_map(Vec::<u32>::new(), 4, |i| i).is_empty());
+    }
+
+    #[test]
+    fn test_own_cgroup_usage_skips_threads() {
+        let process = |pid: u32, cpu: f32, rss: u64| -> ProcessInfo {
+            serde_json::from_value(serde_json::json!({
+                "pid": pid,
+                "name": "worker",
+                "cpu_percent": cpu,
+                "memory_bytes": rss,
+                "memory_percent": 0.0,
+                "virtual_bytes": 0,
+                "user": "0",
+                "command": "worker",
+                "thread_count": 2,
+                "disk_read_bytes": 0,
+                "disk_write_bytes": 0
+            }))
+            .unwrap()
+        };
+        let cgroup = |path: &str| ProcessCgroup::parse(&format!("0::{path}\n"));
+        let own = cgroup("/app.slice").unwrap();
+        let processes = [
+            (
+                process(10, 40.0, 1000),
+                cgroup("/app.slice/web.service"),
+                false,
+            ),
+            // sysinfo lists the second thread of PID 10 with the process's figures
+            (
+                process(11, 40.0, 1000),
+                cgroup("/app.slice/web.service"),
+                true,
+            ),
+            (process(20, 5.0, 500), cgroup("/app.slice"), false),
+            (process(30, 90.0, 9000), cgroup("/other.slice"), false),
+            (process(40, 1.0, 100), None, false),
+        ];
+        assert_eq!(own_cgroup_usage(&processes, &own), (45.0, 1500));
+    }
+
+    #[test]
+    fn test_enrich_reuses_parsed_stat_and_status() {
+        // Nothing is read from disk when the backend parsed both files
+        let stat = "7 (worker) S 1 7 7 0 -1 0 120 0 3 0 250 50 0 0 20 0 1 0 100";
+        let sample = ProcessSample {
+            pid: 7,
+            stat: ProcStat::parse(stat),
+            status: Some(ProcStatus::parse("voluntary_ctxt_switches:\t42\n")),
+            ..Default::default()
+        };
+        let options = CollectOptions {
+            counters: true,
+            ..Default::default()
+        };
+        let (info, _) = enrich_process(sample, Path::new("/nonexistent/proc"), &options, 1);
+        assert_eq!(info.minor_faults, Some(120));
+        assert_eq!(info.major_faults, Some(3));
+        assert_eq!(info.cpu_user_seconds, Some(2.5));
+        assert_eq!(info.voluntary_ctx_switches, Some(42));
+    }
+}
@@ -0,0 +1,484 @@
+//! Columnar output: an Arrow IPC stream with one row per process per
+//! snapshot, and conversion of recorded streams to Parquet.
+
+use crate::columns::{self, Column, ColumnValue, SYSTEM_FIELDS};
+use crate::psi::{Pressure, PressureStall};
+use crate::{ProcessInfo, SystemSnapshot, influx};
+use arrow_array::{
+    ArrayRef, Float32Array, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampNanosecondArray, UInt64Array,
+};
+use arrow_ipc::reader::StreamReader;
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Arguments of `stop parquet`.
+#[derive(clap::Args, Debug)]
+pub struct ParquetArgs {
+    #[arg(
+        value_name = "INPUT",
+        help = "Arrow IPC stream recorded with --format arrow, or - for stdin"
+    )]
+    pub input: PathBuf,
+
+    #[arg(long, value_name = "FILE", help = "Parquet file to write")]
+    pub out: PathBuf,
+}
+
+/// Arrow type of a column value, from the variant its accessor returns.
+fn data_type(value: &ColumnValue) -> DataType {
+    match value {
+        ColumnValue::Unsigned(_) => DataType::UInt64,
+        ColumnValue::Signed(_) => DataType::Int64,
+        ColumnValue::Percent(_) => DataType::Float32,
+        ColumnValue::Float(_) => DataType::Float64,
+        ColumnValue::Text(_) => DataType::Utf8,
+    }
+}
+
+/// Builds an array of the given type from column values; values of another
+/// variant (which the registry never mixes) become nulls.
+fn array<'a>(data_type: &DataType, values: impl Iterator<Item = ColumnValue<'a>>) -> ArrayRef {
+    match data_type {
+        DataType::UInt64 => Arc::new(
+            values
+                .map(|v| match v {
+                    ColumnValue::Unsigned(v) => v,
+                    _ => None,
+                })
+                .collect::<UInt64Array>(),
+        ),
+        DataType::Int64 => Arc::new(
+            values
+                .map(|v| match v {
+                    ColumnValue::Signed(v) => v,
+                    _ => None,
+                })
+                .collect::<Int64Array>(),
+        ),
+        DataType::Float32 => Arc::new(
+            values
+                .map(|v| match v {
+                    ColumnValue::Percent(v) => Some(v),
+                    _ => None,
+                })
+                .collect::<Float32Array>(),
+        ),
+        DataType::Float64 => Arc::new(
+            values
+                .map(|v| match v {
+                    ColumnValue::Float(v) => v,
+                    _ => None,
+                })
+                .collect::<Float64Array>(),
+        ),
+        _ => Arc::new(
+            values
+                .map(|v| match v {
+                    ColumnValue::Text(v) => v,
+                    _ => None,
+                })
+                .collect::<StringArray>(),
+        ),
+    }
+}
+
+/// Accessor of one resource's pressure stall figures.
+type PressureResource = fn(&PressureStall) -> Option<&Pressure>;
+
+/// Pressure stall columns: resource and its accessor.
+const PRESSURE_RESOURCES: [(&str, PressureResource); 3] = [
+    ("cpu", |p| p.cpu.as_ref()),
+    ("memory", |p| p.memory.as_ref()),
+    ("io", |p| p.io.as_ref()),
+];
+
+/// Schema of the rows: the snapshot timestamp, the system fields prefixed
+/// with `system_` (repeated on each row of a snapshot) and the process
+/// columns under their keys.
+///
+/// Types come from the registry accessors: counts and bytes are `UInt64`,
+/// percentages `Float32`, seconds `Float64`, text `Utf8`. Everything but the
+/// timestamp and percentages is nullable.
+fn schema(columns: &[&'static Column]) -> Schema {
+    let system = crate::SystemMetrics::default();
+    let process = ProcessInfo::default();
+    let mut fields = vec![Field::new(
+        "timestamp",
+        DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        false,
+    )];
+    for field in SYSTEM_FIELDS {
+        let value = (field.value)(&system);
+        fields.push(Field::new(
+            format!("system_{}", field.key),
+            data_type(&value),
+            !matches!(value, ColumnValue::Percent(_)),
+        ));
+    }
+    for (resource, _) in PRESSURE_RESOURCES {
+        fields.push(Field::new(
+            format!("system_pressure_{resource}_some_avg10"),
+            DataType::Float32,
+            true,
+        ));
+    }
+    for column in columns {
+        let value = (column.value)(&process);
+        fields.push(Field::new(
+            column.key,
+            data_type(&value),
+            !matches!(value, ColumnValue::Percent(_)),
+        ));
+    }
+    Schema::new(fields)
+}
+
+/// Converts a snapshot to a record batch with one row per process.
+///
+/// # Errors
+///
+/// Returns error if the timestamp is invalid.
+fn record_batch(
+    schema: &SchemaRef,
+    snapshot: &SystemSnapshot,
+    columns: &[&'static Column],
+) -> io::Result<RecordBatch> {
+    let rows = snapshot.processes.len();
+    let nanos = influx::timestamp_nanos(&snapshot.timestamp)?;
+    let mut arrays: Vec<ArrayRef> = vec![Arc::new(
+        TimestampNanosecondArray::from(vec![nanos; rows]).with_timezone("UTC"),
+    )];
+    let mut types = schema.fields().iter().map(|f| f.data_type()).skip(1);
+    for field in SYSTEM_FIELDS {
+        let value = (field.value)(&snapshot.system);
+        arrays.push(array(
+            types.next().unwrap_or(&DataType::Null),
+            std::iter::repeat_n(value, rows),
+        ));
+    }
+    for (_, resource) in PRESSURE_RESOURCES {
+        types.next();
+        let avg10 = snapshot
+            .system
+            .pressure
+            .as_ref()
+            .and_then(resource)
+            .map(|p| p.some.avg10);
+        arrays.push(Arc::new(Float32Array::from(vec![avg10; rows])));
+    }
+    for column in columns {
+        arrays.push(array(
+            types.next().unwrap_or(&DataType::Null),
+            snapshot.processes.iter().map(|p| (column.value)(p)),
+        ));
+    }
+    // A schema built for other columns fails here instead of mislabelling them
+    RecordBatch::try_new(schema.clone(), arrays).map_err(io_error)
+}
+
+/// Arrow IPC stream of snapshots: the schema once, then one record batch per
+/// snapshot, flushed so readers can follow a watch session live.
+pub struct ArrowStream<W: Write> {
+    writer: StreamWriter<W>,
+    schema: SchemaRef,
+    columns: Vec<&'static Column>,
+}
+
+impl<W: Write> ArrowStream<W> {
+    /// Starts a stream with the selected columns (all by default).
+    ///
+    /// # Errors
+    ///
+    /// Returns error if writing the schema fails.
+    pub fn new(out: W, columns: Option<&[&'static Column]>) -> io::Result<Self> {
+        let columns = columns::selected_or_all(columns);
+        let schema = Arc::new(schema(&columns));
+        Ok(Self {
+            writer: StreamWriter::try_new(out, &schema).map_err(io_error)?,
+            schema,
+            columns,
+        })
+    }
+
+    /// Writes one snapshot as a record batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the timestamp is invalid or writing fails.
+    pub fn write(&mut self, snapshot: &SystemSnapshot) -> io::Result<()> {
+        let batch = record_batch(&self.schema, snapshot, &self.columns)?;
+        self.writer.write(&batch).map_err(io_error)?;
+        self.writer.flush().map_err(io_error)
+    }
+
+    /// Writes the end-of-stream marker.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if writing fails.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.finish().map_err(io_error)
+    }
+}
+
+/// Unwraps I/O errors (so a closed pipe stays recognizable) and wraps the rest.
+fn io_error(e: ArrowError) -> io::Error {
+    match e {
+        ArrowError::IoError(_, e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+/// Converts a recorded Arrow IPC stream to a Snappy-compressed Parquet file.
+///
+/// A stream cut off mid-batch (a watch session that was killed) is converted
+/// up to the last complete batch, with a warning.
+///
+/// # Errors
+///
+/// Returns error if the input cannot be read as an Arrow stream or the output
+/// cannot be written.
+pub fn parquet_mode(args: &ParquetArgs) -> Result<(), Box<dyn Error>> {
+    let input: Box<dyn Read> = if args.input.as_os_str() == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(
+            File::open(&args.input)
+                .map_err(|e| format!("cannot open {}: {e}", args.input.display()))?,
+        )
+    };
+    let reader = StreamReader::try_new(BufReader::new(input), None)
+        .map_err(|e| format!("{} is not an Arrow IPC stream: {e}", args.input.display()))?;
+
+    let out = File::create(&args.out)
+        .map_err(|e| format!("cannot create {}: {e}", args.out.display()))?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(out, reader.schema(), Some(properties))?;
+    let mut rows = 0;
+    for batch in reader {
+        match batch {
+            Ok(batch) => {
+                rows += batch.num_rows();
+                writer.write(&batch)?;
+            }
+            Err(e) => {
+                eprintln!("Warning: stopped at an incomplete batch: {e}");
+                break;
+            }
+        }
+    }
+    writer.close()?;
+    eprintln!("Wrote {rows} rows to {}", args.out.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SystemMetrics;
+    use crate::psi::PressureAverages;
+    use arrow_array::Array;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float32Type, TimestampNanosecondType, UInt64Type};
+
+    fn snapshot(timestamp: &str) -> SystemSnapshot {
+        SystemSnapshot {
+            timestamp: timestamp.to_string(),
+            system: SystemMetrics {
+                cpu_usage: 30.0,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                pressure: Some(PressureStall {
+                    io: Some(Pressure {
+                        some: PressureAverages {
+                            avg10: 55.0,
+                            ..Default::default()
+                        },
+                        full: None,
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            processes: vec![
+                ProcessInfo {
+                    pid: 42,
+                    name: "web".to_string(),
+                    cpu_percent: 12.5,
+                    memory_bytes: 1 << 20,
+                    ..Default::default()
+                },
+                ProcessInfo {
+                    pid: 7,
+                    name: "db".to_string(),
+                    open_files: Some(12),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn columns(names: &[&str]) -> Vec<&'static Column> {
+        names
+            .iter()
+            .map(|n| columns::parse_column(n).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_schema_types() {
+        let schema = schema(&columns(&[
+            "pid", "name", "cpu", "rss", "files", "utime", "nice",
+        ]));
+        let field = |name: &str| schema.field_with_name(name).unwrap().clone();
+        assert!(matches!(
+            field("timestamp").data_type(),
+            DataType::Timestamp(TimeUnit::Nanosecond, Some(_))
+        ));
+        assert_eq!(field("system_cpu_usage").data_type(), &DataType::Float32);
+        assert!(!field("system_cpu_usage").is_nullable());
+        assert_eq!(
+            field("system_memory_available").data_type(),
+            &DataType::UInt64
+        );
+        assert!(field("system_memory_available").is_nullable());
+        assert_eq!(
+            field("system_pressure_io_some_avg10").data_type(),
+            &DataType::Float32
+        );
+        assert_eq!(field("pid").data_type(), &DataType::UInt64);
+        assert_eq!(field("name").data_type(), &DataType::Utf8);
+        assert_eq!(field("cpu_percent").data_type(), &DataType::Float32);
+        assert_eq!(field("memory_bytes").data_type(), &DataType::UInt64);
+        assert_eq!(field("open_files").data_type(), &DataType::UInt64);
+        assert_eq!(field("cpu_user_seconds").data_type(), &DataType::Float64);
+        assert_eq!(field("nice").data_type(), &DataType::Int64);
+    }
+
+    #[test]
+    fn test_every_column_has_a_type() {
+        // Every registry column maps to a field under its key
+        let all = columns::selected_or_all(None);
+        let schema = schema(&all);
+        for column in all {
+            assert!(schema.field_with_name(column.key).is_ok(), "{}", column.key);
+        }
+    }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let columns = columns(&["pid", "name", "cpu", "rss", "files"]);
+        let mut out = Vec::new();
+        let mut stream = ArrowStream::new(&mut out, Some(&columns)).unwrap();
+        stream
+            .write(&snapshot("2026-01-02T03:04:05+00:00"))
+            .unwrap();
+        stream
+            .write(&snapshot("2026-01-02T03:04:07+00:00"))
+            .unwrap();
+        stream.finish().unwrap();
+
+        let reader = StreamReader::try_new(out.as_slice(), None).unwrap();
+        let batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.len(), 2);
+        let batch = &batches[1];
+        assert_eq!(batch.num_rows(), 2);
+        let timestamps = batch
+            .column_by_name("timestamp")
+            .unwrap()
+            .as_primitive::<TimestampNanosecondType>();
+        assert_eq!(timestamps.value(0), 1_767_323_047_000_000_000);
+        let pids = batch
+            .column_by_name("pid")
+            .unwrap()
+            .as_primitive::<UInt64Type>();
+        assert_eq!((pids.value(0), pids.value(1)), (42, 7));
+        assert_eq!(
+            batch
+                .column_by_name("name")
+                .unwrap()
+                .as_string::<i32>()
+                .value(1),
+            "db"
+        );
+        let cpu = batch
+            .column_by_name("system_cpu_usage")
+            .unwrap()
+            .as_primitive::<Float32Type>();
+        assert_eq!(cpu.value(1), 30.0);
+        let files = batch.column_by_name("open_files").unwrap();
+        assert!(files.is_null(0));
+        assert_eq!(files.as_primitive::<UInt64Type>().value(1), 12);
+        let io = batch
+            .column_by_name("system_pressure_io_some_avg10")
+            .unwrap()
+            .as_primitive::<Float32Type>();
+        assert_eq!(io.value(0), 55.0);
+        assert!(
+            batch
+                .column_by_name("system_pressure_cpu_some_avg10")
+                .unwrap()
+                .is_null(0)
+        );
+    }
+
+    #[test]
+    fn test_parquet_conversion() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let dir = std::env::temp_dir().join(format!("stop-parquet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let columns = columns(&["pid", "name", "cpu"]);
+        let mut stream = ArrowStream::new(Vec::new(), Some(&columns)).unwrap();
+        stream
+            .write(&snapshot("2026-01-02T03:04:05+00:00"))
+            .unwrap();
+        stream
+            .write(&snapshot("2026-01-02T03:04:07+00:00"))
+            .unwrap();
+        // A killed watch session: no end-of-stream marker, last batch cut off
+        let mut recorded = stream.writer.into_inner().unwrap();
+        recorded.truncate(recorded.len() - 10);
+        let input = dir.join("session.arrows");
+        std::fs::write(&input, recorded).unwrap();
+
+        let out = dir.join("session.parquet");
+        parquet_mode(&ParquetArgs {
+            input,
+            out: out.clone(),
+        })
+        .unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&out).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(Result::unwrap).collect();
+        let rows: usize = batches.iter().map(RecordBatch::num_rows).sum();
+        assert_eq!(rows, 2);
+        assert!(batches[0].schema().field_with_name("cpu_percent").is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_empty_snapshot() {
+        let mut empty = snapshot("2026-01-02T03:04:05+00:00");
+        empty.processes.clear();
+        let columns = columns(&["pid"]);
+        let schema = Arc::new(schema(&columns));
+        let batch = record_batch(&schema, &empty, &columns).unwrap();
+        assert_eq!(batch.num_rows(), 0);
+    }
+}
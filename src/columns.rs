@@ -0,0 +1,1160 @@
+use crate::{
+    ProcessInfo, SnapshotMeta, SystemMetrics, SystemSnapshot, escape_csv_field, format_bytes_parts,
+};
+use owo_colors::OwoColorize;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Optional details a column needs collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detail {
+    Smaps,
+    Sched,
+    Namespaces,
+    FdTypes,
+    Counters,
+    Cgroup,
+}
+
+/// Table alignment of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Percentages above `warn` are shown as warnings (yellow), above `high` as high (red).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    pub warn: f32,
+    pub high: f32,
+}
+
+/// How a percentage compares to its thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Normal,
+    Warn,
+    High,
+}
+
+impl Thresholds {
+    pub fn level(self, value: f32) -> Level {
+        if value > self.high {
+            Level::High
+        } else if value > self.warn {
+            Level::Warn
+        } else {
+            Level::Normal
+        }
+    }
+}
+
+/// Process CPU usage (percent of one core).
+pub const PROCESS_CPU: Thresholds = Thresholds {
+    warn: 20.0,
+    high: 50.0,
+};
+
+/// Process memory usage (percent of system memory).
+pub const PROCESS_MEMORY: Thresholds = Thresholds {
+    warn: 2.0,
+    high: 5.0,
+};
+
+/// System CPU usage and pressure stalls.
+pub const SYSTEM_CPU: Thresholds = Thresholds {
+    warn: 50.0,
+    high: 80.0,
+};
+
+/// System memory usage.
+pub const SYSTEM_MEMORY: Thresholds = Thresholds {
+    warn: 60.0,
+    high: 80.0,
+};
+
+/// How a column is rendered in the table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Plain,
+    /// Highlighted identifier (PID)
+    Id,
+    /// De-emphasized text (user)
+    Dimmed,
+    /// Percentage colored by its thresholds
+    Percent(Thresholds),
+    /// Byte count with a dimmed unit (8 characters wide)
+    Bytes,
+    /// CPU seconds with two decimals
+    Seconds,
+    /// Scheduling policy, highlighted (red) when real-time
+    Policy,
+}
+
+/// Value of one column for one process.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValue<'a> {
+    Unsigned(Option<u64>),
+    Signed(Option<i64>),
+    Percent(f32),
+    Float(Option<f64>),
+    Text(Option<Cow<'a, str>>),
+}
+
+impl ColumnValue<'_> {
+    /// Numeric value, or `None` for text and unavailable values.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Unsigned(v) => v.map(|n| n as f64),
+            Self::Signed(v) => v.map(|n| n as f64),
+            Self::Percent(v) => Some(f64::from(*v)),
+            Self::Float(v) => *v,
+            Self::Text(_) => None,
+        }
+    }
+
+    /// Plain value for CSV (empty if unavailable).
+    fn csv(&self) -> String {
+        match self {
+            Self::Unsigned(v) => v.map(|n| n.to_string()).unwrap_or_default(),
+            Self::Signed(v) => v.map(|n| n.to_string()).unwrap_or_default(),
+            Self::Percent(v) => v.to_string(),
+            Self::Float(v) => v.map(|n| n.to_string()).unwrap_or_default(),
+            Self::Text(v) => escape_csv_field(v.as_deref().unwrap_or_default()).into_owned(),
+        }
+    }
+}
+
+impl Serialize for ColumnValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unsigned(v) => v.serialize(serializer),
+            Self::Signed(v) => v.serialize(serializer),
+            Self::Percent(v) => serializer.serialize_f32(*v),
+            Self::Float(v) => v.serialize(serializer),
+            Self::Text(v) => v.serialize(serializer),
+        }
+    }
+}
+
+/// A process column selectable with `--columns`.
+#[derive(Debug)]
+pub struct Column {
+    /// Name used with `--columns`
+    pub name: &'static str,
+    /// JSON key and CSV header (the `ProcessInfo` field name where one exists)
+    pub key: &'static str,
+    /// Table header
+    pub title: &'static str,
+    /// Table width in characters
+    pub width: usize,
+    pub align: Align,
+    pub format: Format,
+    /// Details that must be collected for the column to have values
+    pub detail: Option<Detail>,
+    pub value: for<'a> fn(&'a ProcessInfo) -> ColumnValue<'a>,
+}
+
+fn text(value: &str) -> ColumnValue<'_> {
+    ColumnValue::Text(Some(Cow::Borrowed(value)))
+}
+
+fn optional_text(value: Option<&str>) -> ColumnValue<'_> {
+    ColumnValue::Text(value.map(Cow::Borrowed))
+}
+
+macro_rules! column {
+    ($name:literal, $key:literal, $title:literal, $width:literal, $align:ident, $format:expr, $detail:expr, $value:expr) => {
+        Column {
+            name: $name,
+            key: $key,
+            title: $title,
+            width: $width,
+            align: Align::$align,
+            format: $format,
+            detail: $detail,
+            value: $value,
+        }
+    };
+}
+
+/// All columns, in the order listed in `--help`.
+pub static COLUMNS: &[Column] = &[
+    column!("pid", "pid", "PID", 8, Left, Format::Id, None, |p| {
+        ColumnValue::Unsigned(Some(u64::from(p.pid)))
+    }),
+    column!("name", "name", "Name", 20, Left, Format::Plain, None, |p| {
+        text(&p.name)
+    }),
+    column!(
+        "cpu",
+        "cpu_percent",
+        "CPU%",
+        8,
+        Right,
+        Format::Percent(PROCESS_CPU),
+        None,
+        |p| ColumnValue::Percent(p.cpu_percent)
+    ),
+    column!(
+        "mem",
+        "memory_percent",
+        "Mem%",
+        8,
+        Right,
+        Format::Percent(PROCESS_MEMORY),
+        None,
+        |p| ColumnValue::Percent(p.memory_percent)
+    ),
+    column!(
+        "rss",
+        "memory_bytes",
+        "RSS",
+        8,
+        Right,
+        Format::Bytes,
+        None,
+        |p| { ColumnValue::Unsigned(Some(p.memory_bytes)) }
+    ),
+    column!(
+        "virt",
+        "virtual_bytes",
+        "Virt",
+        8,
+        Right,
+        Format::Bytes,
+        None,
+        |p| { ColumnValue::Unsigned(Some(p.virtual_bytes)) }
+    ),
+    column!(
+        "shared",
+        "shared_bytes",
+        "Shared",
+        8,
+        Right,
+        Format::Bytes,
+        Some(Detail::Counters),
+        |p| { ColumnValue::Unsigned(p.shared_bytes) }
+    ),
+    column!(
+        "pss",
+        "pss_bytes",
+        "PSS",
+        8,
+        Right,
+        Format::Bytes,
+        Some(Detail::Smaps),
+        |p| ColumnValue::Unsigned(p.pss_bytes)
+    ),
+    column!(
+        "uss",
+        "uss_bytes",
+        "USS",
+        8,
+        Right,
+        Format::Bytes,
+        Some(Detail::Smaps),
+        |p| ColumnValue::Unsigned(p.uss_bytes)
+    ),
+    column!(
+        "threads",
+        "thread_count",
+        "Threads",
+        7,
+        Right,
+        Format::Plain,
+        None,
+        |p| { ColumnValue::Unsigned(Some(p.thread_count as u64)) }
+    ),
+    column!(
+        "user",
+        "user",
+        "User",
+        10,
+        Left,
+        Format::Dimmed,
+        None,
+        |p| { text(&p.user) }
+    ),
+    column!(
+        "command",
+        "command",
+        "Command",
+        40,
+        Left,
+        Format::Plain,
+        None,
+        |p| { text(&p.command) }
+    ),
+    column!(
+        "read",
+        "disk_read_bytes",
+        "Read",
+        8,
+        Right,
+        Format::Bytes,
+        None,
+        |p| { ColumnValue::Unsigned(Some(p.disk_read_bytes)) }
+    ),
+    column!(
+        "write",
+        "disk_write_bytes",
+        "Write",
+        8,
+        Right,
+        Format::Bytes,
+        None,
+        |p| { ColumnValue::Unsigned(Some(p.disk_write_bytes)) }
+    ),
+    column!(
+        "files",
+        "open_files",
+        "Files",
+        7,
+        Right,
+        Format::Plain,
+        None,
+        |p| { ColumnValue::Unsigned(p.open_files.map(|n| n as u64)) }
+    ),
+    column!(
+        "filetypes",
+        "file_types",
+        "File types",
+        24,
+        Left,
+        Format::Dimmed,
+        Some(Detail::FdTypes),
+        |p| ColumnValue::Text(
+            p.fd_types
+                .filter(|c| c.total() > 0)
+                .map(|c| Cow::Owned(c.to_string()))
+        )
+    ),
+    column!(
+        "utime",
+        "cpu_user_seconds",
+        "UTime",
+        10,
+        Right,
+        Format::Seconds,
+        Some(Detail::Counters),
+        |p| { ColumnValue::Float(p.cpu_user_seconds) }
+    ),
+    column!(
+        "stime",
+        "cpu_system_seconds",
+        "STime",
+        10,
+        Right,
+        Format::Seconds,
+        Some(Detail::Counters),
+        |p| { ColumnValue::Float(p.cpu_system_seconds) }
+    ),
+    column!(
+        "vcsw",
+        "voluntary_ctx_switches",
+        "VCSW",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Counters),
+        |p| { ColumnValue::Unsigned(p.voluntary_ctx_switches) }
+    ),
+    column!(
+        "ivcsw",
+        "involuntary_ctx_switches",
+        "IVCSW",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Counters),
+        |p| ColumnValue::Unsigned(p.involuntary_ctx_switches)
+    ),
+    column!(
+        "minflt",
+        "minor_faults",
+        "MinFlt",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Counters),
+        |p| { ColumnValue::Unsigned(p.minor_faults) }
+    ),
+    column!(
+        "majflt",
+        "major_faults",
+        "MajFlt",
+        8,
+        Right,
+        Format::Plain,
+        Some(Detail::Counters),
+        |p| { ColumnValue::Unsigned(p.major_faults) }
+    ),
+    column!(
+        "nice",
+        "nice",
+        "NI",
+        4,
+        Right,
+        Format::Plain,
+        Some(Detail::Sched),
+        |p| ColumnValue::Signed(p.sched.as_ref().map(|s| i64::from(s.nice)))
+    ),
+    column!(
+        "priority",
+        "priority",
+        "PRI",
+        4,
+        Right,
+        Format::Plain,
+        Some(Detail::Sched),
+        |p| ColumnValue::Signed(p.sched.as_ref().map(|s| i64::from(s.priority)))
+    ),
+    column!(
+        "policy",
+        "policy",
+        "Policy",
+        8,
+        Left,
+        Format::Policy,
+        Some(Detail::Sched),
+        |p| optional_text(p.sched.as_ref().map(|s| s.policy.name()))
+    ),
+    column!(
+        "cpus",
+        "cpus_allowed",
+        "CPUs",
+        10,
+        Left,
+        Format::Plain,
+        Some(Detail::Sched),
+        |p| optional_text(p.sched.as_ref().and_then(|s| s.cpus_allowed.as_deref()))
+    ),
+    column!(
+        "oom_score",
+        "oom_score",
+        "OOM",
+        5,
+        Right,
+        Format::Plain,
+        Some(Detail::Sched),
+        |p| ColumnValue::Signed(p.sched.as_ref().and_then(|s| s.oom_score).map(i64::from))
+    ),
+    column!(
+        "oom_score_adj",
+        "oom_score_adj",
+        "OOMAdj",
+        6,
+        Right,
+        Format::Plain,
+        Some(Detail::Sched),
+        |p| ColumnValue::Signed(
+            p.sched
+                .as_ref()
+                .and_then(|s| s.oom_score_adj)
+                .map(i64::from)
+        )
+    ),
+    column!(
+        "cgroup",
+        "cgroup",
+        "Cgroup",
+        40,
+        Left,
+        Format::Plain,
+        Some(Detail::Cgroup),
+        |p| { optional_text(p.cgroup.as_deref()) }
+    ),
+    column!(
+        "container",
+        "container",
+        "Container",
+        20,
+        Left,
+        Format::Plain,
+        Some(Detail::Cgroup),
+        |p| { ColumnValue::Text(p.container.as_ref().map(|c| Cow::Owned(c.short_name()))) }
+    ),
+    column!(
+        "unit",
+        "unit",
+        "Unit",
+        24,
+        Left,
+        Format::Plain,
+        Some(Detail::Cgroup),
+        |p| { optional_text(p.unit.as_deref()) }
+    ),
+    column!(
+        "cgroupns",
+        "cgroup_ns",
+        "CgroupNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.cgroup))
+    ),
+    column!(
+        "ipcns",
+        "ipc_ns",
+        "IpcNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.ipc))
+    ),
+    column!(
+        "mntns",
+        "mnt_ns",
+        "MntNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.mnt))
+    ),
+    column!(
+        "netns",
+        "net_ns",
+        "NetNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.net))
+    ),
+    column!(
+        "pidns",
+        "pid_ns",
+        "PidNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.pid))
+    ),
+    column!(
+        "timens",
+        "time_ns",
+        "TimeNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.time))
+    ),
+    column!(
+        "userns",
+        "user_ns",
+        "UserNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.user))
+    ),
+    column!(
+        "utsns",
+        "uts_ns",
+        "UtsNS",
+        10,
+        Right,
+        Format::Plain,
+        Some(Detail::Namespaces),
+        |p| ColumnValue::Unsigned(p.namespaces.and_then(|n| n.uts))
+    ),
+];
+
+/// Comma-separated names of all columns, for help and error messages.
+pub fn column_names() -> String {
+    COLUMNS
+        .iter()
+        .map(|c| c.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Looks up a column by name (case-insensitive); the value parser of `--columns`.
+///
+/// # Errors
+///
+/// Returns an error listing the available columns if the name is unknown.
+pub fn parse_column(name: &str) -> Result<&'static Column, String> {
+    let name = name.trim();
+    COLUMNS
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown column '{name}' (available: {})", column_names()))
+}
+
+/// Whether any of the columns needs `detail` collected.
+pub fn needs(columns: &[&Column], detail: Detail) -> bool {
+    columns.iter().any(|c| c.detail == Some(detail))
+}
+
+/// The selected columns, or all of them when `--columns` is not given.
+pub fn selected_or_all(columns: Option<&[&'static Column]>) -> Vec<&'static Column> {
+    columns.map_or_else(|| COLUMNS.iter().collect(), <[_]>::to_vec)
+}
+
+/// Columns of the table when `--columns` is not given.
+///
+/// PSS/USS and the scheduling columns only appear when some process has them
+/// collected; `verbose` shows threads, disk I/O and open files instead of the user.
+pub fn default_table(processes: &[ProcessInfo], verbose: bool) -> Vec<&'static Column> {
+    let mut names = vec!["pid", "name", "cpu", "mem"];
+    if processes.iter().any(|p| p.pss_bytes.is_some()) {
+        names.extend(["pss", "uss"]);
+    }
+    if processes.iter().any(|p| p.sched.is_some()) {
+        names.extend([
+            "nice",
+            "priority",
+            "policy",
+            "oom_score",
+            "oom_score_adj",
+            "cpus",
+        ]);
+    }
+    if verbose {
+        names.extend(["threads", "read", "write", "files", "filetypes"]);
+    } else {
+        names.push("user");
+    }
+    names.into_iter().map(registered).collect()
+}
+
+/// The registry column `name`, for the built-in default layouts.
+fn registered(name: &str) -> &'static Column {
+    COLUMNS
+        .iter()
+        .find(|c| c.name == name)
+        .expect("default columns are in the registry")
+}
+
+/// A scalar system-wide value.
+#[derive(Debug)]
+pub struct SystemField {
+    /// Key in the JSON `system` object
+    pub key: &'static str,
+    pub value: fn(&SystemMetrics) -> ColumnValue<'static>,
+}
+
+/// Scalar system-wide values, in JSON order.
+pub static SYSTEM_FIELDS: &[SystemField] = &[
+    SystemField {
+        key: "cpu_usage",
+        value: |s| ColumnValue::Percent(s.cpu_usage),
+    },
+    SystemField {
+        key: "memory_total",
+        value: |s| ColumnValue::Unsigned(Some(s.memory_total)),
+    },
+    SystemField {
+        key: "memory_used",
+        value: |s| ColumnValue::Unsigned(Some(s.memory_used)),
+    },
+    SystemField {
+        key: "memory_percent",
+        value: |s| ColumnValue::Percent(s.memory_percent),
+    },
+    SystemField {
+        key: "memory_available",
+        value: |s| ColumnValue::Unsigned(s.memory_available),
+    },
+    SystemField {
+        key: "cached",
+        value: |s| ColumnValue::Unsigned(s.cached),
+    },
+    SystemField {
+        key: "buffers",
+        value: |s| ColumnValue::Unsigned(s.buffers),
+    },
+    SystemField {
+        key: "dirty",
+        value: |s| ColumnValue::Unsigned(s.dirty),
+    },
+    SystemField {
+        key: "shmem",
+        value: |s| ColumnValue::Unsigned(s.shmem),
+    },
+];
+
+/// System-wide values keyed like the JSON `system` object, with pressure and
+/// cgroup limits flattened (e.g. `pressure_io_some_avg10`, `limit_memory_bytes`).
+pub fn system_values(system: &SystemMetrics) -> Vec<(Cow<'static, str>, ColumnValue<'_>)> {
+    let mut values: Vec<(Cow<'static, str>, ColumnValue)> = SYSTEM_FIELDS
+        .iter()
+        .map(|field| (Cow::Borrowed(field.key), (field.value)(system)))
+        .collect();
+    if let Some(pressure) = &system.pressure {
+        for (resource, stall) in [
+            ("cpu", pressure.cpu),
+            ("memory", pressure.memory),
+            ("io", pressure.io),
+        ] {
+            let Some(stall) = stall else { continue };
+            for (kind, averages) in [("some", Some(stall.some)), ("full", stall.full)] {
+                let Some(averages) = averages else { continue };
+                for (window, value) in [
+                    ("avg10", averages.avg10),
+                    ("avg60", averages.avg60),
+                    ("avg300", averages.avg300),
+                ] {
+                    values.push((
+                        format!("pressure_{resource}_{kind}_{window}").into(),
+                        ColumnValue::Percent(value),
+                    ));
+                }
+            }
+        }
+    }
+    if let Some(limits) = &system.limits {
+        values.push((
+            "limit_memory_bytes".into(),
+            ColumnValue::Unsigned(limits.memory_limit_bytes),
+        ));
+        values.push((
+            "limit_cpu_cores".into(),
+            ColumnValue::Float(limits.cpu_limit_cores),
+        ));
+    }
+    values
+}
+
+/// Formats a cell like the table does, but without padding, truncation or colors.
+pub fn plain_cell(column: &Column, process: &ProcessInfo) -> String {
+    match ((column.value)(process), column.format) {
+        (ColumnValue::Percent(v), _) => format!("{v:.1}%"),
+        (ColumnValue::Unsigned(Some(bytes)), Format::Bytes) => {
+            let (value, unit) = format_bytes_parts(bytes);
+            format!("{value} {unit}")
+        }
+        (ColumnValue::Float(Some(seconds)), Format::Seconds) => format!("{seconds:.2}"),
+        (ColumnValue::Text(Some(text)), _) if !text.is_empty() => text.into_owned(),
+        (value, _) => match value.csv() {
+            s if s.is_empty() => "-".to_string(),
+            s => s,
+        },
+    }
+}
+
+/// Formats one table cell, padded to the column width unless it is the last column.
+fn table_cell(column: &Column, process: &ProcessInfo, last: bool) -> String {
+    let value = (column.value)(process);
+    let width = column.width;
+    let pad = |s: &str| -> String {
+        if last && column.align == Align::Left {
+            s.to_string()
+        } else {
+            match column.align {
+                Align::Left => format!("{s:<width$}"),
+                Align::Right => format!("{s:>width$}"),
+            }
+        }
+    };
+    // Text is cut to the column width, except in the last column
+    let truncate = |s: &str| -> String {
+        if last {
+            s.to_string()
+        } else {
+            s.chars().take(width).collect()
+        }
+    };
+
+    match (value, column.format) {
+        (ColumnValue::Percent(v), Format::Percent(thresholds)) => {
+            let cell = pad(&format!("{v:.1}%"));
+            match thresholds.level(v) {
+                Level::High => cell.red().to_string(),
+                Level::Warn => cell.yellow().to_string(),
+                Level::Normal => cell,
+            }
+        }
+        (ColumnValue::Unsigned(Some(bytes)), Format::Bytes) => {
+            let (value, unit) = format_bytes_parts(bytes);
+            format!(
+                "{:>w$} {}",
+                value,
+                unit.dimmed(),
+                w = width.saturating_sub(2)
+            )
+        }
+        (ColumnValue::Float(Some(seconds)), Format::Seconds) => pad(&format!("{seconds:.2}")),
+        (value, format) => {
+            let s = match value {
+                ColumnValue::Text(Some(s)) => truncate(&s),
+                ColumnValue::Percent(v) => format!("{v:.1}"),
+                other => match other.csv() {
+                    s if s.is_empty() => "-".to_string(),
+                    s => s,
+                },
+            };
+            let cell = pad(&s);
+            match format {
+                Format::Id => cell.cyan().to_string(),
+                Format::Dimmed => cell.dimmed().to_string(),
+                Format::Policy
+                    if process
+                        .sched
+                        .as_ref()
+                        .is_some_and(|s| s.policy.is_realtime()) =>
+                {
+                    cell.red().to_string()
+                }
+                _ => cell,
+            }
+        }
+    }
+}
+
+/// Writes the table header, rule and one row per process.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_table(
+    out: &mut impl Write,
+    processes: &[ProcessInfo],
+    columns: &[&Column],
+) -> io::Result<()> {
+    let last = columns.len().saturating_sub(1);
+    let header: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let title = match c.align {
+                _ if i == last && c.align == Align::Left => c.title.to_string(),
+                Align::Left => format!("{:<w$}", c.title, w = c.width),
+                Align::Right => format!("{:>w$}", c.title, w = c.width),
+            };
+            title.bold().to_string()
+        })
+        .collect();
+    writeln!(out, "{}", header.join(" "))?;
+    let rule_width: usize = columns.iter().map(|c| c.width + 1).sum();
+    writeln!(out, "{}", "─".repeat(rule_width.saturating_sub(1)).dimmed())?;
+
+    for process in processes {
+        let cells: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| table_cell(c, process, i == last))
+            .collect();
+        writeln!(out, "{}", cells.join(" "))?;
+    }
+    Ok(())
+}
+
+/// Where a CSV field takes its value from.
+#[derive(Debug, Clone, Copy)]
+pub enum CsvSource {
+    /// A system-wide value, repeated on every row
+    System(&'static SystemField),
+    Process(&'static Column),
+}
+
+/// One CSV field and its header.
+#[derive(Debug, Clone, Copy)]
+pub struct CsvField {
+    pub header: &'static str,
+    pub source: CsvSource,
+}
+
+impl CsvField {
+    /// A process column under its registry key, as selected with `--columns`.
+    pub fn column(column: &'static Column) -> Self {
+        Self {
+            header: column.key,
+            source: CsvSource::Process(column),
+        }
+    }
+
+    fn system(field: &'static SystemField) -> Self {
+        Self {
+            header: field.key,
+            source: CsvSource::System(field),
+        }
+    }
+
+    fn csv(&self, snapshot: &SystemSnapshot, process: &ProcessInfo) -> String {
+        match self.source {
+            CsvSource::System(field) => (field.value)(&snapshot.system).csv(),
+            CsvSource::Process(column) => (column.value)(process).csv(),
+        }
+    }
+}
+
+/// System fields of the original CSV layout.
+const BASELINE_CSV_SYSTEM: &[&str] =
+    &["cpu_usage", "memory_total", "memory_used", "memory_percent"];
+
+/// Process columns of the original CSV layout, with the header where it
+/// differs from the registry key.
+const BASELINE_CSV_COLUMNS: &[(&str, Option<&str>)] = &[
+    ("pid", None),
+    ("name", None),
+    ("cpu", None),
+    ("rss", None),
+    ("mem", Some("memory_percent_process")),
+    ("user", None),
+    ("command", None),
+    ("threads", None),
+    ("read", None),
+    ("write", None),
+    ("files", None),
+];
+
+/// Fields of the CSV when `--columns` is not given: the original 16 columns
+/// under their original names and order, then every system field and
+/// column added since.
+pub fn default_csv() -> Vec<CsvField> {
+    let baseline_system = SYSTEM_FIELDS
+        .iter()
+        .filter(|f| BASELINE_CSV_SYSTEM.contains(&f.key));
+    let baseline_columns = BASELINE_CSV_COLUMNS.iter().map(|&(name, header)| {
+        let column = registered(name);
+        CsvField {
+            header: header.unwrap_or(column.key),
+            source: CsvSource::Process(column),
+        }
+    });
+    let later_system = SYSTEM_FIELDS
+        .iter()
+        .filter(|f| !BASELINE_CSV_SYSTEM.contains(&f.key));
+    let later_columns = COLUMNS
+        .iter()
+        .filter(|c| !BASELINE_CSV_COLUMNS.iter().any(|&(name, _)| name == c.name));
+    baseline_system
+        .map(CsvField::system)
+        .chain(baseline_columns)
+        .chain(later_system.map(CsvField::system))
+        .chain(later_columns.map(CsvField::column))
+        .collect()
+}
+
+/// Writes the CSV header: `timestamp` followed by the field headers.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_csv_header(out: &mut impl Write, fields: &[CsvField]) -> io::Result<()> {
+    let headers: Vec<&str> = fields.iter().map(|f| f.header).collect();
+    writeln!(out, "timestamp,{}", headers.join(","))
+}
+
+/// Writes one CSV row per process.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_csv_rows(
+    out: &mut impl Write,
+    snapshot: &SystemSnapshot,
+    fields: &[CsvField],
+) -> io::Result<()> {
+    let timestamp = escape_csv_field(&snapshot.timestamp);
+    for process in &snapshot.processes {
+        let cells: Vec<String> = fields.iter().map(|f| f.csv(snapshot, process)).collect();
+        writeln!(out, "{},{}", timestamp, cells.join(","))?;
+    }
+    Ok(())
+}
+
+/// A process serialized with only the selected columns.
+pub struct SelectedProcess<'a> {
+    pub process: &'a ProcessInfo,
+    pub columns: &'a [&'static Column],
+}
+
+impl Serialize for SelectedProcess<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for column in self.columns {
+            map.serialize_entry(column.key, &(column.value)(self.process))?;
+        }
+        map.end()
+    }
+}
+
+/// A snapshot whose processes only carry the selected columns.
+#[derive(serde::Serialize)]
+pub struct SelectedSnapshot<'a> {
+    schema_version: u32,
+    meta: &'a SnapshotMeta,
+    timestamp: &'a str,
+    system: &'a SystemMetrics,
+    processes: Vec<SelectedProcess<'a>>,
+}
+
+impl<'a> SelectedSnapshot<'a> {
+    pub fn new(snapshot: &'a SystemSnapshot, columns: &'a [&'static Column]) -> Self {
+        Self {
+            schema_version: snapshot.schema_version,
+            meta: &snapshot.meta,
+            timestamp: &snapshot.timestamp,
+            system: &snapshot.system,
+            processes: snapshot
+                .processes
+                .iter()
+                .map(|process| SelectedProcess { process, columns })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(names: &[&str]) -> Vec<&'static Column> {
+        names.iter().map(|n| parse_column(n).unwrap()).collect()
+    }
+
+    fn process() -> ProcessInfo {
+        serde_json::from_value(serde_json::json!({
+            "pid": 42,
+            "name": "web, server",
+            "cpu_percent": 12.5,
+            "memory_bytes": 1048576,
+            "memory_percent": 0.4,
+            "virtual_bytes": 4194304,
+            "shared_bytes": null,
+            "pss_bytes": null,
+            "uss_bytes": null,
+            "user": "1000",
+            "command": "web --port 80",
+            "thread_count": 4,
+            "disk_read_bytes": 0,
+            "disk_write_bytes": 0,
+            "open_files": null,
+            "cpu_user_seconds": 1.5,
+            "cpu_system_seconds": null,
+            "voluntary_ctx_switches": null,
+            "involuntary_ctx_switches": null,
+            "minor_faults": null,
+            "major_faults": null,
+            "cgroup": null,
+            "container": null,
+            "unit": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_column() {
+        assert_eq!(parse_column("RSS").unwrap().key, "memory_bytes");
+        assert_eq!(parse_column(" cpu ").unwrap().key, "cpu_percent");
+        let error = parse_column("bogus").unwrap_err();
+        assert!(error.contains("unknown column 'bogus'"));
+        assert!(error.contains("pid, name, cpu"));
+    }
+
+    #[test]
+    fn test_column_names_are_unique() {
+        for (i, column) in COLUMNS.iter().enumerate() {
+            assert!(
+                COLUMNS[i + 1..]
+                    .iter()
+                    .all(|c| c.name != column.name && c.key != column.key),
+                "Duplicate column {}",
+                column.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_values() {
+        let process = process();
+        let cells: Vec<String> = columns(&["pid", "name", "cpu", "rss", "files", "utime"])
+            .iter()
+            .map(|c| (c.value)(&process).csv())
+            .collect();
+        assert_eq!(
+            cells,
+            ["42", "\"web, server\"", "12.5", "1048576", "", "1.5"]
+        );
+    }
+
+    #[test]
+    fn test_default_csv_keeps_baseline_layout() {
+        let mut out = Vec::new();
+        write_csv_header(&mut out, &default_csv()).unwrap();
+        let header = String::from_utf8(out).unwrap();
+        assert!(header.starts_with(
+            "timestamp,cpu_usage,memory_total,memory_used,memory_percent,pid,name,cpu_percent,\
+             memory_bytes,memory_percent_process,user,command,thread_count,disk_read_bytes,\
+             disk_write_bytes,open_files,memory_available,"
+        ));
+        let headers: Vec<&str> = header.trim_end().split(',').collect();
+        assert_eq!(headers.len(), 1 + SYSTEM_FIELDS.len() + COLUMNS.len());
+        for (i, key) in headers.iter().enumerate() {
+            assert!(
+                !headers[i + 1..].contains(key),
+                "Duplicate CSV header {key}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_selected_process() {
+        let process = process();
+        let columns = columns(&["pid", "mem", "files", "user"]);
+        let json = serde_json::to_string(&SelectedProcess {
+            process: &process,
+            columns: &columns,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"pid":42,"memory_percent":0.4,"open_files":null,"user":"1000"}"#
+        );
+    }
+
+    #[test]
+    fn test_table_cells() {
+        let process = process();
+        let cell = |name: &str, last: bool| table_cell(parse_column(name).unwrap(), &process, last);
+        assert_eq!(cell("threads", false), "      4");
+        assert_eq!(cell("files", false), "      -");
+        assert_eq!(cell("command", true), "web --port 80");
+        assert_eq!(cell("utime", false), "      1.50");
+        assert_eq!(cell("name", false).len(), 20);
+    }
+
+    #[test]
+    fn test_default_table() {
+        let names = |processes: &[ProcessInfo], verbose| -> Vec<&str> {
+            default_table(processes, verbose)
+                .iter()
+                .map(|c| c.name)
+                .collect()
+        };
+        let mut process = process();
+        assert_eq!(
+            names(std::slice::from_ref(&process), false),
+            ["pid", "name", "cpu", "mem", "user"]
+        );
+        process.pss_bytes = Some(4096);
+        assert_eq!(
+            names(&[process], true),
+            [
+                "pid",
+                "name",
+                "cpu",
+                "mem",
+                "pss",
+                "uss",
+                "threads",
+                "read",
+                "write",
+                "files",
+                "filetypes"
+            ]
+        );
+    }
+}
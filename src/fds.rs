@@ -0,0 +1,402 @@
+use crate::{Args, PROC_ROOT, escape_csv_field, json, procfs};
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Kind of object an open file descriptor refers to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FdKind {
+    /// Regular file or directory on a filesystem
+    Regular,
+    /// Network or Unix domain socket
+    Socket,
+    /// Anonymous pipe or FIFO
+    Pipe,
+    /// Anonymous inode (eventfd, epoll, timerfd, signalfd, ...)
+    AnonInode,
+    /// Character or block device under /dev
+    Device,
+    /// Anything the kernel reports in an unrecognized form
+    Other,
+}
+
+impl FdKind {
+    /// Classifies a descriptor from its `/proc/<pid>/fd/<n>` link target.
+    ///
+    /// Sockets, pipes and anonymous inodes are reported by the kernel as
+    /// `socket:[inode]`, `pipe:[inode]` and `anon_inode:<name>`. Paths under
+    /// `/dev` are devices, except tmpfs mounts like `/dev/shm` which hold
+    /// ordinary files.
+    pub fn classify(target: &str) -> Self {
+        if target.starts_with("socket:") {
+            Self::Socket
+        } else if target.starts_with("pipe:") {
+            Self::Pipe
+        } else if target.starts_with("anon_inode:") {
+            Self::AnonInode
+        } else if target.starts_with("/dev/")
+            && !target.starts_with("/dev/shm/")
+            && !target.starts_with("/dev/mqueue/")
+        {
+            Self::Device
+        } else if target.starts_with('/') {
+            Self::Regular
+        } else {
+            Self::Other
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Regular => "regular",
+            Self::Socket => "socket",
+            Self::Pipe => "pipe",
+            Self::AnonInode => "anon_inode",
+            Self::Device => "device",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// A single open file descriptor of a process.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenFile {
+    /// Descriptor number
+    pub fd: u32,
+    /// Descriptor type
+    #[serde(rename = "type")]
+    pub kind: FdKind,
+    /// Resolved link target (path, or e.g. `socket:[12345]`)
+    pub path: String,
+}
+
+/// Number of open file descriptors per type.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FdTypeCounts {
+    pub regular: usize,
+    pub socket: usize,
+    pub pipe: usize,
+    pub anon_inode: usize,
+    pub device: usize,
+    pub other: usize,
+}
+
+impl FdTypeCounts {
+    /// Tallies descriptors by kind.
+    pub fn from_files(files: &[OpenFile]) -> Self {
+        let mut counts = Self::default();
+        for file in files {
+            counts.add(file.kind);
+        }
+        counts
+    }
+
+    fn add(&mut self, kind: FdKind) {
+        match kind {
+            FdKind::Regular => self.regular += 1,
+            FdKind::Socket => self.socket += 1,
+            FdKind::Pipe => self.pipe += 1,
+            FdKind::AnonInode => self.anon_inode += 1,
+            FdKind::Device => self.device += 1,
+            FdKind::Other => self.other += 1,
+        }
+    }
+
+    /// Total number of descriptors counted.
+    pub fn total(&self) -> usize {
+        self.regular + self.socket + self.pipe + self.anon_inode + self.device + self.other
+    }
+}
+
+/// Compact summary listing only non-zero kinds, e.g. `reg:8 sock:3 pipe:1`.
+impl fmt::Display for FdTypeCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            ("reg", self.regular),
+            ("sock", self.socket),
+            ("pipe", self.pipe),
+            ("anon", self.anon_inode),
+            ("dev", self.device),
+            ("other", self.other),
+        ];
+        let mut first = true;
+        for (label, count) in parts.iter().filter(|(_, n)| *n > 0) {
+            if !first {
+                write!(f, " ")?;
+            }
+            write!(f, "{label}:{count}")?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Lists the open file descriptors of a process from `<proc_root>/<pid>/fd`.
+///
+/// Descriptors that close between listing the directory and resolving the
+/// link are skipped. Results are sorted by descriptor number.
+///
+/// # Errors
+///
+/// Returns error if the fd directory cannot be read (process gone, or
+/// insufficient permissions).
+pub fn read_open_files(proc_root: &Path, pid: u32) -> io::Result<Vec<OpenFile>> {
+    let fd_dir = proc_root.join(pid.to_string()).join("fd");
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(&fd_dir)? {
+        let entry = entry?;
+        let Some(fd) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        let target = match fs::read_link(entry.path()) {
+            Ok(target) => target,
+            // Descriptor closed while we were listing
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let path = target.to_string_lossy().into_owned();
+        files.push(OpenFile {
+            fd,
+            kind: FdKind::classify(&path),
+            path,
+        });
+    }
+
+    files.sort_by_key(|f| f.fd);
+    Ok(files)
+}
+
+/// Counts open file descriptors by type, or `None` if they cannot be read.
+pub fn count_fd_types(proc_root: &Path, pid: u32) -> Option<FdTypeCounts> {
+    read_open_files(proc_root, pid)
+        .ok()
+        .map(|files| FdTypeCounts::from_files(&files))
+}
+
+/// Detailed open file listing for a single process.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OpenFilesReport {
+    /// ISO 8601 timestamp (RFC3339)
+    pub timestamp: String,
+    /// Process ID
+    pub pid: u32,
+    /// Process name
+    pub name: String,
+    /// Descriptor counts per type
+    pub counts: FdTypeCounts,
+    /// Every open descriptor, sorted by number
+    pub files: Vec<OpenFile>,
+}
+
+/// Reads the process name from `comm`, or from `stat` in captures, which leave out `comm`.
+fn read_process_name(proc_root: &Path, pid: u32) -> String {
+    fs::read_to_string(proc_root.join(pid.to_string()).join("comm"))
+        .map(|s| s.trim_end().to_string())
+        .ok()
+        .or_else(|| {
+            let stat = procfs::read_pid_file(proc_root, pid, "stat").ok()?;
+            procfs::parse_stat_comm(&stat).map(str::to_string)
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Runs the `--files <PID>` detail mode, listing every descriptor of one process.
+///
+/// # Errors
+///
+/// Returns error if output fails. Collection errors are reported to the user
+/// (as JSON in `--json` mode) and exit with status 1.
+pub fn files_mode(args: &Args, pid: u32) -> Result<(), Box<dyn Error>> {
+    let proc_root = args.proc_root.as_deref().unwrap_or(Path::new(PROC_ROOT));
+
+    let files = match read_open_files(proc_root, pid) {
+        Ok(files) => files,
+        Err(e) => {
+            let message = match e.kind() {
+                io::ErrorKind::NotFound if cfg!(target_os = "linux") => {
+                    format!("Process {pid} not found")
+                }
+                io::ErrorKind::NotFound => {
+                    "Open file listing is only supported on Linux".to_string()
+                }
+                io::ErrorKind::PermissionDenied => {
+                    format!("Permission denied reading open files of process {pid}")
+                }
+                _ => format!("Failed to read open files of process {pid}: {e}"),
+            };
+            if args.json {
+                let error_json = serde_json::json!({
+                    "error": "FilesError",
+                    "message": message,
+                    "pid": pid,
+                });
+                let _ = writeln!(
+                    io::stdout(),
+                    "{}",
+                    serde_json::to_string_pretty(&error_json)?
+                );
+            } else {
+                eprintln!("Error: {message}");
+            }
+            std::process::exit(1);
+        }
+    };
+
+    let report = OpenFilesReport {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        pid,
+        name: read_process_name(proc_root, pid),
+        counts: FdTypeCounts::from_files(&files),
+        files,
+    };
+
+    let result = if args.json {
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        json::write_value(&mut out, &report, args.json_compact).and_then(|_| out.flush())
+    } else if args.csv {
+        output_files_csv(&report)
+    } else {
+        output_files_human(&report)
+    };
+
+    if let Err(e) = result {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            return Ok(());
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn output_files_csv(report: &OpenFilesReport) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    writeln!(stdout, "timestamp,pid,name,fd,type,path")?;
+    for file in &report.files {
+        writeln!(
+            stdout,
+            "{},{},{},{},{},{}",
+            escape_csv_field(&report.timestamp),
+            report.pid,
+            escape_csv_field(&report.name),
+            file.fd,
+            file.kind.name(),
+            escape_csv_field(&file.path)
+        )?;
+    }
+    stdout.flush()
+}
+
+fn output_files_human(report: &OpenFilesReport) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    writeln!(
+        stdout,
+        "{} {}",
+        "stop".bold().cyan(),
+        format!("v{}", env!("CARGO_PKG_VERSION")).dimmed()
+    )?;
+    writeln!(stdout)?;
+    writeln!(
+        stdout,
+        "{} {} ({})",
+        "Process:".bold(),
+        report.pid.to_string().cyan(),
+        report.name
+    )?;
+    writeln!(
+        stdout,
+        "{} {} {}",
+        "Open files:".bold(),
+        report.counts.total().to_string().green(),
+        report.counts.to_string().dimmed()
+    )?;
+    writeln!(stdout)?;
+
+    writeln!(
+        stdout,
+        "{:>6} {:<11} {}",
+        "FD".bold(),
+        "Type".bold(),
+        "Path".bold()
+    )?;
+    writeln!(stdout, "{}", "─".repeat(70).dimmed())?;
+
+    for file in &report.files {
+        let kind = format!("{:<11}", file.kind.name());
+        let kind_display = match file.kind {
+            FdKind::Socket => kind.magenta().to_string(),
+            FdKind::Pipe | FdKind::AnonInode => kind.yellow().to_string(),
+            FdKind::Device => kind.dimmed().to_string(),
+            FdKind::Regular | FdKind::Other => kind,
+        };
+        writeln!(
+            stdout,
+            "{:>6} {} {}",
+            file.fd.to_string().cyan(),
+            kind_display,
+            file.path
+        )?;
+    }
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_special_targets() {
+        assert_eq!(FdKind::classify("socket:[123456]"), FdKind::Socket);
+        assert_eq!(FdKind::classify("pipe:[98765]"), FdKind::Pipe);
+        assert_eq!(FdKind::classify("anon_inode:[eventfd]"), FdKind::AnonInode);
+        assert_eq!(FdKind::classify("anon_inode:inotify"), FdKind::AnonInode);
+    }
+
+    #[test]
+    fn test_classify_paths() {
+        assert_eq!(FdKind::classify("/dev/null"), FdKind::Device);
+        assert_eq!(FdKind::classify("/dev/pts/0"), FdKind::Device);
+        assert_eq!(FdKind::classify("/dev/shm/pulse-shm-1"), FdKind::Regular);
+        assert_eq!(FdKind::classify("/var/log/syslog"), FdKind::Regular);
+        assert_eq!(
+            FdKind::classify("/tmp/deleted.log (deleted)"),
+            FdKind::Regular
+        );
+        assert_eq!(FdKind::classify("net:[4026531840]"), FdKind::Other);
+    }
+
+    #[test]
+    fn test_counts_and_display() {
+        let files: Vec<OpenFile> = ["/dev/null", "socket:[1]", "socket:[2]", "/etc/hosts"]
+            .iter()
+            .enumerate()
+            .map(|(fd, path)| OpenFile {
+                fd: fd as u32,
+                kind: FdKind::classify(path),
+                path: path.to_string(),
+            })
+            .collect();
+
+        let counts = FdTypeCounts::from_files(&files);
+        assert_eq!(counts.socket, 2);
+        assert_eq!(counts.regular, 1);
+        assert_eq!(counts.device, 1);
+        assert_eq!(counts.total(), 4);
+        assert_eq!(counts.to_string(), "reg:1 sock:2 dev:1");
+        assert_eq!(FdTypeCounts::default().to_string(), "");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_own_open_files() {
+        let files = read_open_files(Path::new(PROC_ROOT), std::process::id()).unwrap();
+        assert!(!files.is_empty());
+        assert!(files.windows(2).all(|w| w[0].fd < w[1].fd));
+    }
+}
@@ -6,7 +6,9 @@ pub enum FilterError {
     #[error("Invalid filter expression: {0}")]
     InvalidExpression(String),
 
-    #[error("Unknown field '{0}'. Valid fields: cpu, mem, pid, name, user")]
+    #[error(
+        "Unknown field '{0}'. Valid fields: cpu, mem, pid, name, user, utime, stime, cputime, vcsw, ivcsw, minflt, majflt, nice, priority, oom_score, oom_score_adj, container, cgroup, unit, netns, pidns, mntns, userns, utsns, ipcns"
+    )]
     UnknownField(String),
 
     #[error("Unknown operator '{0}'. Valid operators: >, >=, <, <=, ==, !=")]
@@ -71,6 +73,46 @@ pub enum FilterField {
     Name,
     /// User ID or name (string, case-sensitive)
     User,
+    /// Cumulative user-mode CPU seconds (numeric)
+    Utime,
+    /// Cumulative kernel-mode CPU seconds (numeric)
+    Stime,
+    /// Cumulative total CPU seconds (numeric)
+    CpuTime,
+    /// Voluntary context switches (numeric)
+    Vcsw,
+    /// Involuntary context switches (numeric)
+    Ivcsw,
+    /// Minor page faults (numeric)
+    Minflt,
+    /// Major page faults (numeric)
+    Majflt,
+    /// Nice value (numeric, may be negative)
+    Nice,
+    /// Kernel scheduling priority (numeric, may be negative)
+    Priority,
+    /// OOM killer badness score (numeric)
+    OomScore,
+    /// OOM score adjustment (numeric, may be negative)
+    OomScoreAdj,
+    /// Container ID prefix or runtime name (string, case-insensitive)
+    Container,
+    /// Cgroup path (string, case-insensitive contains)
+    Cgroup,
+    /// Systemd unit (string, exact; `.service` may be omitted)
+    Unit,
+    /// Network namespace inode (numeric)
+    NetNs,
+    /// PID namespace inode (numeric)
+    PidNs,
+    /// Mount namespace inode (numeric)
+    MntNs,
+    /// User namespace inode (numeric)
+    UserNs,
+    /// UTS (hostname) namespace inode (numeric)
+    UtsNs,
+    /// IPC namespace inode (numeric)
+    IpcNs,
 }
 
 impl FilterField {
@@ -81,12 +123,123 @@ impl FilterField {
             "pid" => Ok(Self::Pid),
             "name" => Ok(Self::Name),
             "user" => Ok(Self::User),
+            "utime" => Ok(Self::Utime),
+            "stime" => Ok(Self::Stime),
+            "cputime" => Ok(Self::CpuTime),
+            "vcsw" => Ok(Self::Vcsw),
+            "ivcsw" => Ok(Self::Ivcsw),
+            "minflt" => Ok(Self::Minflt),
+            "majflt" => Ok(Self::Majflt),
+            "nice" => Ok(Self::Nice),
+            "priority" | "prio" => Ok(Self::Priority),
+            "oom_score" | "oom" => Ok(Self::OomScore),
+            "oom_score_adj" => Ok(Self::OomScoreAdj),
+            "container" => Ok(Self::Container),
+            "cgroup" => Ok(Self::Cgroup),
+            "unit" => Ok(Self::Unit),
+            "netns" => Ok(Self::NetNs),
+            "pidns" => Ok(Self::PidNs),
+            "mntns" => Ok(Self::MntNs),
+            "userns" => Ok(Self::UserNs),
+            "utsns" => Ok(Self::UtsNs),
+            "ipcns" => Ok(Self::IpcNs),
             _ => Err(FilterError::UnknownField(s.to_string())),
         }
     }
 
     fn is_numeric(&self) -> bool {
-        matches!(self, Self::Cpu | Self::Mem | Self::Pid)
+        !self.is_string()
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(
+            self,
+            Self::Name | Self::User | Self::Container | Self::Cgroup | Self::Unit
+        )
+    }
+
+    /// Whether the integer field can be negative.
+    fn is_signed(&self) -> bool {
+        matches!(self, Self::Nice | Self::Priority | Self::OomScoreAdj)
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(
+            self,
+            Self::Cpu | Self::Mem | Self::Utime | Self::Stime | Self::CpuTime
+        )
+    }
+
+    /// Whether the field is only available when scheduling details are collected.
+    pub fn is_sched(&self) -> bool {
+        matches!(
+            self,
+            Self::Nice | Self::Priority | Self::OomScore | Self::OomScoreAdj
+        )
+    }
+
+    /// Whether the field is only available when counters are collected.
+    pub fn is_counter(&self) -> bool {
+        matches!(
+            self,
+            Self::Utime
+                | Self::Stime
+                | Self::CpuTime
+                | Self::Vcsw
+                | Self::Ivcsw
+                | Self::Minflt
+                | Self::Majflt
+        )
+    }
+
+    /// Whether the field is only available when cgroup membership is collected.
+    pub fn is_cgroup(&self) -> bool {
+        matches!(self, Self::Container | Self::Cgroup | Self::Unit)
+    }
+
+    /// Whether the field is only available when namespaces are collected.
+    pub fn is_namespace(&self) -> bool {
+        matches!(
+            self,
+            Self::NetNs | Self::PidNs | Self::MntNs | Self::UserNs | Self::UtsNs | Self::IpcNs
+        )
+    }
+
+    /// Value of a floating-point field, or `None` if unavailable for this process.
+    fn float_value(&self, process: &crate::ProcessInfo) -> Option<f32> {
+        match self {
+            Self::Cpu => Some(process.cpu_percent),
+            Self::Mem => Some(process.memory_percent),
+            Self::Utime => process.cpu_user_seconds.map(|v| v as f32),
+            Self::Stime => process.cpu_system_seconds.map(|v| v as f32),
+            Self::CpuTime => process.cpu_total_seconds().map(|v| v as f32),
+            _ => None,
+        }
+    }
+
+    /// Value of an integer field, or `None` if unavailable for this process.
+    fn int_value(&self, process: &crate::ProcessInfo) -> Option<i64> {
+        let sched = process.sched.as_ref();
+        let ns = process.namespaces.as_ref();
+        let value = match self {
+            Self::Nice => return sched.map(|s| i64::from(s.nice)),
+            Self::Priority => return sched.map(|s| i64::from(s.priority)),
+            Self::OomScore => return sched.and_then(|s| s.oom_score).map(i64::from),
+            Self::OomScoreAdj => return sched.and_then(|s| s.oom_score_adj).map(i64::from),
+            Self::Pid => Some(u64::from(process.pid)),
+            Self::Vcsw => process.voluntary_ctx_switches,
+            Self::Ivcsw => process.involuntary_ctx_switches,
+            Self::Minflt => process.minor_faults,
+            Self::Majflt => process.major_faults,
+            Self::NetNs => ns.and_then(|n| n.net),
+            Self::PidNs => ns.and_then(|n| n.pid),
+            Self::MntNs => ns.and_then(|n| n.mnt),
+            Self::UserNs => ns.and_then(|n| n.user),
+            Self::UtsNs => ns.and_then(|n| n.uts),
+            Self::IpcNs => ns.and_then(|n| n.ipc),
+            _ => None,
+        }?;
+        i64::try_from(value).ok()
     }
 
     fn name(&self) -> &'static str {
@@ -96,6 +249,26 @@ impl FilterField {
             Self::Pid => "pid",
             Self::Name => "name",
             Self::User => "user",
+            Self::Utime => "utime",
+            Self::Stime => "stime",
+            Self::CpuTime => "cputime",
+            Self::Vcsw => "vcsw",
+            Self::Ivcsw => "ivcsw",
+            Self::Minflt => "minflt",
+            Self::Majflt => "majflt",
+            Self::Nice => "nice",
+            Self::Priority => "priority",
+            Self::OomScore => "oom_score",
+            Self::OomScoreAdj => "oom_score_adj",
+            Self::Container => "container",
+            Self::Cgroup => "cgroup",
+            Self::Unit => "unit",
+            Self::NetNs => "netns",
+            Self::PidNs => "pidns",
+            Self::MntNs => "mntns",
+            Self::UserNs => "userns",
+            Self::UtsNs => "utsns",
+            Self::IpcNs => "ipcns",
         }
     }
 }
@@ -105,10 +278,10 @@ impl FilterField {
 /// Stores both original and lowercase versions of strings for efficient matching.
 #[derive(Debug, Clone, PartialEq)]
 pub enum FilterValue {
-    /// Floating-point value (for cpu, mem fields)
+    /// Floating-point value (for cpu, mem and CPU time fields)
     Float(f32),
-    /// Integer value (for pid field)
-    Int(u32),
+    /// Integer value (for pid and counter fields)
+    Int(i64),
     /// String value with pre-computed lowercase for case-insensitive matching
     String { original: String, lowercase: String },
 }
@@ -217,6 +390,18 @@ impl FilterExpr {
         Filter::parse_simple(expr).map(FilterExpr::Simple)
     }
 
+    /// Returns true if any condition in the expression is on a field matching `pred`.
+    ///
+    /// Used to enable collection of optional details the filter depends on.
+    pub fn uses_field(&self, pred: fn(&FilterField) -> bool) -> bool {
+        match self {
+            FilterExpr::Simple(f) => pred(&f.field),
+            FilterExpr::And(l, r) | FilterExpr::Or(l, r) => {
+                l.uses_field(pred) || r.uses_field(pred)
+            }
+        }
+    }
+
     /// Tests whether a process matches this filter expression.
     ///
     /// # Arguments
@@ -291,23 +476,7 @@ impl Filter {
 
         // Parse value based on field type
         let value = match field {
-            FilterField::Cpu | FilterField::Mem => value_str
-                .parse::<f32>()
-                .map(FilterValue::Float)
-                .map_err(|_| FilterError::InvalidValue {
-                    field: field.name().to_string(),
-                    value: value_str.to_string(),
-                    reason: "Expected a number (e.g., 10 or 5.5)".to_string(),
-                })?,
-            FilterField::Pid => value_str
-                .parse::<u32>()
-                .map(FilterValue::Int)
-                .map_err(|_| FilterError::InvalidValue {
-                    field: field.name().to_string(),
-                    value: value_str.to_string(),
-                    reason: "Expected an integer (e.g., 1000)".to_string(),
-                })?,
-            FilterField::Name | FilterField::User => {
+            _ if field.is_string() => {
                 let original = value_str.to_string();
                 let lowercase = original.to_lowercase();
                 FilterValue::String {
@@ -315,6 +484,31 @@ impl Filter {
                     lowercase,
                 }
             }
+            _ if field.is_float() => {
+                value_str
+                    .parse::<f32>()
+                    .map(FilterValue::Float)
+                    .map_err(|_| FilterError::InvalidValue {
+                        field: field.name().to_string(),
+                        value: value_str.to_string(),
+                        reason: "Expected a number (e.g., 10 or 5.5)".to_string(),
+                    })?
+            }
+            _ => {
+                let invalid = |reason: &str| FilterError::InvalidValue {
+                    field: field.name().to_string(),
+                    value: value_str.to_string(),
+                    reason: reason.to_string(),
+                };
+                match value_str.parse::<i64>() {
+                    // PIDs, counters and inodes are never negative
+                    Ok(value) if value < 0 && !field.is_signed() => {
+                        return Err(invalid("Expected a non-negative integer (e.g., 1000)"));
+                    }
+                    Ok(value) => FilterValue::Int(value),
+                    Err(_) => return Err(invalid("Expected an integer (e.g., 1000)")),
+                }
+            }
         };
 
         Ok(Self { field, op, value })
@@ -331,18 +525,13 @@ impl Filter {
     /// `true` if the process matches the filter condition, `false` otherwise.
     pub fn matches(&self, process: &crate::ProcessInfo) -> bool {
         match (&self.field, &self.value, &self.op) {
-            // CPU comparisons
-            (FilterField::Cpu, FilterValue::Float(val), op) => {
-                Self::compare_float(process.cpu_percent, *val, *op)
-            }
-            // Memory comparisons
-            (FilterField::Mem, FilterValue::Float(val), op) => {
-                Self::compare_float(process.memory_percent, *val, *op)
-            }
-            // PID comparisons
-            (FilterField::Pid, FilterValue::Int(val), op) => {
-                Self::compare_int(process.pid, *val, *op)
-            }
+            // Numeric comparisons (processes lacking the metric never match)
+            (field, FilterValue::Float(val), op) => field
+                .float_value(process)
+                .is_some_and(|v| Self::compare_float(v, *val, *op)),
+            (field, FilterValue::Int(val), op) => field
+                .int_value(process)
+                .is_some_and(|v| Self::compare_int(v, *val, *op)),
             // Name matching (case-insensitive contains for ==, inverse for !=)
             (FilterField::Name, FilterValue::String { lowercase, .. }, FilterOp::Eq) => {
                 process.name.to_lowercase().contains(lowercase)
@@ -357,11 +546,52 @@ impl Filter {
             (FilterField::User, FilterValue::String { original, .. }, FilterOp::Ne) => {
                 &process.user != original
             }
+            // Container matching (ID prefix or runtime name; "none" for host processes)
+            (FilterField::Container, FilterValue::String { lowercase, .. }, FilterOp::Eq) => {
+                Self::matches_container(process, lowercase)
+            }
+            (FilterField::Container, FilterValue::String { lowercase, .. }, FilterOp::Ne) => {
+                !Self::matches_container(process, lowercase)
+            }
+            // Cgroup matching (case-insensitive contains; processes without one never match)
+            (FilterField::Cgroup, FilterValue::String { lowercase, .. }, FilterOp::Eq) => process
+                .cgroup
+                .as_ref()
+                .is_some_and(|c| c.to_lowercase().contains(lowercase)),
+            (FilterField::Cgroup, FilterValue::String { lowercase, .. }, FilterOp::Ne) => process
+                .cgroup
+                .as_ref()
+                .is_some_and(|c| !c.to_lowercase().contains(lowercase)),
+            // Unit matching (exact, processes without a unit never match)
+            (FilterField::Unit, FilterValue::String { original, .. }, FilterOp::Eq) => process
+                .unit
+                .as_ref()
+                .is_some_and(|u| Self::matches_unit(u, original)),
+            (FilterField::Unit, FilterValue::String { original, .. }, FilterOp::Ne) => process
+                .unit
+                .as_ref()
+                .is_some_and(|u| !Self::matches_unit(u, original)),
             // Invalid combinations (should be caught during parsing)
             _ => false,
         }
     }
 
+    fn matches_container(process: &crate::ProcessInfo, value: &str) -> bool {
+        match &process.container {
+            None => value == "none",
+            Some(container) => {
+                container.runtime.name() == value
+                    || container.id.to_lowercase().starts_with(value)
+                    || container.short_name() == value
+            }
+        }
+    }
+
+    /// Unit names match exactly; `nginx` is accepted for `nginx.service`.
+    fn matches_unit(unit: &str, value: &str) -> bool {
+        unit == value || unit.strip_suffix(".service") == Some(value)
+    }
+
     fn compare_float(a: f32, b: f32, op: FilterOp) -> bool {
         match op {
             FilterOp::Gt => a > b,
@@ -373,7 +603,7 @@ impl Filter {
         }
     }
 
-    fn compare_int(a: u32, b: u32, op: FilterOp) -> bool {
+    fn compare_int(a: i64, b: i64, op: FilterOp) -> bool {
         match op {
             FilterOp::Gt => a > b,
             FilterOp::Gte => a >= b,
@@ -426,6 +656,190 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_counter_filters() {
+        let process = crate::ProcessInfo {
+            pid: 1,
+            name: "worker".to_string(),
+            cpu_user_seconds: Some(12.5),
+            cpu_system_seconds: Some(2.5),
+            voluntary_ctx_switches: Some(1000),
+            involuntary_ctx_switches: Some(40),
+            minor_faults: Some(5000),
+            major_faults: Some(3),
+            ..Default::default()
+        };
+
+        assert!(
+            FilterExpr::parse("cputime >= 15")
+                .unwrap()
+                .matches(&process)
+        );
+        assert!(
+            FilterExpr::parse("utime > 10 and stime < 5")
+                .unwrap()
+                .matches(&process)
+        );
+        assert!(FilterExpr::parse("majflt > 0").unwrap().matches(&process));
+        assert!(FilterExpr::parse("ivcsw == 40").unwrap().matches(&process));
+        assert!(!FilterExpr::parse("vcsw > 1000").unwrap().matches(&process));
+
+        // Counters are unavailable on some platforms and never match
+        let unknown = crate::ProcessInfo::default();
+        assert!(!FilterExpr::parse("minflt >= 0").unwrap().matches(&unknown));
+        assert!(!FilterExpr::parse("minflt != 1").unwrap().matches(&unknown));
+    }
+
+    #[test]
+    fn test_sched_filters() {
+        let process = crate::ProcessInfo {
+            pid: 1,
+            name: "pipewire".to_string(),
+            sched: Some(crate::SchedInfo {
+                nice: -11,
+                priority: 9,
+                policy: crate::procfs::SchedPolicy::Other,
+                cpus_allowed: Some("0-7".to_string()),
+                oom_score: Some(666),
+                oom_score_adj: Some(200),
+            }),
+            ..Default::default()
+        };
+
+        assert!(FilterExpr::parse("nice < 0").unwrap().matches(&process));
+        assert!(FilterExpr::parse("nice == -11").unwrap().matches(&process));
+        assert!(
+            FilterExpr::parse("oom_score > 500")
+                .unwrap()
+                .matches(&process)
+        );
+        assert!(
+            FilterExpr::parse("oom_score_adj >= 200 and priority < 20")
+                .unwrap()
+                .matches(&process)
+        );
+        assert!(!FilterExpr::parse("nice > 0").unwrap().matches(&process));
+
+        // Without collected scheduling details nothing matches
+        let unknown = crate::ProcessInfo::default();
+        assert!(!FilterExpr::parse("nice <= 19").unwrap().matches(&unknown));
+    }
+
+    #[test]
+    fn test_container_and_cgroup_filters() {
+        let containerized = crate::ProcessInfo {
+            pid: 1,
+            name: "nginx".to_string(),
+            cgroup: Some("/system.slice/docker-3F1A9C2B.scope".to_string()),
+            container: Some(crate::cgroup::ContainerInfo {
+                runtime: crate::cgroup::ContainerRuntime::Docker,
+                id: "3f1a9c2b7d4e".to_string(),
+            }),
+            ..Default::default()
+        };
+        let host = crate::ProcessInfo {
+            pid: 2,
+            name: "sshd".to_string(),
+            cgroup: Some("/system.slice/ssh.service".to_string()),
+            ..Default::default()
+        };
+
+        let docker = FilterExpr::parse("container == docker").unwrap();
+        assert!(docker.matches(&containerized));
+        assert!(!docker.matches(&host));
+        let by_id = FilterExpr::parse("container == 3F1A9C").unwrap();
+        assert!(by_id.matches(&containerized));
+        let host_only = FilterExpr::parse("container == none").unwrap();
+        assert!(host_only.matches(&host));
+        assert!(!host_only.matches(&containerized));
+        assert!(
+            FilterExpr::parse("container != none")
+                .unwrap()
+                .matches(&containerized)
+        );
+
+        let cgroup = FilterExpr::parse("cgroup == system.slice/docker").unwrap();
+        assert!(cgroup.matches(&containerized));
+        assert!(!cgroup.matches(&host));
+        assert!(
+            FilterExpr::parse("cgroup != docker")
+                .unwrap()
+                .matches(&host)
+        );
+        assert!(
+            !FilterExpr::parse("cgroup == /")
+                .unwrap()
+                .matches(&crate::ProcessInfo::default())
+        );
+        assert!(FilterExpr::parse("container > 1").is_err());
+    }
+
+    #[test]
+    fn test_namespace_filters() {
+        let sandboxed = crate::ProcessInfo {
+            pid: 1,
+            namespaces: Some(crate::Namespaces {
+                net: Some(4026532301),
+                pid: Some(4026531836),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let expr = FilterExpr::parse("netns != 4026531840").unwrap();
+        assert!(expr.uses_field(FilterField::is_namespace));
+        assert!(expr.matches(&sandboxed));
+        assert!(
+            FilterExpr::parse("pidns == 4026531836")
+                .unwrap()
+                .matches(&sandboxed)
+        );
+        // Unreadable namespaces never match
+        assert!(!FilterExpr::parse("mntns != 1").unwrap().matches(&sandboxed));
+        assert!(!expr.matches(&crate::ProcessInfo::default()));
+    }
+
+    #[test]
+    fn test_unit_filter() {
+        let nginx = crate::ProcessInfo {
+            pid: 1,
+            name: "nginx".to_string(),
+            unit: Some("nginx.service".to_string()),
+            ..Default::default()
+        };
+        let kernel = crate::ProcessInfo::default();
+
+        let expr = FilterExpr::parse("unit == nginx.service").unwrap();
+        assert!(expr.matches(&nginx));
+        assert!(!expr.matches(&kernel));
+        assert!(FilterExpr::parse("unit == nginx").unwrap().matches(&nginx));
+        assert!(!FilterExpr::parse("unit == ngin").unwrap().matches(&nginx));
+        assert!(
+            !FilterExpr::parse("unit != nginx.service")
+                .unwrap()
+                .matches(&nginx)
+        );
+        assert!(
+            !FilterExpr::parse("unit != sshd.service")
+                .unwrap()
+                .matches(&kernel)
+        );
+    }
+
+    #[test]
+    fn test_uses_field() {
+        let expr = FilterExpr::parse("cpu > 10 or name == x and oom_score > 500").unwrap();
+        assert!(expr.uses_field(FilterField::is_sched));
+        let expr = FilterExpr::parse("cpu > 10 and mem > 5").unwrap();
+        assert!(!expr.uses_field(FilterField::is_sched));
+    }
+
+    #[test]
+    fn test_counter_filter_requires_integer() {
+        let result = FilterExpr::parse("majflt > 1.5");
+        assert!(matches!(result, Err(FilterError::InvalidValue { .. })));
+    }
+
     #[test]
     fn test_invalid_field() {
         let result = FilterExpr::parse("invalid > 10");
@@ -452,6 +866,20 @@ mod tests {
         assert!(matches!(result, Err(FilterError::InvalidValue { .. })));
     }
 
+    #[test]
+    fn test_negative_value_for_unsigned_field() {
+        for expression in ["pid > -5", "pid == -1", "vcsw < -1", "netns != -3"] {
+            let result = FilterExpr::parse(expression);
+            assert!(
+                matches!(result, Err(FilterError::InvalidValue { ref reason, .. }) if reason.contains("non-negative")),
+                "{expression}"
+            );
+        }
+        for expression in ["nice < -5", "priority == -51", "oom_score_adj < -100"] {
+            assert!(FilterExpr::parse(expression).is_ok(), "{expression}");
+        }
+    }
+
     #[test]
     fn test_empty_expression() {
         let result = FilterExpr::parse("");
@@ -476,6 +904,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&matching_process));
 
@@ -492,6 +921,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(!expr.matches(&partial_match_1));
 
@@ -508,6 +938,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(!expr.matches(&partial_match_2));
     }
@@ -529,6 +960,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&match_cpu));
 
@@ -545,6 +977,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&match_mem));
 
@@ -561,6 +994,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&match_both));
 
@@ -577,6 +1011,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(!expr.matches(&match_none));
     }
@@ -610,6 +1045,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&match_cpu));
 
@@ -626,6 +1062,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&match_and));
 
@@ -642,6 +1079,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(!expr.matches(&no_match));
     }
@@ -663,6 +1101,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&process));
     }
@@ -701,6 +1140,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&chrome));
 
@@ -716,6 +1156,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(expr.matches(&firefox));
 
@@ -731,6 +1172,7 @@ mod tests {
             disk_read_bytes: 0,
             disk_write_bytes: 0,
             open_files: None,
+            ..Default::default()
         };
         assert!(!expr.matches(&other));
     }
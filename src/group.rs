@@ -0,0 +1,426 @@
+use crate::psi::PressureStall;
+use crate::{
+    ProcessInfo, SystemMetrics, SystemSnapshot, escape_csv_field, format_bytes_parts,
+    format_pressure, write_system_header,
+};
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Attribute processes are aggregated by with `--group-by`.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Container runtime and short container ID (e.g. `docker:3f1a9c2b7d4e`)
+    Container,
+    /// Systemd unit (service, scope or slice)
+    Unit,
+    /// Network namespace inode number (implies `--namespaces`)
+    Netns,
+}
+
+impl GroupBy {
+    /// Name as accepted by `--group-by`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Container => "container",
+            Self::Unit => "unit",
+            Self::Netns => "netns",
+        }
+    }
+
+    /// Column title used in table output.
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Container => "Container",
+            Self::Unit => "Unit",
+            Self::Netns => "Net namespace",
+        }
+    }
+
+    /// Cgroup directory shared by the group a process belongs to, used to
+    /// read per-group pressure. Only containers and units map to a cgroup.
+    fn cgroup_path(&self, process: &ProcessInfo) -> Option<String> {
+        let path = process.cgroup.as_deref()?;
+        let components: Vec<&str> = path.split('/').collect();
+        let end = match self {
+            Self::Container => {
+                let id = &process.container.as_ref()?.id;
+                components.iter().rposition(|c| c.contains(id.as_str()))?
+            }
+            Self::Unit => {
+                let unit = process.unit.as_deref()?;
+                components.iter().rposition(|c| *c == unit)?
+            }
+            Self::Netns => return None,
+        };
+        Some(components[..=end].join("/"))
+    }
+
+    /// Group key of a process, or `None` if it does not belong to any group
+    /// (e.g. a process running directly on the host).
+    fn key(&self, process: &ProcessInfo) -> Option<String> {
+        match self {
+            Self::Container => process.container.as_ref().map(|c| c.short_name()),
+            Self::Unit => process.unit.clone(),
+            Self::Netns => process
+                .namespaces
+                .and_then(|ns| ns.net)
+                .map(|inode| inode.to_string()),
+        }
+    }
+}
+
+/// Aggregated metrics of all processes sharing a group key.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GroupSummary {
+    /// Group key, or null for processes outside any group
+    pub group: Option<String>,
+    /// Number of processes in the group
+    pub process_count: usize,
+    /// Sum of CPU usage percentages
+    pub cpu_percent: f32,
+    /// Sum of resident memory (RSS) in bytes
+    pub memory_bytes: u64,
+    /// Sum of memory usage percentages
+    pub memory_percent: f32,
+    /// Sum of thread counts
+    pub thread_count: usize,
+    /// Cgroup of the container or unit (Linux)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<String>,
+    /// Pressure stall information of the group's cgroup (cgroup v2 only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure: Option<PressureStall>,
+}
+
+/// A snapshot with processes aggregated into groups.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GroupedSnapshot {
+    /// ISO 8601 timestamp (RFC3339)
+    pub timestamp: String,
+    /// System-wide metrics
+    pub system: SystemMetrics,
+    /// Attribute the processes were grouped by
+    pub group_by: GroupBy,
+    /// Groups, sorted by `--sort-by` (CPU descending by default)
+    pub groups: Vec<GroupSummary>,
+}
+
+impl GroupedSnapshot {
+    /// Aggregates the (already searched and filtered) processes of a snapshot,
+    /// and reads the pressure of each group's cgroup from `cgroup_root` where
+    /// available.
+    pub fn from_snapshot(
+        snapshot: SystemSnapshot,
+        group_by: GroupBy,
+        cgroup_root: Option<&Path>,
+    ) -> Self {
+        let mut groups = group_processes(&snapshot.processes, group_by);
+        if let Some(mount) = cgroup_root.and_then(crate::cgroup::unified_mount) {
+            for group in &mut groups {
+                group.pressure = group.cgroup.as_deref().and_then(|cgroup| {
+                    PressureStall::read_cgroup(&mount.join(cgroup.trim_start_matches('/')))
+                });
+            }
+        }
+        Self {
+            timestamp: snapshot.timestamp,
+            system: snapshot.system,
+            group_by,
+            groups,
+        }
+    }
+}
+
+/// Aggregates processes by the given key, in order of first appearance.
+pub fn group_processes(processes: &[ProcessInfo], group_by: GroupBy) -> Vec<GroupSummary> {
+    let mut groups: Vec<GroupSummary> = Vec::new();
+    let mut index: HashMap<Option<String>, usize> = HashMap::new();
+    for process in processes {
+        let key = group_by.key(process);
+        let i = *index.entry(key.clone()).or_insert_with(|| {
+            groups.push(GroupSummary {
+                group: key,
+                process_count: 0,
+                cpu_percent: 0.0,
+                memory_bytes: 0,
+                memory_percent: 0.0,
+                thread_count: 0,
+                cgroup: group_by.cgroup_path(process),
+                pressure: None,
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[i];
+        group.process_count += 1;
+        group.cpu_percent += process.cpu_percent;
+        group.memory_bytes += process.memory_bytes;
+        group.memory_percent += process.memory_percent;
+        group.thread_count += process.thread_count;
+    }
+    groups
+}
+
+/// Sorts groups in-place: "cpu" (descending, default), "mem"/"memory", "name",
+/// "count"/"procs" or "threads". Other keys fall back to CPU with a warning.
+pub fn sort_groups(groups: &mut [GroupSummary], sort_by: &str) {
+    let by_cpu = |a: &GroupSummary, b: &GroupSummary| {
+        b.cpu_percent
+            .partial_cmp(&a.cpu_percent)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    };
+    match sort_by.to_lowercase().as_str() {
+        "cpu" => groups.sort_by(by_cpu),
+        "mem" | "memory" => groups.sort_by_key(|g| std::cmp::Reverse(g.memory_bytes)),
+        // Ungrouped processes sort last
+        "name" => groups.sort_by(|a, b| match (&a.group, &b.group) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }),
+        "count" | "procs" => groups.sort_by_key(|g| std::cmp::Reverse(g.process_count)),
+        "threads" => groups.sort_by_key(|g| std::cmp::Reverse(g.thread_count)),
+        _ => {
+            eprintln!(
+                "Warning: Unknown sort field '{sort_by}' for groups, using 'cpu'. Valid: cpu, mem, name, count, threads"
+            );
+            groups.sort_by(by_cpu);
+        }
+    }
+}
+
+/// Outputs the CSV header row for grouped output.
+///
+/// # Errors
+///
+/// Returns error if writing to stdout fails.
+pub fn output_groups_csv_header() -> io::Result<()> {
+    writeln!(
+        io::stdout(),
+        "timestamp,cpu_usage,memory_total,memory_used,memory_percent,group_by,group,process_count,cpu_percent,memory_bytes,memory_percent_group,thread_count,cgroup,cpu_pressure_some_avg10,memory_pressure_some_avg10,io_pressure_some_avg10"
+    )?;
+    io::stdout().flush()
+}
+
+/// Outputs one CSV row per group.
+///
+/// # Errors
+///
+/// Returns error if writing to stdout fails.
+pub fn output_groups_csv_rows(grouped: &GroupedSnapshot) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    for group in &grouped.groups {
+        let pressure = |select: fn(&PressureStall) -> Option<crate::psi::Pressure>| {
+            group
+                .pressure
+                .as_ref()
+                .and_then(select)
+                .map(|p| p.some.avg10.to_string())
+                .unwrap_or_default()
+        };
+        writeln!(
+            stdout,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            escape_csv_field(&grouped.timestamp),
+            grouped.system.cpu_usage,
+            grouped.system.memory_total,
+            grouped.system.memory_used,
+            grouped.system.memory_percent,
+            grouped.group_by.name(),
+            escape_csv_field(group.group.as_deref().unwrap_or_default()),
+            group.process_count,
+            group.cpu_percent,
+            group.memory_bytes,
+            group.memory_percent,
+            group.thread_count,
+            escape_csv_field(group.cgroup.as_deref().unwrap_or_default()),
+            pressure(|p| p.cpu),
+            pressure(|p| p.memory),
+            pressure(|p| p.io)
+        )?;
+    }
+    stdout.flush()
+}
+
+/// Outputs grouped snapshot in human-readable format.
+///
+/// # Errors
+///
+/// Returns error if writing to stdout fails.
+pub fn output_groups_human(
+    grouped: &GroupedSnapshot,
+    filter_expr: Option<&String>,
+    sort_by: &str,
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write_system_header(&mut stdout, &grouped.system)?;
+
+    if let Some(filter) = filter_expr {
+        writeln!(stdout, "{} {}", "Filter:".bold(), filter.cyan())?;
+    }
+    writeln!(
+        stdout,
+        "{} {} | {} {} | {} {} {}",
+        "Group by:".bold(),
+        grouped.group_by.name().yellow(),
+        "Sort:".bold(),
+        sort_by.yellow(),
+        "Showing:".bold(),
+        grouped.groups.len().to_string().green(),
+        "groups".dimmed()
+    )?;
+    writeln!(stdout)?;
+
+    // Pressure columns only appear when some group's cgroup reports PSI
+    let show_pressure = grouped.groups.iter().any(|g| g.pressure.is_some());
+    let pressure_header = if show_pressure {
+        format!(
+            " {:>7} {:>7} {:>7}",
+            "CPU PSI".bold(),
+            "Mem PSI".bold(),
+            "IO PSI".bold()
+        )
+    } else {
+        String::new()
+    };
+    writeln!(
+        stdout,
+        "{:<24} {:>6} {:>8} {:>8} {:>8} {:>7}{}",
+        grouped.group_by.title().bold(),
+        "Procs".bold(),
+        "CPU%".bold(),
+        "Mem%".bold(),
+        "RSS".bold(),
+        "Threads".bold(),
+        pressure_header
+    )?;
+    let pressure_rule = if show_pressure { 24 } else { 0 };
+    writeln!(stdout, "{}", "─".repeat(66 + pressure_rule).dimmed())?;
+
+    for group in &grouped.groups {
+        let (rss_val, rss_unit) = format_bytes_parts(group.memory_bytes);
+        let name = match &group.group {
+            Some(name) => format!("{:<24}", &name[..name.len().min(24)])
+                .cyan()
+                .to_string(),
+            None => format!("{:<24}", "(none)").dimmed().to_string(),
+        };
+        let pressure_display = if show_pressure {
+            let pressure = group.pressure.as_ref();
+            format!(
+                " {} {} {}",
+                format_pressure(pressure.and_then(|p| p.cpu.as_ref()), 7),
+                format_pressure(pressure.and_then(|p| p.memory.as_ref()), 7),
+                format_pressure(pressure.and_then(|p| p.io.as_ref()), 7)
+            )
+        } else {
+            String::new()
+        };
+        writeln!(
+            stdout,
+            "{} {:>6} {:>7.1}% {:>7.1}% {:>6} {} {:>7}{}",
+            name,
+            group.process_count,
+            group.cpu_percent,
+            group.memory_percent,
+            rss_val,
+            rss_unit.dimmed(),
+            group.thread_count,
+            pressure_display
+        )?;
+    }
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cgroup::{ContainerInfo, ContainerRuntime};
+
+    fn process(pid: u32, container: Option<&str>, cpu_percent: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: format!("proc{pid}"),
+            cpu_percent,
+            memory_bytes: 1024,
+            memory_percent: 0.5,
+            thread_count: 2,
+            container: container.map(|id| ContainerInfo {
+                runtime: ContainerRuntime::Docker,
+                id: id.to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_container() {
+        let processes = vec![
+            process(1, None, 1.0),
+            process(2, Some("aaaaaaaaaaaaaaaa"), 10.0),
+            process(3, Some("aaaaaaaaaaaaaaaa"), 5.0),
+            process(4, Some("bbbbbbbbbbbbbbbb"), 30.0),
+        ];
+        let mut groups = group_processes(&processes, GroupBy::Container);
+        assert_eq!(groups.len(), 3);
+
+        sort_groups(&mut groups, "cpu");
+        assert_eq!(groups[0].group.as_deref(), Some("docker:bbbbbbbbbbbb"));
+        assert_eq!(groups[1].group.as_deref(), Some("docker:aaaaaaaaaaaa"));
+        assert_eq!(groups[1].process_count, 2);
+        assert_eq!(groups[1].thread_count, 4);
+        assert_eq!(groups[1].memory_bytes, 2048);
+        assert!((groups[1].cpu_percent - 15.0).abs() < f32::EPSILON);
+        assert_eq!(groups[2].group, None);
+
+        sort_groups(&mut groups, "name");
+        assert_eq!(groups[0].group.as_deref(), Some("docker:aaaaaaaaaaaa"));
+        assert_eq!(groups[2].group, None);
+
+        sort_groups(&mut groups, "count");
+        assert_eq!(groups[0].process_count, 2);
+    }
+
+    #[test]
+    fn test_group_by_unit() {
+        let unit = |pid: u32, unit: &str| ProcessInfo {
+            unit: Some(unit.to_string()),
+            ..process(pid, None, 2.0)
+        };
+        let processes = vec![
+            unit(1, "nginx.service"),
+            unit(2, "nginx.service"),
+            unit(3, "sshd.service"),
+        ];
+        let groups = group_processes(&processes, GroupBy::Unit);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].group.as_deref(), Some("nginx.service"));
+        assert_eq!(groups[0].process_count, 2);
+        assert!((groups[0].cpu_percent - 4.0).abs() < f32::EPSILON);
+        assert!((groups[0].memory_percent - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_group_cgroup_path() {
+        let podman = ProcessInfo {
+            cgroup: Some(
+                "/user.slice/user@1000.service/user.slice/libpod-aaaa.scope/container".to_string(),
+            ),
+            unit: Some("libpod-aaaa.scope".to_string()),
+            ..process(1, Some("aaaa"), 1.0)
+        };
+        assert_eq!(
+            GroupBy::Container.cgroup_path(&podman).as_deref(),
+            Some("/user.slice/user@1000.service/user.slice/libpod-aaaa.scope")
+        );
+        assert_eq!(
+            GroupBy::Unit.cgroup_path(&podman).as_deref(),
+            Some("/user.slice/user@1000.service/user.slice/libpod-aaaa.scope")
+        );
+        assert_eq!(GroupBy::Netns.cgroup_path(&podman), None);
+        assert_eq!(GroupBy::Container.cgroup_path(&process(2, None, 1.0)), None);
+    }
+}
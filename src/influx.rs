@@ -0,0 +1,249 @@
+use crate::SystemSnapshot;
+use crate::columns::{self, Column, ColumnValue};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Measurement of the system-wide point.
+const SYSTEM_MEASUREMENT: &str = "stop_system";
+
+/// Measurement of the per-process points.
+const PROCESS_MEASUREMENT: &str = "stop_process";
+
+/// Columns written as tags rather than fields.
+const TAG_COLUMNS: &[&str] = &["pid", "name", "user"];
+
+/// Escapes a tag key, tag value or field key: commas, equals signs and spaces
+/// are backslash-escaped, and line feeds are written as `\n` so every point
+/// stays on one line.
+pub fn escape_tag(value: &str) -> Cow<'_, str> {
+    escape(value, &[',', '=', ' '])
+}
+
+/// Escapes a string field value, which is written inside double quotes:
+/// double quotes and backslashes are backslash-escaped, line feeds written as `\n`.
+pub fn escape_string_field(value: &str) -> Cow<'_, str> {
+    escape(value, &['"', '\\'])
+}
+
+fn escape<'a>(value: &'a str, special: &[char]) -> Cow<'a, str> {
+    if !value.contains(|c| special.contains(&c) || c == '\n') {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        if c == '\n' {
+            escaped.push_str("\\n");
+            continue;
+        }
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
+/// Formats a field value, or `None` if it is unavailable or not representable
+/// (line protocol has no NaN or infinity).
+///
+/// Unsigned values are clamped to `i64::MAX`, so every integer field stays a
+/// signed integer, the only integer type InfluxDB 1.x accepts.
+fn field_value(value: &ColumnValue) -> Option<String> {
+    match value {
+        ColumnValue::Unsigned(v) => v.map(|n| format!("{}i", i64::try_from(n).unwrap_or(i64::MAX))),
+        ColumnValue::Signed(v) => v.map(|n| format!("{n}i")),
+        ColumnValue::Percent(v) => v.is_finite().then(|| v.to_string()),
+        ColumnValue::Float(v) => v.filter(|f| f.is_finite()).map(|f| f.to_string()),
+        ColumnValue::Text(v) => v
+            .as_deref()
+            .map(|s| format!("\"{}\"", escape_string_field(s))),
+    }
+}
+
+/// Converts the RFC3339 snapshot timestamp to nanoseconds since the epoch.
+///
+/// # Errors
+///
+/// Returns `InvalidData` if the timestamp cannot be parsed or is out of range.
+pub fn timestamp_nanos(timestamp: &str) -> io::Result<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .and_then(|t| t.timestamp_nanos_opt())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid timestamp '{timestamp}'"),
+            )
+        })
+}
+
+/// Writes one point; skipped if it has no fields, which line protocol requires.
+fn write_point<'a>(
+    out: &mut impl Write,
+    measurement: &str,
+    tags: &[(&str, Cow<'a, str>)],
+    fields: impl IntoIterator<Item = (Cow<'a, str>, ColumnValue<'a>)>,
+    nanos: i64,
+) -> io::Result<()> {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .filter_map(|(key, value)| Some(format!("{}={}", escape_tag(&key), field_value(&value)?)))
+        .collect();
+    if fields.is_empty() {
+        return Ok(());
+    }
+    write!(out, "{measurement}")?;
+    // Empty tag values are not allowed; leave such tags out
+    for (key, value) in tags.iter().filter(|(_, value)| !value.is_empty()) {
+        write!(out, ",{}={}", escape_tag(key), escape_tag(value))?;
+    }
+    writeln!(out, " {} {nanos}", fields.join(","))
+}
+
+/// Writes a snapshot in InfluxDB line protocol.
+///
+/// One `stop_system` point carries the system metrics; one `stop_process`
+/// point per process is tagged with `pid`, `name` and `user` and has the
+/// selected columns (all by default) as fields. Unavailable values are left
+/// out. All points share the snapshot timestamp in nanoseconds.
+///
+/// # Errors
+///
+/// Returns error if writing fails or the timestamp is invalid.
+pub fn write_snapshot(
+    out: &mut impl Write,
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+) -> io::Result<()> {
+    let nanos = timestamp_nanos(&snapshot.timestamp)?;
+    write_point(
+        out,
+        SYSTEM_MEASUREMENT,
+        &[],
+        columns::system_values(&snapshot.system),
+        nanos,
+    )?;
+
+    let fields: Vec<&Column> = columns::selected_or_all(columns)
+        .into_iter()
+        .filter(|c| !TAG_COLUMNS.contains(&c.name))
+        .collect();
+    for process in &snapshot.processes {
+        let tags = [
+            ("pid", Cow::Owned(process.pid.to_string())),
+            ("name", Cow::Borrowed(process.name.as_str())),
+            ("user", Cow::Borrowed(process.user.as_str())),
+        ];
+        write_point(
+            out,
+            PROCESS_MEASUREMENT,
+            &tags,
+            fields
+                .iter()
+                .map(|c| (Cow::Borrowed(c.key), (c.value)(process))),
+            nanos,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProcessInfo, SystemMetrics};
+
+    fn snapshot() -> SystemSnapshot {
+        SystemSnapshot {
+            timestamp: "2026-01-02T03:04:05.123456789+00:00".to_string(),
+            system: SystemMetrics {
+                cpu_usage: 30.0,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                ..Default::default()
+            },
+            processes: vec![ProcessInfo {
+                pid: 42,
+                name: "web server,v=2".to_string(),
+                cpu_percent: 12.5,
+                memory_bytes: 1 << 20,
+                command: "web --title \"a\\b\"".to_string(),
+                thread_count: 4,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_escape_tag() {
+        assert_eq!(escape_tag("nginx"), "nginx");
+        assert!(matches!(escape_tag("nginx"), Cow::Borrowed(_)));
+        assert_eq!(escape_tag("a b,c=d"), r"a\ b\,c\=d");
+        assert_eq!(escape_tag("a\nb"), r"a\nb");
+        // Quotes and backslashes are literal in tags
+        assert_eq!(escape_tag(r#"a"b\c"#), r#"a"b\c"#);
+    }
+
+    #[test]
+    fn test_escape_string_field() {
+        assert_eq!(escape_string_field("a b,c=d"), "a b,c=d");
+        assert_eq!(escape_string_field(r#"say "hi" \o/"#), r#"say \"hi\" \\o/"#);
+        assert_eq!(escape_string_field("two\nlines"), r"two\nlines");
+    }
+
+    #[test]
+    fn test_field_value_integers() {
+        assert_eq!(
+            field_value(&ColumnValue::Unsigned(Some(42))).unwrap(),
+            "42i"
+        );
+        assert_eq!(
+            field_value(&ColumnValue::Unsigned(Some(u64::MAX))).unwrap(),
+            "9223372036854775807i"
+        );
+        assert_eq!(field_value(&ColumnValue::Signed(Some(-3))).unwrap(), "-3i");
+        assert_eq!(field_value(&ColumnValue::Unsigned(None)), None);
+    }
+
+    #[test]
+    fn test_timestamp_nanos() {
+        assert_eq!(
+            timestamp_nanos("2026-01-02T03:04:05.123456789+00:00").unwrap(),
+            1_767_323_045_123_456_789
+        );
+        assert_eq!(timestamp_nanos("1970-01-01T01:00:00+01:00").unwrap(), 0);
+        assert!(timestamp_nanos("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_write_snapshot() {
+        let columns: Vec<&Column> = ["pid", "name", "cpu", "rss", "files", "command"]
+            .iter()
+            .map(|n| columns::parse_column(n).unwrap())
+            .collect();
+        let mut out = Vec::new();
+        write_snapshot(&mut out, &snapshot(), Some(&columns)).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "stop_system cpu_usage=30,memory_total=8589934592i,memory_used=2147483648i,\
+             memory_percent=25 1767323045123456789"
+        );
+        // The empty user tag and the unavailable open_files field are left out
+        assert_eq!(
+            lines[1],
+            r#"stop_process,pid=42,name=web\ server\,v\=2 cpu_percent=12.5,memory_bytes=1048576i,command="web --title \"a\\b\"" 1767323045123456789"#
+        );
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_point_without_fields_is_skipped() {
+        let columns = [columns::parse_column("pid").unwrap()];
+        let mut out = Vec::new();
+        write_snapshot(&mut out, &snapshot(), Some(&columns)).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 1);
+    }
+}
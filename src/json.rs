@@ -0,0 +1,148 @@
+//! Streaming JSON output for `--json`, `--json-compact` and `--json-lines`.
+//!
+//! Values are serialized straight into the writer, so a snapshot is never held
+//! as one string; wrap stdout in a `BufWriter` to batch the writes.
+
+use crate::SystemSnapshot;
+use crate::columns::{Column, SelectedProcess, SelectedSnapshot};
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// One `--json-lines` record: the snapshot timestamp followed by the process fields.
+#[derive(Serialize)]
+struct ProcessLine<'a, P> {
+    timestamp: &'a str,
+    #[serde(flatten)]
+    process: P,
+}
+
+/// Writes `value` as one JSON document, pretty-printed or on a single line,
+/// followed by a newline.
+///
+/// # Errors
+///
+/// Returns error if serialization or writing fails.
+pub fn write_value<W: Write, T: Serialize + ?Sized>(
+    out: &mut W,
+    value: &T,
+    compact: bool,
+) -> io::Result<()> {
+    if compact {
+        serde_json::to_writer(&mut *out, value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+    }
+    writeln!(out)
+}
+
+/// Writes a snapshot as one JSON document, with only the selected process
+/// columns if `columns` is set.
+///
+/// # Errors
+///
+/// Returns error if serialization or writing fails.
+pub fn write_snapshot<W: Write>(
+    out: &mut W,
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+    compact: bool,
+) -> io::Result<()> {
+    match columns {
+        Some(columns) => write_value(out, &SelectedSnapshot::new(snapshot, columns), compact),
+        None => write_value(out, snapshot, compact),
+    }
+}
+
+/// Writes one JSON object per process and line (JSON Lines), each starting
+/// with the snapshot `timestamp`. System metrics are not written.
+///
+/// # Errors
+///
+/// Returns error if serialization or writing fails.
+pub fn write_lines<W: Write>(
+    out: &mut W,
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+) -> io::Result<()> {
+    let timestamp = snapshot.timestamp.as_str();
+    for process in &snapshot.processes {
+        match columns {
+            Some(columns) => write_value(
+                out,
+                &ProcessLine {
+                    timestamp,
+                    process: SelectedProcess { process, columns },
+                },
+                true,
+            )?,
+            None => write_value(out, &ProcessLine { timestamp, process }, true)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProcessInfo;
+    use crate::columns::parse_column;
+
+    fn snapshot() -> SystemSnapshot {
+        let process = |pid, name: &str| ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu_percent: 12.5,
+            ..Default::default()
+        };
+        SystemSnapshot {
+            timestamp: "2026-01-02T03:04:05+00:00".to_string(),
+            processes: vec![process(42, "web"), process(7, "db")],
+            ..Default::default()
+        }
+    }
+
+    fn render(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_snapshot_matches_to_string() {
+        let snapshot = snapshot();
+        let pretty = render(|out| write_snapshot(out, &snapshot, None, false));
+        assert_eq!(
+            pretty,
+            serde_json::to_string_pretty(&snapshot).unwrap() + "\n"
+        );
+        let compact = render(|out| write_snapshot(out, &snapshot, None, true));
+        assert_eq!(compact, serde_json::to_string(&snapshot).unwrap() + "\n");
+    }
+
+    #[test]
+    fn test_write_lines() {
+        let snapshot = snapshot();
+        let output = render(|out| write_lines(out, &snapshot, None));
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["timestamp"], "2026-01-02T03:04:05+00:00");
+        assert_eq!(lines[0]["pid"], 42);
+        assert_eq!(lines[1]["name"], "db");
+        assert!(output.starts_with(r#"{"timestamp":"2026-01-02T03:04:05+00:00","pid":42,"#));
+    }
+
+    #[test]
+    fn test_write_lines_with_columns() {
+        let snapshot = snapshot();
+        let columns = vec![parse_column("pid").unwrap(), parse_column("cpu").unwrap()];
+        let output = render(|out| write_lines(out, &snapshot, Some(&columns)));
+        assert_eq!(
+            output,
+            "{\"timestamp\":\"2026-01-02T03:04:05+00:00\",\"pid\":42,\"cpu_percent\":12.5}\n\
+             {\"timestamp\":\"2026-01-02T03:04:05+00:00\",\"pid\":7,\"cpu_percent\":12.5}\n"
+        );
+    }
+}
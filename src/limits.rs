@@ -0,0 +1,232 @@
+use crate::cgroup::ProcessCgroup;
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// cgroup v1 reports "no memory limit" as a page-aligned value close to `i64::MAX`.
+const V1_UNLIMITED_THRESHOLD: u64 = 1 << 62;
+
+/// Whose cgroup limits percentages are made relative to with `--cgroup-limits`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitScope {
+    /// Limits of stop's own cgroup, e.g. the container it runs in
+    Own,
+    /// Limits of each process's own cgroup
+    Process,
+}
+
+/// Effective resource limits of a cgroup.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CgroupLimits {
+    /// Cgroup path the limits apply to
+    pub cgroup: String,
+    /// Memory limit in bytes, the smallest along the hierarchy (None if unlimited)
+    pub memory_limit_bytes: Option<u64>,
+    /// Current memory usage of the cgroup in bytes, including page cache
+    pub memory_usage_bytes: Option<u64>,
+    /// CPU quota in cores (e.g. 1.5), the smallest along the hierarchy (None if unlimited)
+    pub cpu_limit_cores: Option<f64>,
+}
+
+impl CgroupLimits {
+    /// Reads the limits of a cgroup from the cgroup filesystem mounted at `cgroup_root`.
+    ///
+    /// Handles both the v2 unified hierarchy (`memory.max`, `cpu.max`) and v1
+    /// per-controller hierarchies (`memory.limit_in_bytes`, `cpu.cfs_quota_us`).
+    /// Limits set on an ancestor also apply, so the smallest one wins. Inside
+    /// a container without a cgroup namespace, the process's path does not
+    /// exist below the mount and the limits of the mount root are used.
+    pub fn read(cgroup_root: &Path, cgroup: &ProcessCgroup) -> Self {
+        if cgroup_root.join("cgroup.controllers").exists() {
+            let path = cgroup.unified_path().unwrap_or(&cgroup.path);
+            return Self {
+                cgroup: path.to_string(),
+                memory_limit_bytes: effective_limit(cgroup_root, path, |dir| {
+                    parse_memory_max(&read_file(&dir.join("memory.max"))?)
+                }),
+                memory_usage_bytes: read_u64(
+                    &deepest_existing(cgroup_root, path).join("memory.current"),
+                ),
+                cpu_limit_cores: effective_limit(cgroup_root, path, |dir| {
+                    parse_cpu_max(&read_file(&dir.join("cpu.max"))?)
+                }),
+            };
+        }
+
+        let memory_root = cgroup_root.join("memory");
+        let memory_path = cgroup.controller_path("memory").unwrap_or("/");
+        let cpu_root = ["cpu,cpuacct", "cpu"]
+            .iter()
+            .map(|name| cgroup_root.join(name))
+            .find(|dir| dir.is_dir())
+            .unwrap_or_else(|| cgroup_root.join("cpu"));
+        let cpu_path = cgroup.controller_path("cpu").unwrap_or("/");
+        Self {
+            cgroup: cgroup.path.clone(),
+            memory_limit_bytes: effective_limit(&memory_root, memory_path, |dir| {
+                read_u64(&dir.join("memory.limit_in_bytes")).filter(|&v| v < V1_UNLIMITED_THRESHOLD)
+            }),
+            memory_usage_bytes: read_u64(
+                &deepest_existing(&memory_root, memory_path).join("memory.usage_in_bytes"),
+            ),
+            cpu_limit_cores: effective_limit(&cpu_root, cpu_path, |dir| {
+                parse_cfs_quota(
+                    &read_file(&dir.join("cpu.cfs_quota_us"))?,
+                    &read_file(&dir.join("cpu.cfs_period_us"))?,
+                )
+            }),
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_file(path)?.trim().parse().ok()
+}
+
+/// Directory of a cgroup path below a hierarchy mount.
+fn hierarchy_dir(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// The deepest directory of `path` (or its ancestors) that exists below `root`.
+fn deepest_existing(root: &Path, path: &str) -> PathBuf {
+    Path::new(path)
+        .ancestors()
+        .map(|ancestor| hierarchy_dir(root, &ancestor.to_string_lossy()))
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| root.to_path_buf())
+}
+
+/// Smallest limit set on `path` or any of its ancestors below `root`.
+fn effective_limit<T: PartialOrd>(
+    root: &Path,
+    path: &str,
+    read: impl Fn(&Path) -> Option<T>,
+) -> Option<T> {
+    Path::new(path)
+        .ancestors()
+        .filter_map(|ancestor| read(&hierarchy_dir(root, &ancestor.to_string_lossy())))
+        .reduce(|a, b| if b < a { b } else { a })
+}
+
+/// Parses v2 `memory.max`: a byte count, or `max` for no limit.
+fn parse_memory_max(content: &str) -> Option<u64> {
+    content.trim().parse().ok()
+}
+
+/// Parses v2 `cpu.max` (`<quota> <period>` in microseconds, quota `max` for no limit) into cores.
+fn parse_cpu_max(content: &str) -> Option<f64> {
+    let mut fields = content.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next()?.parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Parses v1 `cpu.cfs_quota_us` and `cpu.cfs_period_us` into cores (quota -1 for no limit).
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota: i64 = quota.trim().parse().ok()?;
+    let period: i64 = period.trim().parse().ok()?;
+    (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+}
+
+/// Whether cgroup `path` is `ancestor` or nested below it.
+pub fn is_within(path: &str, ancestor: &str) -> bool {
+    ancestor == "/"
+        || path == ancestor
+        || path
+            .strip_prefix(ancestor)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "3f1a9c2b7d4e5f60718293a4b5c6d7e8f90123456789abcdef0123456789abcd";
+
+    fn fixture_root(version: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/fixtures/cgroupfs/{version}"))
+    }
+
+    fn cgroup(content: &str) -> ProcessCgroup {
+        ProcessCgroup::parse(content).unwrap()
+    }
+
+    #[test]
+    fn test_v2_limits() {
+        let limits = CgroupLimits::read(
+            &fixture_root("v2"),
+            &cgroup(&format!("0::/system.slice/docker-{ID}.scope\n")),
+        );
+        assert_eq!(limits.memory_limit_bytes, Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(limits.memory_usage_bytes, Some(1932735283));
+        assert_eq!(limits.cpu_limit_cores, Some(1.5));
+    }
+
+    #[test]
+    fn test_v2_ancestor_limit_wins() {
+        let limits = CgroupLimits::read(
+            &fixture_root("v2"),
+            &cgroup(&format!(
+                "0::/kubepods.slice/kubepods-pod1.slice/cri-containerd-{ID}.scope\n"
+            )),
+        );
+        // The pod's limits are tighter than the container's own
+        assert_eq!(limits.memory_limit_bytes, Some(1024 * 1024 * 1024));
+        assert_eq!(limits.cpu_limit_cores, Some(0.5));
+        assert_eq!(limits.memory_usage_bytes, Some(256 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_v2_unlimited() {
+        let limits = CgroupLimits::read(
+            &fixture_root("v2"),
+            &cgroup("0::/system.slice/nginx.service\n"),
+        );
+        assert_eq!(limits.memory_limit_bytes, None);
+        assert_eq!(limits.cpu_limit_cores, None);
+    }
+
+    #[test]
+    fn test_v1_limits() {
+        let limits = CgroupLimits::read(
+            &fixture_root("v1"),
+            &cgroup(include_str!(
+                "../tests/fixtures/cgroup/docker-cgroupfs-v1.cgroup"
+            )),
+        );
+        assert_eq!(limits.memory_limit_bytes, Some(512 * 1024 * 1024));
+        assert_eq!(limits.memory_usage_bytes, Some(128 * 1024 * 1024));
+        assert_eq!(limits.cpu_limit_cores, Some(0.5));
+
+        // Host processes: the root's "unlimited" values are ignored
+        let host = CgroupLimits::read(
+            &fixture_root("v1"),
+            &cgroup("4:memory:/\n3:cpu,cpuacct:/\n"),
+        );
+        assert_eq!(host.memory_limit_bytes, None);
+        assert_eq!(host.cpu_limit_cores, None);
+    }
+
+    #[test]
+    fn test_parse_limit_files() {
+        assert_eq!(parse_memory_max("max\n"), None);
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2.0));
+        assert_eq!(parse_cfs_quota("-1\n", "100000\n"), None);
+        assert_eq!(parse_cfs_quota("25000\n", "100000\n"), Some(0.25));
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("/system.slice/a.service", "/"));
+        assert!(is_within("/system.slice/a.service", "/system.slice"));
+        assert!(!is_within("/system.slice-x/a.service", "/system.slice"));
+        assert!(is_within("/system.slice", "/system.slice"));
+    }
+}
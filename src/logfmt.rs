@@ -0,0 +1,147 @@
+use crate::SystemSnapshot;
+use crate::columns::{self, Column, ColumnValue};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Escapes a logfmt value.
+///
+/// Values containing spaces, `=`, double quotes, backslashes or control
+/// characters are wrapped in double quotes, with quotes and backslashes
+/// backslash-escaped and line feeds, carriage returns and tabs written as
+/// `\n`, `\r` and `\t`. Empty values become `""`.
+pub fn escape_value(value: &str) -> Cow<'_, str> {
+    if value.is_empty() {
+        return Cow::Borrowed("\"\"");
+    }
+    if !value.contains(|c: char| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control()) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    Cow::Owned(escaped)
+}
+
+/// Formats a value, or `None` if it is unavailable.
+fn format_value(value: &ColumnValue) -> Option<String> {
+    match value {
+        ColumnValue::Unsigned(v) => v.map(|n| n.to_string()),
+        ColumnValue::Signed(v) => v.map(|n| n.to_string()),
+        ColumnValue::Percent(v) => Some(v.to_string()),
+        ColumnValue::Float(v) => v.map(|f| f.to_string()),
+        ColumnValue::Text(v) => v.as_deref().map(|s| escape_value(s).into_owned()),
+    }
+}
+
+/// Writes one record: `time`, `type` and the available values.
+fn write_record<'a>(
+    out: &mut impl Write,
+    timestamp: &str,
+    kind: &str,
+    values: impl IntoIterator<Item = (Cow<'a, str>, ColumnValue<'a>)>,
+) -> io::Result<()> {
+    write!(out, "time={} type={kind}", escape_value(timestamp))?;
+    for (key, value) in values {
+        if let Some(value) = format_value(&value) {
+            write!(out, " {key}={value}")?;
+        }
+    }
+    writeln!(out)
+}
+
+/// Writes a snapshot as logfmt: one `type=system` record followed by one
+/// `type=process` record per process with the selected columns (all by
+/// default), all carrying the snapshot's RFC3339 `time`. Unavailable values
+/// are left out.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_snapshot(
+    out: &mut impl Write,
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+) -> io::Result<()> {
+    write_record(
+        out,
+        &snapshot.timestamp,
+        "system",
+        columns::system_values(&snapshot.system),
+    )?;
+    let columns = columns::selected_or_all(columns);
+    for process in &snapshot.processes {
+        write_record(
+            out,
+            &snapshot.timestamp,
+            "process",
+            columns
+                .iter()
+                .map(|c| (Cow::Borrowed(c.key), (c.value)(process))),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProcessInfo, SystemMetrics};
+
+    #[test]
+    fn test_escape_value() {
+        assert_eq!(escape_value("nginx"), "nginx");
+        assert!(matches!(escape_value("nginx"), Cow::Borrowed(_)));
+        assert_eq!(escape_value(""), r#""""#);
+        assert_eq!(escape_value("a b"), r#""a b""#);
+        assert_eq!(escape_value("k=v"), r#""k=v""#);
+        assert_eq!(escape_value(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+        assert_eq!(escape_value("two\nlines\tx"), r#""two\nlines\tx""#);
+        assert_eq!(escape_value("bell\x07"), r#""bell\u{7}""#);
+    }
+
+    #[test]
+    fn test_write_snapshot() {
+        let snapshot = SystemSnapshot {
+            timestamp: "2026-01-02T03:04:05+00:00".to_string(),
+            system: SystemMetrics {
+                cpu_usage: 30.0,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                ..Default::default()
+            },
+            processes: vec![ProcessInfo {
+                pid: 42,
+                name: "web".to_string(),
+                cpu_percent: 12.5,
+                command: "web --port 80".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let columns: Vec<&Column> = ["pid", "name", "cpu", "user", "files", "command"]
+            .iter()
+            .map(|n| columns::parse_column(n).unwrap())
+            .collect();
+        let mut out = Vec::new();
+        write_snapshot(&mut out, &snapshot, Some(&columns)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "time=2026-01-02T03:04:05+00:00 type=system cpu_usage=30 memory_total=8589934592 \
+             memory_used=2147483648 memory_percent=25\n\
+             time=2026-01-02T03:04:05+00:00 type=process pid=42 name=web cpu_percent=12.5 \
+             user=\"\" command=\"web --port 80\"\n"
+        );
+    }
+}
@@ -1,14 +1,54 @@
+mod capture;
+mod cgroup;
+mod collector;
+#[cfg(feature = "columnar")]
+mod columnar;
+mod columns;
+mod fds;
 mod filter;
+mod group;
+mod influx;
+mod json;
+mod limits;
+mod logfmt;
+mod namespaces;
+mod procfs;
+mod procfs_collector;
+mod prometheus;
+mod psi;
+#[cfg(feature = "record")]
+mod record;
+mod report;
+mod serve;
+mod template;
+mod threads;
 mod watch;
 
-use clap::Parser;
-use filter::FilterExpr;
+use cgroup::ContainerInfo;
+use clap::{Parser, Subcommand};
+use collector::Backend;
+use columns::{Column, Detail, Level, SYSTEM_CPU, SYSTEM_MEMORY};
+use fds::FdTypeCounts;
+use filter::{FilterExpr, FilterField};
+use group::{GroupBy, GroupedSnapshot};
+use limits::{CgroupLimits, LimitScope};
+use namespaces::Namespaces;
 use owo_colors::OwoColorize;
+use procfs::{HugePages, SchedPolicy};
+use psi::{Pressure, PressureStall};
+#[cfg(feature = "record")]
+use record::RecordTarget;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::error::Error;
 use std::io::{self, Write};
-use sysinfo::System;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+#[cfg(feature = "record")]
+use std::time::Duration;
+use template::Template;
+use threads::ThreadInfo;
 
 /// Minimum interval for CPU usage calculation (milliseconds).
 /// Required by sysinfo to get accurate CPU percentage.
@@ -17,6 +57,18 @@ const CPU_SAMPLE_INTERVAL_MS: u64 = 200;
 /// Default number of processes to show when --top-n is not specified.
 const DEFAULT_TOP_N: usize = 20;
 
+/// Mount point of the Linux proc filesystem.
+const PROC_ROOT: &str = "/proc";
+
+/// Mount point of the Linux cgroup filesystem.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Version of the JSON snapshot layout (`schema_version`).
+///
+/// Bumped when fields are removed, renamed or change type; new optional
+/// fields keep the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// Format bytes into human-readable string with colored unit suffix.
 /// Returns a tuple of (value_string, unit_string) for proper alignment.
 fn format_bytes_parts(bytes: u64) -> (String, String) {
@@ -40,6 +92,23 @@ fn format_bytes_parts(bytes: u64) -> (String, String) {
     }
 }
 
+/// Formats the "some" avg10 stall percentage of a resource right-aligned to `width`,
+/// colored with the same thresholds as system CPU, or `-` if unavailable.
+fn format_pressure(pressure: Option<&Pressure>, width: usize) -> String {
+    match pressure {
+        Some(pressure) => {
+            let value = pressure.some.avg10;
+            let text = format!("{:>width$}", format!("{value:.1}%"));
+            match SYSTEM_CPU.level(value) {
+                Level::High => text.red().to_string(),
+                Level::Warn => text.yellow().to_string(),
+                Level::Normal => text.green().to_string(),
+            }
+        }
+        None => format!("{:>width$}", "-"),
+    }
+}
+
 /// Command-line arguments for the stop tool.
 #[derive(Parser, Debug)]
 #[command(name = "stop")]
@@ -49,21 +118,89 @@ fn format_bytes_parts(bytes: u64) -> (String, String) {
 EXAMPLES:
     stop                              # Human-readable table
     stop --json                       # JSON output
+    stop --json-lines                 # One JSON object per process and line
     stop -s chrome                    # Search for chrome processes
     stop --filter \"cpu > 10\"          # Filter processes
     stop -s postgres --filter \"mem > 5\" # Combine search and filter
-    stop --watch                      # Live monitoring")]
+    stop --watch                      # Live monitoring
+    stop --files 1234                 # List open files of PID 1234
+    stop --threads 1234               # Per-thread CPU of PID 1234
+    stop --group-by container         # CPU/memory per container
+    stop --group-by unit              # CPU/memory per systemd unit
+    stop --group-by netns             # Processes per network namespace
+    stop --print-schema               # JSON Schema of --json snapshots
+    stop capture --out bundle.tar     # Capture /proc for offline analysis
+    stop serve                        # HTTP exporter on 127.0.0.1:9256")]
 #[command(version)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(long, help = "Output as JSON")]
     pub json: bool,
 
+    #[arg(
+        long,
+        conflicts_with_all = ["csv", "json_lines"],
+        help = "Output as JSON on a single line (implies --json)"
+    )]
+    pub json_compact: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["csv", "group_by", "files", "threads"],
+        help = "Output one JSON object per process and line, with the snapshot timestamp (implies --json)"
+    )]
+    pub json_lines: bool,
+
     #[arg(long, help = "Output as CSV")]
     pub csv: bool,
 
+    #[arg(long, help = "Print the JSON Schema of --json snapshots and exit")]
+    pub print_schema: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = OutputFormat::parse,
+        conflicts_with_all = ["json", "json_compact", "json_lines", "csv"],
+        help = "Output format: prometheus, influx (line protocol), logfmt, markdown, html, arrow, or a template like '{pid}\\t{name}\\t{cpu:.1}'",
+        long_help = "Output format: prometheus, influx (line protocol), logfmt, markdown, html
+(a self-contained report with sortable columns), arrow (Arrow IPC stream; needs the
+`columnar` cargo feature), or a template
+
+Templates are rendered once per process. Placeholders:
+  {field}              column name or key: {pid}, {cpu}, {cpu_percent}, {rss}, ...
+  {system.<key>}       system value: {system.cpu_usage}, {system.memory_used}, ...
+  {timestamp}, {count} snapshot timestamp and number of processes
+  {field:spec}         [[fill]align][width][.precision], e.g. {cpu:>6.1}, {name:<20}
+  {field|helper}       byte units: bytes (human-readable), kib, mib, gib, e.g. {rss|mib:.0}
+Use {{ and }} for braces and \\t, \\n for tab and line feed.
+
+Example: --format '{pid}\\t{name}\\t{cpu:.1}\\t{rss|bytes}'"
+    )]
+    pub format: Option<OutputFormat>,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = Template::parse_summary,
+        help = "Line printed before template output (system fields, timestamp and count only)"
+    )]
+    pub header_template: Option<Template>,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = Template::parse_summary,
+        help = "Line printed after template output (system fields, timestamp and count only)"
+    )]
+    pub footer_template: Option<Template>,
+
     #[arg(
         short,
         long,
+        global = true,
         value_name = "TEXT",
         help = "Search processes by name or command"
     )]
@@ -71,22 +208,36 @@ pub struct Args {
 
     #[arg(
         long,
+        global = true,
         value_name = "EXPR",
         help = "Filter processes (e.g., 'cpu > 10')",
         long_help = "Filter processes by expression
 
-Fields:    cpu, mem, pid, name, user
+Fields:    cpu, mem, pid, name, user,
+           utime, stime, cputime (CPU seconds),
+           vcsw, ivcsw (context switches), minflt, majflt (page faults),
+           nice, priority, oom_score, oom_score_adj (implies --sched),
+           container (ID prefix, runtime, or 'none'), cgroup (path substring),
+           unit (systemd unit, e.g. nginx.service),
+           netns, pidns, mntns, userns, utsns, ipcns (implies --namespaces)
 Operators: >, >=, <, <=, ==, !=
 Logic:     and, or
 
 Examples:
   cpu > 50
   cpu > 10 and mem > 5
-  name == chrome or name == firefox"
+  name == chrome or name == firefox
+  container == docker and cpu > 5
+  unit == nginx.service
+  netns != 4026531840"
     )]
     pub filter: Option<String>,
 
-    #[arg(long, value_name = "FIELD", help = "Sort by: cpu, mem, pid, name")]
+    #[arg(
+        long,
+        value_name = "FIELD",
+        help = "Sort by: cpu, mem, pid, name, virt, pss, uss, cputime, utime, stime, vcsw, ivcsw, minflt, majflt"
+    )]
     pub sort_by: Option<String>,
 
     #[arg(long, value_name = "N", help = "Show top N processes")]
@@ -103,13 +254,230 @@ Examples:
     )]
     pub interval: f64,
 
+    #[cfg(feature = "record")]
+    #[arg(
+        long,
+        value_name = "TARGET",
+        value_parser = RecordTarget::parse,
+        requires = "watch",
+        help = "Record each watch interval into a database: sqlite:PATH"
+    )]
+    pub record: Option<RecordTarget>,
+
+    #[cfg(not(feature = "record"))]
+    #[arg(
+        long,
+        value_name = "TARGET",
+        value_parser = record_needs_feature,
+        requires = "watch",
+        help = "Record each watch interval into a database: sqlite:PATH (with the `record` feature)"
+    )]
+    pub record: Option<String>,
+
+    #[cfg(feature = "record")]
+    #[arg(
+        long,
+        value_name = "AGE",
+        value_parser = record::parse_age,
+        requires = "record",
+        help = "Delete recorded snapshots older than AGE (e.g. 30m, 24h, 7d)"
+    )]
+    pub record_max_age: Option<Duration>,
+
+    #[cfg(feature = "record")]
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "record",
+        help = "Keep only the newest N recorded snapshots"
+    )]
+    pub record_max_snapshots: Option<NonZeroUsize>,
+
+    #[cfg(feature = "record")]
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "10",
+        help = "Recorded snapshots per transaction (pending ones are saved on exit)"
+    )]
+    pub record_batch: NonZeroUsize,
+
     #[arg(short, long, help = "Show threads, disk I/O, and open files")]
     pub verbose: bool,
+
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        value_parser = columns::parse_column,
+        help = "Process columns for table, CSV and JSON output (e.g. pid,name,cpu,rss,threads,user,command)",
+        long_help = "Comma-separated process columns for table, CSV and JSON output
+
+Columns: pid, name, cpu, mem, rss, virt, shared, pss, uss, threads, user,
+         command, read, write, files, utime, stime, vcsw, ivcsw, minflt,
+         majflt, nice, priority, policy, cpus, oom_score, oom_score_adj,
+         cgroup, container, unit, cgroupns, ipcns, mntns, netns, pidns,
+         timens, userns, utsns
+
+Columns needing extra details (pss, nice, netns, ...) collect them automatically.
+CSV output keeps the leading timestamp column."
+    )]
+    pub columns: Option<Vec<&'static Column>>,
+
+    #[arg(
+        long,
+        value_name = "PID",
+        help = "List open file descriptors of a process (Linux)"
+    )]
+    pub files: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "PID",
+        help = "List threads of a process with per-thread CPU (Linux)"
+    )]
+    pub threads: Option<u32>,
+
+    #[arg(
+        long,
+        help = "Collect PSS/USS memory from smaps_rollup (Linux, slower)"
+    )]
+    pub smaps: bool,
+
+    #[arg(
+        long,
+        help = "Collect nice, priority, policy, CPU affinity and OOM score (Linux)"
+    )]
+    pub sched: bool,
+
+    #[arg(
+        long,
+        help = "Collect namespace inode numbers (net, pid, mnt, user, ...) (Linux)"
+    )]
+    pub namespaces: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KEY",
+        help = "Aggregate CPU, memory and threads per group (sort by cpu, mem, name, count, threads)"
+    )]
+    pub group_by: Option<GroupBy>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "SCOPE",
+        num_args = 0..=1,
+        default_missing_value = "own",
+        conflicts_with = "proc_root",
+        help = "Make CPU/memory percentages relative to cgroup limits: own (default, e.g. inside a container) or process (Linux)"
+    )]
+    pub cgroup_limits: Option<LimitScope>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "BACKEND",
+        help = "Process collection backend: sysinfo (default, all platforms) or procfs (reads /proc directly, Linux)"
+    )]
+    pub backend: Option<Backend>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Read processes from a /proc tree captured to disk (uses the procfs backend; CPU% averaged since process start)"
+    )]
+    pub proc_root: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Threads collecting per-process details (default: number of CPUs)"
+    )]
+    pub jobs: Option<NonZeroUsize>,
+}
+
+/// Output formats besides JSON, CSV and the human-readable table.
+#[derive(Clone, Debug)]
+pub enum OutputFormat {
+    /// Prometheus text exposition format, for node_exporter's textfile collector or a scraper
+    Prometheus,
+    /// InfluxDB line protocol with nanosecond timestamps
+    Influx,
+    /// logfmt, one record per line
+    Logfmt,
+    /// GitHub-flavored Markdown: system summary and process table
+    Markdown,
+    /// Self-contained HTML report with sortable, color-coded columns
+    Html,
+    /// Arrow IPC stream, one record batch per snapshot (with the `columnar` feature)
+    #[cfg(feature = "columnar")]
+    Arrow,
+    /// User-defined template rendered per process
+    Template(Template),
+}
+
+/// Value parser of `--record` without the `record` feature.
+#[cfg(not(feature = "record"))]
+fn record_needs_feature(_: &str) -> Result<String, String> {
+    Err("recording needs stop built with `--features record`".to_string())
+}
+
+impl OutputFormat {
+    /// Parses a format name, or a template if the value contains `{`; the value parser of `--format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for unknown names and invalid templates.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "prometheus" => Ok(Self::Prometheus),
+            "influx" => Ok(Self::Influx),
+            "logfmt" => Ok(Self::Logfmt),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            #[cfg(feature = "columnar")]
+            "arrow" => Ok(Self::Arrow),
+            #[cfg(not(feature = "columnar"))]
+            "arrow" => Err("arrow output needs stop built with `--features columnar`".to_string()),
+            _ if value.contains('{') => Template::parse(value).map(Self::Template),
+            _ => Err(format!(
+                "unknown format '{value}' (expected prometheus, influx, logfmt, markdown, html, arrow or a template like '{{pid}} {{name}}')"
+            )),
+        }
+    }
+
+    /// Process columns a template references.
+    fn columns(&self) -> Vec<&'static Column> {
+        match self {
+            Self::Template(template) => template.columns().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Subcommands besides the default process listing.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Capture /proc files of all or filtered processes into a tar bundle,
+    /// readable with --proc-root after extraction
+    Capture(capture::CaptureArgs),
+    /// Serve /metrics (Prometheus), /snapshot (JSON) and /healthz over HTTP
+    Serve(serve::ServeArgs),
+    /// Convert an Arrow IPC stream recorded with --format arrow to Parquet
+    #[cfg(feature = "columnar")]
+    Parquet(columnar::ParquetArgs),
 }
 
 /// A snapshot of system and process metrics at a point in time.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
 pub struct SystemSnapshot {
+    /// Version of this JSON layout, bumped when fields are removed or change type
+    #[schemars(extend("const" = SCHEMA_VERSION))]
+    pub schema_version: u32,
+    /// Tool, host and collection details
+    pub meta: SnapshotMeta,
     /// ISO 8601 timestamp (RFC3339)
     pub timestamp: String,
     /// System-wide metrics
@@ -118,8 +486,43 @@ pub struct SystemSnapshot {
     pub processes: Vec<ProcessInfo>,
 }
 
+/// Where and how a snapshot was collected.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct SnapshotMeta {
+    /// Name of the tool that wrote the snapshot
+    pub tool: String,
+    /// Version of the tool
+    pub tool_version: String,
+    /// Host name
+    pub hostname: Option<String>,
+    /// Operating system, e.g. "Linux (Ubuntu 24.04)"
+    pub os: Option<String>,
+    /// Kernel release, e.g. "6.8.0-45-generic"
+    pub kernel: Option<String>,
+    /// Time spent collecting the snapshot, in milliseconds
+    pub collection_ms: f64,
+}
+
+/// JSON Schema (draft 2020-12) of the `--json` snapshot, as printed by
+/// `--print-schema` and published in `schema/snapshot.schema.json`.
+///
+/// Objects reject unknown properties, so output that gains a field without
+/// a schema update fails validation.
+pub fn snapshot_schema() -> schemars::Schema {
+    let deny_unknown = |schema: &mut schemars::Schema| {
+        if schema.get("properties").is_some() {
+            schema.insert("additionalProperties".to_string(), false.into());
+        }
+    };
+    schemars::generate::SchemaSettings::draft2020_12()
+        .for_serialize()
+        .with_transform(schemars::transform::RecursiveTransform(deny_unknown))
+        .into_generator()
+        .into_root_schema_for::<SystemSnapshot>()
+}
+
 /// System-wide metrics (CPU, memory).
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
 pub struct SystemMetrics {
     /// Global CPU usage percentage (0-100)
     pub cpu_usage: f32,
@@ -129,10 +532,28 @@ pub struct SystemMetrics {
     pub memory_used: u64,
     /// Memory usage percentage (0-100)
     pub memory_percent: f32,
+    /// Memory available for new allocations without swapping, in bytes (Linux only)
+    pub memory_available: Option<u64>,
+    /// Page cache in bytes, reclaimable except for shmem (Linux only)
+    pub cached: Option<u64>,
+    /// Block device buffers in bytes (Linux only)
+    pub buffers: Option<u64>,
+    /// Dirty pages waiting for write-back, in bytes (Linux only)
+    pub dirty: Option<u64>,
+    /// Shared memory and tmpfs pages in bytes (Linux only)
+    pub shmem: Option<u64>,
+    /// Huge page pool statistics (Linux only)
+    pub hugepages: Option<HugePages>,
+    /// Pressure stall information (Linux 4.20+ with PSI enabled)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure: Option<PressureStall>,
+    /// Cgroup limits the figures above are relative to (with `--cgroup-limits`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<CgroupLimits>,
 }
 
 /// Information about a single process.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
 pub struct ProcessInfo {
     /// Process ID
     pub pid: u32,
@@ -140,10 +561,20 @@ pub struct ProcessInfo {
     pub name: String,
     /// CPU usage percentage (0-100+)
     pub cpu_percent: f32,
-    /// Memory usage in bytes
+    /// Resident memory (RSS) in bytes
     pub memory_bytes: u64,
     /// Memory usage percentage (0-100)
     pub memory_percent: f32,
+    /// Virtual memory size in bytes
+    pub virtual_bytes: u64,
+    /// Resident memory shared with other processes, in bytes (Linux only)
+    pub shared_bytes: Option<u64>,
+    /// Proportional set size in bytes (with `--smaps`, Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pss_bytes: Option<u64>,
+    /// Unique set size in bytes (with `--smaps`, Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uss_bytes: Option<u64>,
     /// User ID (may be numeric string like "501")
     pub user: String,
     /// Full command line
@@ -156,70 +587,227 @@ pub struct ProcessInfo {
     pub disk_write_bytes: u64,
     /// Number of open file descriptors (None if unavailable)
     pub open_files: Option<usize>,
+    /// Open file descriptors per type (verbose mode, Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fd_types: Option<FdTypeCounts>,
+    /// Cumulative CPU time in user mode, in seconds (Linux only)
+    pub cpu_user_seconds: Option<f64>,
+    /// Cumulative CPU time in kernel mode, in seconds (Linux only)
+    pub cpu_system_seconds: Option<f64>,
+    /// Voluntary context switches, e.g. blocking on I/O (Linux only)
+    pub voluntary_ctx_switches: Option<u64>,
+    /// Involuntary context switches, i.e. preemptions (Linux only)
+    pub involuntary_ctx_switches: Option<u64>,
+    /// Minor page faults (Linux only)
+    pub minor_faults: Option<u64>,
+    /// Major page faults requiring disk I/O (Linux only)
+    pub major_faults: Option<u64>,
+    /// Cgroup path, e.g. `/system.slice/nginx.service` (Linux only)
+    pub cgroup: Option<String>,
+    /// Container the process runs in, if any (Linux only)
+    pub container: Option<ContainerInfo>,
+    /// Systemd unit derived from the cgroup path, e.g. `nginx.service` (Linux only)
+    pub unit: Option<String>,
+    /// Change in cumulative counters since the previous watch interval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deltas: Option<ProcessCounters>,
+    /// Scheduling and OOM killer details (with `--sched`, Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sched: Option<SchedInfo>,
+    /// Namespace inode numbers (with `--namespaces`, Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespaces: Option<Namespaces>,
+    /// Individual threads (verbose mode, Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<Vec<ThreadInfo>>,
 }
 
-/// Collects a snapshot of system and process metrics.
-///
-/// Sleeps for 200ms to allow accurate CPU usage calculation as required by sysinfo.
-///
-/// # Errors
-///
-/// Returns error if system information collection fails.
-pub fn collect_snapshot() -> Result<SystemSnapshot, Box<dyn Error>> {
-    let mut sys = System::new_all();
-
-    std::thread::sleep(std::time::Duration::from_millis(CPU_SAMPLE_INTERVAL_MS));
-    sys.refresh_all();
-
-    let total_memory = sys.total_memory();
-    let used_memory = sys.used_memory();
-    let memory_percent = (used_memory as f64 / total_memory as f64 * 100.0) as f32;
-
-    let global_cpu_usage = sys.global_cpu_usage();
-
-    let processes: Vec<ProcessInfo> = sys
-        .processes()
-        .iter()
-        .map(|(pid, process)| {
-            let cmd_vec: Vec<String> = process
-                .cmd()
-                .iter()
-                .map(|s| s.to_string_lossy().into_owned())
-                .collect();
-
-            let disk_usage = process.disk_usage();
-            let (disk_read, disk_write) =
-                (disk_usage.total_read_bytes, disk_usage.total_written_bytes);
-
-            ProcessInfo {
-                pid: pid.as_u32(),
-                name: process.name().to_string_lossy().into_owned(),
-                cpu_percent: process.cpu_usage(),
-                memory_bytes: process.memory(),
-                memory_percent: (process.memory() as f64 / total_memory as f64 * 100.0) as f32,
-                user: process
-                    .user_id()
-                    .map(|uid| uid.to_string())
-                    .unwrap_or_else(|| "unknown".to_string()),
-                command: cmd_vec.join(" "),
-                thread_count: process.tasks().map(|t| t.len()).unwrap_or(1),
-                disk_read_bytes: disk_read,
-                disk_write_bytes: disk_write,
-                open_files: process.open_files(),
-            }
+impl ProcessInfo {
+    /// Total CPU time (user + system) in seconds, if available.
+    pub fn cpu_total_seconds(&self) -> Option<f64> {
+        Some(self.cpu_user_seconds? + self.cpu_system_seconds?)
+    }
+}
+
+/// Scheduling and OOM killer details of a process.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct SchedInfo {
+    /// Nice value (-20 to 19)
+    pub nice: i32,
+    /// Kernel scheduling priority (negative for real-time policies)
+    pub priority: i32,
+    /// Scheduling policy (other, fifo, rr, batch, idle, deadline)
+    pub policy: SchedPolicy,
+    /// CPUs the process may run on, in list format (e.g. `0-3,8`)
+    pub cpus_allowed: Option<String>,
+    /// OOM killer badness score (0-1000, higher is killed first)
+    pub oom_score: Option<i32>,
+    /// OOM score adjustment (-1000 to 1000)
+    pub oom_score_adj: Option<i32>,
+}
+
+/// Cumulative per-process counters, or their change over a watch interval.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProcessCounters {
+    pub cpu_user_seconds: f64,
+    pub cpu_system_seconds: f64,
+    pub voluntary_ctx_switches: u64,
+    pub involuntary_ctx_switches: u64,
+    pub minor_faults: u64,
+    pub major_faults: u64,
+}
+
+impl ProcessCounters {
+    /// Extracts the counters of a process, or `None` if any are unavailable.
+    pub fn of(process: &ProcessInfo) -> Option<Self> {
+        Some(Self {
+            cpu_user_seconds: process.cpu_user_seconds?,
+            cpu_system_seconds: process.cpu_system_seconds?,
+            voluntary_ctx_switches: process.voluntary_ctx_switches?,
+            involuntary_ctx_switches: process.involuntary_ctx_switches?,
+            minor_faults: process.minor_faults?,
+            major_faults: process.major_faults?,
         })
-        .collect();
-
-    Ok(SystemSnapshot {
-        timestamp: chrono::Utc::now().to_rfc3339(),
-        system: SystemMetrics {
-            cpu_usage: global_cpu_usage,
-            memory_total: total_memory,
-            memory_used: used_memory,
-            memory_percent,
-        },
-        processes,
-    })
+    }
+
+    /// Change from `earlier` to `self`. Counters never decrease for a live
+    /// process, so a decrease (PID reuse) saturates at zero.
+    pub fn since(&self, earlier: &Self) -> Self {
+        // CPU times have clock tick (10ms) resolution; round away float noise
+        let seconds = |now: f64, before: f64| ((now - before).max(0.0) * 100.0).round() / 100.0;
+        Self {
+            cpu_user_seconds: seconds(self.cpu_user_seconds, earlier.cpu_user_seconds),
+            cpu_system_seconds: seconds(self.cpu_system_seconds, earlier.cpu_system_seconds),
+            voluntary_ctx_switches: self
+                .voluntary_ctx_switches
+                .saturating_sub(earlier.voluntary_ctx_switches),
+            involuntary_ctx_switches: self
+                .involuntary_ctx_switches
+                .saturating_sub(earlier.involuntary_ctx_switches),
+            minor_faults: self.minor_faults.saturating_sub(earlier.minor_faults),
+            major_faults: self.major_faults.saturating_sub(earlier.major_faults),
+        }
+    }
+}
+
+/// Optional per-process details that are more expensive to collect.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CollectOptions {
+    /// Classify open file descriptors by type
+    pub fd_types: bool,
+    /// Read PSS/USS from smaps_rollup
+    pub smaps: bool,
+    /// Read scheduling policy, affinity and OOM scores
+    pub sched: bool,
+    /// Read namespace inode numbers
+    pub namespaces: bool,
+    /// Read CPU times, context switches, page faults and shared memory
+    pub counters: bool,
+    /// Read cgroup membership (path, container, systemd unit)
+    pub cgroups: bool,
+    /// List individual threads
+    pub threads: bool,
+    /// Leave out threads that sysinfo lists as processes (Linux), for grouping
+    pub skip_threads: bool,
+    /// Make percentages relative to cgroup limits
+    pub limits: Option<LimitScope>,
+    /// Threads reading per-process details (1 or less reads serially)
+    pub jobs: usize,
+}
+
+impl CollectOptions {
+    /// Derives which optional details are needed for the given arguments.
+    pub fn from_args(args: &Args) -> Self {
+        // Invalid filters are reported when the filter is parsed for matching
+        let filter = args
+            .filter
+            .as_deref()
+            .and_then(|f| FilterExpr::parse(f).ok());
+        let shown = Self::shown_columns(args);
+        // Opt-in details are collected for every field only when asked for
+        let shows = |detail: Detail| shown.as_ref().is_some_and(|c| columns::needs(c, detail));
+        let shows_or_all =
+            |detail: Detail| shown.as_ref().is_none_or(|c| columns::needs(c, detail));
+        let options = Self {
+            fd_types: args.verbose || shows(Detail::FdTypes),
+            smaps: args.smaps || shows(Detail::Smaps),
+            sched: args.sched || shows(Detail::Sched),
+            namespaces: args.namespaces
+                || shows(Detail::Namespaces)
+                || args.group_by == Some(GroupBy::Netns),
+            counters: shows_or_all(Detail::Counters),
+            cgroups: shows_or_all(Detail::Cgroup)
+                || args.group_by.is_some()
+                || args.cgroup_limits.is_some(),
+            threads: args.verbose,
+            skip_threads: args.group_by.is_some(),
+            limits: args.cgroup_limits,
+            jobs: args.jobs.map_or_else(
+                || std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+                NonZeroUsize::get,
+            ),
+        };
+        options.selecting(filter.as_ref(), args.sort_by.as_deref().unwrap_or(""))
+    }
+
+    /// Process columns the output shows, or `None` if it has every field
+    /// (JSON, CSV and the line formats without `--columns`, Prometheus,
+    /// `serve` and recording).
+    fn shown_columns(args: &Args) -> Option<Vec<&'static Column>> {
+        if args.record.is_some()
+            || matches!(args.command, Some(Command::Serve(_)))
+            || (!args.json && !args.csv && matches!(args.format, Some(OutputFormat::Prometheus)))
+        {
+            return None;
+        }
+        // Groups only show aggregates
+        if args.group_by.is_some() {
+            return Some(Vec::new());
+        }
+        let selected = args.columns.as_deref();
+        if args.json || args.csv {
+            return selected.map(<[_]>::to_vec);
+        }
+        match &args.format {
+            Some(format @ OutputFormat::Template(_)) => Some(
+                format
+                    .columns()
+                    .into_iter()
+                    .chain(selected.into_iter().flatten().copied())
+                    .collect(),
+            ),
+            Some(OutputFormat::Markdown | OutputFormat::Html) => {
+                Some(report::report_columns(selected))
+            }
+            Some(_) => selected.map(<[_]>::to_vec),
+            None => Some(
+                selected.map_or_else(|| columns::default_table(&[], args.verbose), <[_]>::to_vec),
+            ),
+        }
+    }
+
+    /// Adds the details that `filter` and the sort key `sort_by` depend on.
+    pub fn selecting(mut self, filter: Option<&FilterExpr>, sort_by: &str) -> Self {
+        let sort_by = sort_by.to_lowercase();
+        let filter_uses =
+            |pred: fn(&FilterField) -> bool| filter.is_some_and(|f| f.uses_field(pred));
+        self.smaps |= sort_by == "pss" || sort_by == "uss";
+        self.sched |= filter_uses(FilterField::is_sched);
+        self.namespaces |= filter_uses(FilterField::is_namespace);
+        self.counters |= filter_uses(FilterField::is_counter)
+            || matches!(
+                sort_by.as_str(),
+                "cputime" | "utime" | "stime" | "vcsw" | "ivcsw" | "minflt" | "majflt"
+            );
+        self.cgroups |= filter_uses(FilterField::is_cgroup);
+        self
+    }
+}
+
+/// Whether a process name or command contains `search_lower` (already lowercased).
+pub fn matches_search(process: &ProcessInfo, search_lower: &str) -> bool {
+    process.name.to_lowercase().contains(search_lower)
+        || process.command.to_lowercase().contains(search_lower)
 }
 
 /// Escapes a field for CSV output according to RFC 4180.
@@ -236,58 +824,58 @@ pub fn escape_csv_field(field: &str) -> Cow<'_, str> {
     }
 }
 
-/// Outputs the CSV header row with all column names.
+/// CSV fields of the selected `columns`, or the default layout.
+fn csv_fields(columns: Option<&[&'static Column]>) -> Vec<columns::CsvField> {
+    columns.map_or_else(columns::default_csv, |columns| {
+        columns
+            .iter()
+            .copied()
+            .map(columns::CsvField::column)
+            .collect()
+    })
+}
+
+/// Outputs the CSV header row with all column names, or with the selected `columns`.
 ///
 /// # Errors
 ///
 /// Returns error if writing to stdout fails.
-pub fn output_csv_header() -> io::Result<()> {
-    writeln!(
-        io::stdout(),
-        "timestamp,cpu_usage,memory_total,memory_used,memory_percent,pid,name,cpu_percent,memory_bytes,memory_percent_process,user,command,thread_count,disk_read_bytes,disk_write_bytes,open_files"
-    )?;
-    io::stdout().flush()
+pub fn output_csv_header(columns: Option<&[&'static Column]>) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    columns::write_csv_header(&mut stdout, &csv_fields(columns))?;
+    stdout.flush()
 }
 
-/// Outputs CSV rows for all processes in the snapshot.
+/// Outputs CSV rows for all processes in the snapshot, optionally limited to `columns`.
 ///
 /// # Errors
 ///
 /// Returns error if writing to stdout fails.
-pub fn output_csv_rows(snapshot: &SystemSnapshot) -> io::Result<()> {
+pub fn output_csv_rows(
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+) -> io::Result<()> {
     let mut stdout = io::stdout();
-    for process in &snapshot.processes {
-        let open_files_str = process
-            .open_files
-            .map(|n| n.to_string())
-            .unwrap_or_default();
-        writeln!(
-            stdout,
-            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
-            escape_csv_field(&snapshot.timestamp),
-            snapshot.system.cpu_usage,
-            snapshot.system.memory_total,
-            snapshot.system.memory_used,
-            snapshot.system.memory_percent,
-            process.pid,
-            escape_csv_field(&process.name),
-            process.cpu_percent,
-            process.memory_bytes,
-            process.memory_percent,
-            escape_csv_field(&process.user),
-            escape_csv_field(&process.command),
-            process.thread_count,
-            process.disk_read_bytes,
-            process.disk_write_bytes,
-            open_files_str
-        )?;
-    }
+    columns::write_csv_rows(&mut stdout, snapshot, &csv_fields(columns))?;
     stdout.flush()
 }
 
-fn output_csv(snapshot: &SystemSnapshot) -> io::Result<()> {
-    output_csv_header()?;
-    output_csv_rows(snapshot)
+fn output_csv(snapshot: &SystemSnapshot, columns: Option<&[&'static Column]>) -> io::Result<()> {
+    output_csv_header(columns)?;
+    output_csv_rows(snapshot, columns)
+}
+
+/// Process sort keys accepted by `sort_processes`, besides the aliases
+/// `memory` and `virtual`.
+pub const SORT_KEYS: &[&str] = &[
+    "cpu", "mem", "pid", "name", "virt", "pss", "uss", "cputime", "utime", "stime", "vcsw",
+    "ivcsw", "minflt", "majflt",
+];
+
+/// Whether `sort_by` is a process sort key (case-insensitive).
+pub fn is_sort_key(sort_by: &str) -> bool {
+    let sort_by = sort_by.to_lowercase();
+    matches!(sort_by.as_str(), "memory" | "virtual") || SORT_KEYS.contains(&sort_by.as_str())
 }
 
 /// Sorts processes in-place by the specified metric.
@@ -295,7 +883,9 @@ fn output_csv(snapshot: &SystemSnapshot) -> io::Result<()> {
 /// # Arguments
 ///
 /// * `processes` - Mutable slice of processes to sort
-/// * `sort_by` - Sort key: "cpu", "mem"/"memory", "pid", or "name" (case-insensitive)
+/// * `sort_by` - Sort key: "cpu", "mem"/"memory", "pid", "name", "virt", "pss", "uss",
+///   "cputime", "utime", "stime", "vcsw", "ivcsw", "minflt" or "majflt" (case-insensitive).
+///   Processes missing the sorted metric sort last.
 ///
 /// Defaults to CPU descending if an unknown sort key is provided.
 pub fn sort_processes(processes: &mut [ProcessInfo], sort_by: &str) {
@@ -312,9 +902,20 @@ pub fn sort_processes(processes: &mut [ProcessInfo], sort_by: &str) {
         }),
         "pid" => processes.sort_by_key(|p| p.pid),
         "name" => processes.sort_by_cached_key(|p| p.name.to_lowercase()),
+        "virt" | "virtual" => processes.sort_by_key(|p| std::cmp::Reverse(p.virtual_bytes)),
+        "pss" => processes.sort_by_key(|p| std::cmp::Reverse(p.pss_bytes)),
+        "uss" => processes.sort_by_key(|p| std::cmp::Reverse(p.uss_bytes)),
+        "cputime" => sort_by_seconds(processes, ProcessInfo::cpu_total_seconds),
+        "utime" => sort_by_seconds(processes, |p| p.cpu_user_seconds),
+        "stime" => sort_by_seconds(processes, |p| p.cpu_system_seconds),
+        "vcsw" => processes.sort_by_key(|p| std::cmp::Reverse(p.voluntary_ctx_switches)),
+        "ivcsw" => processes.sort_by_key(|p| std::cmp::Reverse(p.involuntary_ctx_switches)),
+        "minflt" => processes.sort_by_key(|p| std::cmp::Reverse(p.minor_faults)),
+        "majflt" => processes.sort_by_key(|p| std::cmp::Reverse(p.major_faults)),
         _ => {
             eprintln!(
-                "Warning: Unknown sort field '{sort_by}', using 'cpu'. Valid: cpu, mem, pid, name"
+                "Warning: Unknown sort field '{sort_by}', using 'cpu'. Valid: {}",
+                SORT_KEYS.join(", ")
             );
             processes.sort_by(|a, b| {
                 b.cpu_percent
@@ -325,23 +926,20 @@ pub fn sort_processes(processes: &mut [ProcessInfo], sort_by: &str) {
     }
 }
 
-/// Outputs snapshot in human-readable format with colors and formatting.
-///
-/// Displays system metrics, filter info, and a table of processes with
-/// color-coded CPU and memory usage.
+/// Sorts descending by an optional seconds value, with missing values last.
+fn sort_by_seconds(processes: &mut [ProcessInfo], key: impl Fn(&ProcessInfo) -> Option<f64>) {
+    processes.sort_by(|a, b| match (key(a), key(b)) {
+        (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+}
+
+/// Writes the tool banner and the color-coded system CPU and memory summary.
 ///
 /// # Errors
 ///
-/// Returns error if writing to stdout fails.
-pub fn output_human_readable(
-    snapshot: &SystemSnapshot,
-    search_term: Option<&String>,
-    filter_expr: Option<&String>,
-    sort_by: &str,
-    limit: usize,
-    verbose: bool,
-) -> io::Result<()> {
-    let mut stdout = io::stdout();
+/// Returns error if writing fails.
+pub fn write_system_header(stdout: &mut impl Write, system: &SystemMetrics) -> io::Result<()> {
     writeln!(
         stdout,
         "{} {}",
@@ -352,33 +950,92 @@ pub fn output_human_readable(
     writeln!(stdout, "{}", "System:".bold())?;
 
     // Color code CPU based on usage
-    let cpu_value = snapshot.system.cpu_usage;
-    let cpu_display = if cpu_value > 80.0 {
-        format!("{cpu_value:.1}%").red().to_string()
-    } else if cpu_value > 50.0 {
-        format!("{cpu_value:.1}%").yellow().to_string()
-    } else {
-        format!("{cpu_value:.1}%").green().to_string()
+    let cpu_value = system.cpu_usage;
+    let cpu_str = format!("{cpu_value:.1}%");
+    let cpu_display = match SYSTEM_CPU.level(cpu_value) {
+        Level::High => cpu_str.red().to_string(),
+        Level::Warn => cpu_str.yellow().to_string(),
+        Level::Normal => cpu_str.green().to_string(),
     };
-    writeln!(stdout, "  CPU: {cpu_display}")?;
+    let limits = system.limits.as_ref();
+    match limits.and_then(|l| l.cpu_limit_cores) {
+        Some(cores) => writeln!(
+            stdout,
+            "  CPU: {cpu_display} {}",
+            format!("(of {cores:.2} cores, cgroup limit)").dimmed()
+        )?,
+        None => writeln!(stdout, "  CPU: {cpu_display}")?,
+    }
 
     // Color code memory based on usage
-    let mem_value = snapshot.system.memory_percent;
+    let mem_value = system.memory_percent;
     let mem_str = format!(
         "{:.1}% ({} / {} MB)",
         mem_value,
-        snapshot.system.memory_used / 1024 / 1024,
-        snapshot.system.memory_total / 1024 / 1024
+        system.memory_used / 1024 / 1024,
+        system.memory_total / 1024 / 1024
     );
-    let mem_display = if mem_value > 80.0 {
-        mem_str.red().to_string()
-    } else if mem_value > 60.0 {
-        mem_str.yellow().to_string()
-    } else {
-        mem_str.green().to_string()
+    let mem_display = match SYSTEM_MEMORY.level(mem_value) {
+        Level::High => mem_str.red().to_string(),
+        Level::Warn => mem_str.yellow().to_string(),
+        Level::Normal => mem_str.green().to_string(),
     };
-    writeln!(stdout, "  Memory: {mem_display}")?;
-    writeln!(stdout)?;
+    if limits.is_some_and(|l| l.memory_limit_bytes.is_some()) {
+        writeln!(
+            stdout,
+            "  Memory: {mem_display} {}",
+            "(cgroup limit)".dimmed()
+        )?;
+    } else if let Some(available) = system.memory_available {
+        // Page cache counts as neither used nor unavailable: it is reclaimed on demand
+        let cache = system.cached.unwrap_or(0) + system.buffers.unwrap_or(0);
+        writeln!(
+            stdout,
+            "  Memory: {mem_display} | {} MB available {}",
+            available / 1024 / 1024,
+            format!(
+                "(cache {} MB, dirty {} MB)",
+                cache / 1024 / 1024,
+                system.dirty.unwrap_or(0) / 1024 / 1024
+            )
+            .dimmed()
+        )?;
+    } else {
+        writeln!(stdout, "  Memory: {mem_display}")?;
+    }
+
+    if let Some(pressure) = &system.pressure {
+        writeln!(
+            stdout,
+            "  Pressure: CPU {} | Memory {} | I/O {} {}",
+            format_pressure(pressure.cpu.as_ref(), 0),
+            format_pressure(pressure.memory.as_ref(), 0),
+            format_pressure(pressure.io.as_ref(), 0),
+            "(some, avg10)".dimmed()
+        )?;
+    }
+    writeln!(stdout)
+}
+
+/// Outputs snapshot in human-readable format with colors and formatting.
+///
+/// Displays system metrics, filter info, and a table of processes with
+/// color-coded CPU and memory usage.
+///
+/// # Errors
+///
+/// Returns error if writing to stdout fails.
+pub fn output_human_readable(
+    snapshot: &SystemSnapshot,
+    search_term: Option<&String>,
+    filter_expr: Option<&String>,
+    sort_by: &str,
+    limit: usize,
+    verbose: bool,
+    columns: Option<&[&'static Column]>,
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write_system_header(&mut stdout, &snapshot.system)?;
 
     if let Some(search) = search_term {
         writeln!(stdout, "{} {}", "Search:".bold(), search.cyan())?;
@@ -397,111 +1054,88 @@ pub fn output_human_readable(
     )?;
     writeln!(stdout)?;
 
-    if verbose {
-        writeln!(
-            stdout,
-            "{:<8} {:<20} {:>8} {:>8} {:>7} {:>8} {:>8} {:>7}",
-            "PID".bold(),
-            "Name".bold(),
-            "CPU%".bold(),
-            "Mem%".bold(),
-            "Threads".bold(),
-            "Read".bold(),
-            "Write".bold(),
-            "Files".bold()
-        )?;
-        writeln!(stdout, "{}", "─".repeat(93).dimmed())?;
-    } else {
-        writeln!(
-            stdout,
-            "{:<8} {:<20} {:>8} {:>8} {:<10}",
-            "PID".bold(),
-            "Name".bold(),
-            "CPU%".bold(),
-            "Mem%".bold(),
-            "User".bold()
-        )?;
-        writeln!(stdout, "{}", "─".repeat(70).dimmed())?;
-    }
-
-    for process in &snapshot.processes {
-        // Color code CPU usage
-        let cpu_str = format!("{:>7.1}%", process.cpu_percent);
-        let cpu_display = if process.cpu_percent > 50.0 {
-            cpu_str.red().to_string()
-        } else if process.cpu_percent > 20.0 {
-            cpu_str.yellow().to_string()
-        } else {
-            cpu_str.to_string()
-        };
-
-        // Color code memory usage
-        let mem_str = format!("{:>7.1}%", process.memory_percent);
-        let mem_display = if process.memory_percent > 5.0 {
-            mem_str.red().to_string()
-        } else if process.memory_percent > 2.0 {
-            mem_str.yellow().to_string()
-        } else {
-            mem_str.to_string()
-        };
-
-        if verbose {
-            let (read_val, read_unit) = format_bytes_parts(process.disk_read_bytes);
-            let (write_val, write_unit) = format_bytes_parts(process.disk_write_bytes);
-            let open_files_str = process
-                .open_files
-                .map(|f| f.to_string())
-                .unwrap_or_else(|| "-".to_string());
-
-            // Format disk I/O with right-aligned numbers and dimmed units
-            // Width: 6 chars for number + 1 space + 1 char for unit = 8 total
-            let read_formatted = format!("{:>6} {}", read_val, read_unit.dimmed());
-            let write_formatted = format!("{:>6} {}", write_val, write_unit.dimmed());
-
-            writeln!(
-                stdout,
-                "{:<8} {:<20} {} {} {:>7} {} {} {:>7}",
-                process.pid.to_string().cyan(),
-                &process.name[..process.name.len().min(20)],
-                cpu_display,
-                mem_display,
-                process.thread_count,
-                read_formatted,
-                write_formatted,
-                open_files_str
-            )?;
-        } else {
-            let user_str = &process.user[..process.user.len().min(10)];
-            let user_display = user_str.dimmed();
-            writeln!(
-                stdout,
-                "{:<8} {:<20} {} {} {:<10}",
-                process.pid.to_string().cyan(),
-                &process.name[..process.name.len().min(20)],
-                cpu_display,
-                mem_display,
-                user_display
-            )?;
-        }
-    }
+    let columns = columns.map_or_else(
+        || columns::default_table(&snapshot.processes, verbose),
+        <[_]>::to_vec,
+    );
+    columns::write_table(&mut stdout, &snapshot.processes, &columns)?;
     stdout.flush()
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    // The compact and line modes are JSON output with another layout
+    args.json |= args.json_compact || args.json_lines;
 
     // Validate interval
     if args.interval < 0.2 {
         eprintln!("Warning: Interval below 0.2s may cause high CPU usage");
     }
 
+    if args.print_schema {
+        println!("{}", serde_json::to_string_pretty(&snapshot_schema())?);
+        return Ok(());
+    }
+
+    if args.proc_root.is_some() && args.backend == Some(Backend::Sysinfo) {
+        return Err("--proc-root is read with the procfs backend and cannot be combined with --backend sysinfo".into());
+    }
+
+    match &args.command {
+        Some(Command::Capture(capture)) => return capture::capture_mode(&args, capture),
+        Some(Command::Serve(serve)) => return serve::serve_mode(&args, serve),
+        #[cfg(feature = "columnar")]
+        Some(Command::Parquet(parquet)) => return columnar::parquet_mode(parquet),
+        None => {}
+    }
+
+    // Open file detail mode
+    if let Some(pid) = args.files {
+        return fds::files_mode(&args, pid);
+    }
+
+    // Thread detail mode
+    if let Some(pid) = args.threads {
+        return threads::threads_mode(&args, pid);
+    }
+
+    if let Some(format @ (OutputFormat::Prometheus | OutputFormat::Markdown | OutputFormat::Html)) =
+        &args.format
+        && args.watch
+    {
+        return Err(match format {
+            OutputFormat::Prometheus => {
+                "--format prometheus cannot be combined with --watch (use `stop serve` for scraping)"
+            }
+            _ => "--format markdown and html are single reports and cannot be combined with --watch",
+        }
+        .into());
+    }
+    #[cfg(feature = "columnar")]
+    if matches!(args.format, Some(OutputFormat::Arrow))
+        && io::IsTerminal::is_terminal(&io::stdout())
+    {
+        return Err("--format arrow writes binary data; redirect it to a file or pipe".into());
+    }
+    if args.format.is_some() && args.group_by.is_some() {
+        return Err("--format cannot be combined with --group-by".into());
+    }
+    if args.record.is_some() && args.group_by.is_some() {
+        return Err("--record cannot be combined with --group-by".into());
+    }
+    if (args.header_template.is_some() || args.footer_template.is_some())
+        && !matches!(args.format, Some(OutputFormat::Template(_)))
+    {
+        return Err("--header-template and --footer-template need a template --format".into());
+    }
+
     // Watch mode
     if args.watch {
         return watch::watch_mode(&args);
     }
 
     // Single snapshot mode
-    let mut snapshot = collect_snapshot()?;
+    let mut snapshot = collector::from_args(&args).collect(&CollectOptions::from_args(&args))?;
 
     // Parse filter if provided
     let filter = if let Some(filter_expr_str) = &args.filter {
@@ -541,8 +1175,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             if p.pid == current_pid {
                 return false; // Always exclude current process from search results
             }
-            p.name.to_lowercase().contains(&search_lower)
-                || p.command.to_lowercase().contains(&search_lower)
+            matches_search(p, &search_lower)
         });
     }
 
@@ -551,38 +1184,112 @@ fn main() -> Result<(), Box<dyn Error>> {
         snapshot.processes.retain(|p| f.matches(p));
     }
 
-    // Apply sorting
     let sort_by = args.sort_by.as_deref().unwrap_or("cpu");
-    sort_processes(&mut snapshot.processes, sort_by);
-
-    // Apply top-n limit
     let limit = args.top_n.unwrap_or(DEFAULT_TOP_N);
-    snapshot.processes.truncate(limit);
 
-    // Output with graceful broken pipe handling
-    let result = if args.json {
-        writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&snapshot)?)
-            .and_then(|_| io::stdout().flush())
+    // Aggregate, sort and limit groups instead of processes
+    let result = if let Some(group_by) = args.group_by {
+        let mut grouped =
+            GroupedSnapshot::from_snapshot(snapshot, group_by, collector::cgroup_root(&args));
+        group::sort_groups(&mut grouped.groups, sort_by);
+        grouped.groups.truncate(limit);
+        output_grouped(&args, &grouped, sort_by)
+    } else {
+        sort_processes(&mut snapshot.processes, sort_by);
+        snapshot.processes.truncate(limit);
+        output_snapshot(&args, &snapshot, sort_by, limit)
+    };
+
+    // Exit gracefully on broken pipe (e.g., piping to head)
+    if let Err(e) = result {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            return Ok(());
+        }
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// Writes a single snapshot in the output format selected by `args`.
+fn output_snapshot(
+    args: &Args,
+    snapshot: &SystemSnapshot,
+    sort_by: &str,
+    limit: usize,
+) -> io::Result<()> {
+    let columns = args.columns.as_deref();
+    if args.json {
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        if args.json_lines {
+            json::write_lines(&mut out, snapshot, columns)?;
+        } else {
+            json::write_snapshot(&mut out, snapshot, columns, args.json_compact)?;
+        }
+        out.flush()
     } else if args.csv {
-        output_csv(&snapshot)
+        output_csv(snapshot, columns)
+    } else if let Some(format) = &args.format {
+        output_format(args, format, snapshot)
     } else {
         output_human_readable(
-            &snapshot,
+            snapshot,
             args.search.as_ref(),
             args.filter.as_ref(),
             sort_by,
             limit,
             args.verbose,
+            columns,
         )
-    };
+    }
+}
 
-    // Exit gracefully on broken pipe (e.g., piping to head)
-    if let Err(e) = result {
-        if e.kind() == io::ErrorKind::BrokenPipe {
-            return Ok(());
+/// Writes a snapshot in one of the `--format` output formats.
+///
+/// # Errors
+///
+/// Returns error if writing to stdout fails.
+pub fn output_format(
+    args: &Args,
+    format: &OutputFormat,
+    snapshot: &SystemSnapshot,
+) -> io::Result<()> {
+    let columns = args.columns.as_deref();
+    let sort_by = args.sort_by.as_deref().unwrap_or("cpu");
+    let mut stdout = io::stdout().lock();
+    match format {
+        OutputFormat::Prometheus => prometheus::write_metrics(&mut stdout, snapshot)?,
+        OutputFormat::Influx => influx::write_snapshot(&mut stdout, snapshot, columns)?,
+        OutputFormat::Logfmt => logfmt::write_snapshot(&mut stdout, snapshot, columns)?,
+        OutputFormat::Markdown => report::write_markdown(&mut stdout, snapshot, columns, sort_by)?,
+        OutputFormat::Html => report::write_html(&mut stdout, snapshot, columns, sort_by)?,
+        #[cfg(feature = "columnar")]
+        OutputFormat::Arrow => {
+            let mut stream = columnar::ArrowStream::new(&mut stdout, columns)?;
+            stream.write(snapshot)?;
+            stream.finish()?;
         }
-        return Err(e.into());
+        OutputFormat::Template(template) => template::write_snapshot(
+            &mut stdout,
+            snapshot,
+            template,
+            args.header_template.as_ref(),
+            args.footer_template.as_ref(),
+        )?,
     }
+    stdout.flush()
+}
 
-    Ok(())
+/// Writes a grouped snapshot in the output format selected by `args`.
+fn output_grouped(args: &Args, grouped: &GroupedSnapshot, sort_by: &str) -> io::Result<()> {
+    if args.json {
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        json::write_value(&mut out, grouped, args.json_compact)?;
+        out.flush()
+    } else if args.csv {
+        group::output_groups_csv_header()?;
+        group::output_groups_csv_rows(grouped)
+    } else {
+        group::output_groups_human(grouped, args.filter.as_ref(), sort_by)
+    }
 }
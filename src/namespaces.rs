@@ -0,0 +1,71 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Namespace inode numbers of a process, from the `/proc/<pid>/ns/*` links.
+///
+/// Processes share a namespace exactly when the inode numbers are equal.
+/// Each entry is `None` if the kernel lacks that namespace type or the link
+/// could not be read (reading another user's links requires ptrace access).
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Namespaces {
+    pub cgroup: Option<u64>,
+    pub ipc: Option<u64>,
+    pub mnt: Option<u64>,
+    pub net: Option<u64>,
+    pub pid: Option<u64>,
+    pub time: Option<u64>,
+    pub user: Option<u64>,
+    pub uts: Option<u64>,
+}
+
+/// Parses a namespace link target like `net:[4026531840]` into its inode number.
+pub fn parse_ns_link(target: &str) -> Option<u64> {
+    target.split_once(":[")?.1.strip_suffix(']')?.parse().ok()
+}
+
+/// Reads all namespace links of a process, or `None` if none are readable.
+pub fn read_namespaces(proc_root: &Path, pid: u32) -> Option<Namespaces> {
+    let ns_dir = proc_root.join(pid.to_string()).join("ns");
+    let read = |name: &str| {
+        std::fs::read_link(ns_dir.join(name))
+            .ok()
+            .and_then(|target| parse_ns_link(&target.to_string_lossy()))
+    };
+    let namespaces = Namespaces {
+        cgroup: read("cgroup"),
+        ipc: read("ipc"),
+        mnt: read("mnt"),
+        net: read("net"),
+        pid: read("pid"),
+        time: read("time"),
+        user: read("user"),
+        uts: read("uts"),
+    };
+    (namespaces != Namespaces::default()).then_some(namespaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ns_link() {
+        assert_eq!(parse_ns_link("net:[4026531840]"), Some(4026531840));
+        assert_eq!(
+            parse_ns_link("pid_for_children:[4026532201]"),
+            Some(4026532201)
+        );
+        assert_eq!(parse_ns_link("net:4026531840"), None);
+        assert_eq!(parse_ns_link("socket:[]"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_own_namespaces() {
+        let namespaces = read_namespaces(Path::new("/proc"), std::process::id()).unwrap();
+        assert!(namespaces.net.is_some());
+        assert!(namespaces.pid.is_some());
+        assert!(namespaces.mnt.is_some());
+    }
+}
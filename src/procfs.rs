@@ -0,0 +1,591 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Kernel clock ticks per second (`USER_HZ`) used for times in `/proc/<pid>/stat`.
+///
+/// This is part of the procfs ABI and is 100 on all mainstream architectures.
+const CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+/// Reads `<proc_root>/<pid>/<file>` into a string.
+///
+/// # Errors
+///
+/// Returns error if the process is gone or the file cannot be read.
+pub fn read_pid_file(proc_root: &Path, pid: u32, file: &str) -> io::Result<String> {
+    fs::read_to_string(proc_root.join(pid.to_string()).join(file))
+}
+
+/// Parses a `Key:   1234 kB` line as found in `status`, `smaps_rollup` and
+/// `meminfo`, returning the value in bytes.
+pub fn parse_kb_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let rest = line.strip_prefix(key)?.strip_prefix(':')?;
+        let kb: u64 = rest.split_whitespace().next()?.parse().ok()?;
+        Some(kb * 1024)
+    })
+}
+
+/// Parses a `Key:\tvalue` line holding a plain number (e.g. context switch counts).
+pub fn parse_u64_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let rest = line.strip_prefix(key)?.strip_prefix(':')?;
+        rest.trim().parse().ok()
+    })
+}
+
+/// Fields of interest from `/proc/<pid>/stat`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcStat {
+    /// Scheduler state code (`R`, `S`, `D`, `Z`, ...)
+    pub state: char,
+    /// Minor page faults (served without disk I/O)
+    pub minor_faults: u64,
+    /// Major page faults (required loading a page from disk)
+    pub major_faults: u64,
+    /// CPU time spent in user mode, in seconds
+    pub user_seconds: f64,
+    /// CPU time spent in kernel mode, in seconds
+    pub system_seconds: f64,
+    /// Kernel scheduling priority (negative for real-time policies)
+    pub priority: i32,
+    /// Nice value (-20 to 19)
+    pub nice: i32,
+    /// Scheduling policy
+    pub policy: SchedPolicy,
+    /// Time the process started after system boot, in seconds
+    pub start_seconds: f64,
+}
+
+impl ProcStat {
+    /// Parses the single line of `/proc/<pid>/stat`.
+    ///
+    /// The command name (field 2) is wrapped in parentheses and may itself
+    /// contain spaces or parentheses, so fields are counted from the last `)`.
+    pub fn parse(content: &str) -> Option<Self> {
+        let rest = &content[content.rfind(')')? + 1..];
+        // fields[0] is field 3 (state) in proc(5) numbering
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |n: usize| -> Option<u64> { fields.get(n - 3)?.parse().ok() };
+        let signed = |n: usize| -> Option<i32> { fields.get(n - 3)?.parse().ok() };
+
+        Some(Self {
+            state: fields.first()?.chars().next()?,
+            minor_faults: field(10)?,
+            major_faults: field(12)?,
+            user_seconds: field(14)? as f64 / CLOCK_TICKS_PER_SEC,
+            system_seconds: field(15)? as f64 / CLOCK_TICKS_PER_SEC,
+            priority: signed(18)?,
+            nice: signed(19)?,
+            policy: field(41).map_or(SchedPolicy::Unknown, SchedPolicy::from_raw),
+            start_seconds: field(22)? as f64 / CLOCK_TICKS_PER_SEC,
+        })
+    }
+}
+
+/// Extracts the command name (field 2, without parentheses) from `/proc/<pid>/stat`.
+pub fn parse_stat_comm(content: &str) -> Option<&str> {
+    content.get(content.find('(')? + 1..content.rfind(')')?)
+}
+
+/// Linux scheduling policy (`SCHED_*`) of a process.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedPolicy {
+    /// SCHED_OTHER (default time-sharing)
+    Other,
+    /// SCHED_FIFO (real-time, first in first out)
+    Fifo,
+    /// SCHED_RR (real-time, round robin)
+    Rr,
+    /// SCHED_BATCH (CPU-bound, non-interactive)
+    Batch,
+    /// SCHED_IDLE (very low priority background)
+    Idle,
+    /// SCHED_DEADLINE (earliest deadline first)
+    Deadline,
+    /// Unrecognized or unreported policy
+    Unknown,
+}
+
+impl SchedPolicy {
+    fn from_raw(value: u64) -> Self {
+        match value {
+            0 => Self::Other,
+            1 => Self::Fifo,
+            2 => Self::Rr,
+            3 => Self::Batch,
+            5 => Self::Idle,
+            6 => Self::Deadline,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Whether this is a real-time policy (FIFO, RR or deadline).
+    pub fn is_realtime(&self) -> bool {
+        matches!(self, Self::Fifo | Self::Rr | Self::Deadline)
+    }
+
+    /// Short lowercase name as used by `chrt`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Fifo => "fifo",
+            Self::Rr => "rr",
+            Self::Batch => "batch",
+            Self::Idle => "idle",
+            Self::Deadline => "deadline",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Reads and parses `/proc/<pid>/stat`.
+pub fn read_stat(proc_root: &Path, pid: u32) -> Option<ProcStat> {
+    read_pid_file(proc_root, pid, "stat")
+        .ok()
+        .and_then(|content| ProcStat::parse(&content))
+}
+
+/// Fields of interest from `/proc/<pid>/status`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcStatus {
+    /// Resident set size in bytes (`VmRSS`, absent for kernel threads)
+    pub rss_bytes: Option<u64>,
+    /// Virtual memory size in bytes (`VmSize`)
+    pub virtual_bytes: Option<u64>,
+    /// Real user ID (first field of `Uid`)
+    pub uid: Option<u32>,
+    /// Number of threads
+    pub threads: Option<u64>,
+    /// Resident shared memory (file-backed plus shmem pages) in bytes
+    pub shared_bytes: Option<u64>,
+    /// Context switches where the process gave up the CPU (e.g. blocking I/O)
+    pub voluntary_ctx_switches: Option<u64>,
+    /// Context switches where the process was preempted
+    pub involuntary_ctx_switches: Option<u64>,
+    /// CPUs the process may run on, in list format (e.g. `0-3,8`)
+    pub cpus_allowed_list: Option<String>,
+}
+
+impl ProcStatus {
+    /// Parses the contents of `/proc/<pid>/status`.
+    pub fn parse(content: &str) -> Self {
+        let shared_bytes = parse_kb_field(content, "RssFile")
+            .map(|file| file + parse_kb_field(content, "RssShmem").unwrap_or(0));
+        Self {
+            rss_bytes: parse_kb_field(content, "VmRSS"),
+            virtual_bytes: parse_kb_field(content, "VmSize"),
+            uid: content.lines().find_map(|line| {
+                line.strip_prefix("Uid:")?
+                    .split_whitespace()
+                    .next()?
+                    .parse()
+                    .ok()
+            }),
+            threads: parse_u64_field(content, "Threads"),
+            shared_bytes,
+            voluntary_ctx_switches: parse_u64_field(content, "voluntary_ctxt_switches"),
+            involuntary_ctx_switches: parse_u64_field(content, "nonvoluntary_ctxt_switches"),
+            cpus_allowed_list: content.lines().find_map(|line| {
+                let value = line.strip_prefix("Cpus_allowed_list:")?.trim();
+                Some(value.to_string())
+            }),
+        }
+    }
+}
+
+/// Reads and parses `/proc/<pid>/status`, or `None` if it cannot be read.
+pub fn read_status(proc_root: &Path, pid: u32) -> Option<ProcStatus> {
+    read_pid_file(proc_root, pid, "status")
+        .ok()
+        .map(|content| ProcStatus::parse(&content))
+}
+
+/// Storage I/O of a process from `/proc/<pid>/io`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcIo {
+    /// Bytes fetched from the storage layer
+    pub read_bytes: u64,
+    /// Bytes sent to the storage layer
+    pub write_bytes: u64,
+}
+
+impl ProcIo {
+    /// Parses the contents of `/proc/<pid>/io`.
+    pub fn parse(content: &str) -> Option<Self> {
+        Some(Self {
+            read_bytes: parse_u64_field(content, "read_bytes")?,
+            write_bytes: parse_u64_field(content, "write_bytes")?,
+        })
+    }
+}
+
+/// Reads `/proc/<pid>/io`, or `None` if it cannot be read (it requires
+/// ptrace access to the process).
+pub fn read_io(proc_root: &Path, pid: u32) -> Option<ProcIo> {
+    read_pid_file(proc_root, pid, "io")
+        .ok()
+        .and_then(|content| ProcIo::parse(&content))
+}
+
+/// Joins the NUL-separated arguments of `/proc/<pid>/cmdline` with spaces.
+pub fn parse_cmdline(content: &str) -> String {
+    content
+        .split('\0')
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Aggregate CPU time of all CPUs from the first line of `/proc/stat`, in clock ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    /// Total time across all states
+    pub total: u64,
+    /// Time idle or waiting for I/O
+    pub idle: u64,
+}
+
+impl CpuTimes {
+    /// Parses the `cpu` line of `/proc/stat`.
+    ///
+    /// Guest time is already included in user time and is not added again.
+    pub fn parse(content: &str) -> Option<Self> {
+        let line = content.lines().find(|line| line.starts_with("cpu "))?;
+        let values: Vec<u64> = line
+            .split_whitespace()
+            .skip(1)
+            .take(8)
+            .map(|value| value.parse().ok())
+            .collect::<Option<_>>()?;
+        // user nice system idle iowait irq softirq steal
+        (values.len() >= 5).then(|| Self {
+            total: values.iter().sum(),
+            idle: values[3] + values[4],
+        })
+    }
+
+    /// Busy share (0-100%) of the time elapsed since `earlier`.
+    pub fn usage_since(&self, earlier: &Self) -> f32 {
+        let total = self.total.saturating_sub(earlier.total);
+        let idle = self.idle.saturating_sub(earlier.idle);
+        if total == 0 {
+            return 0.0;
+        }
+        (total.saturating_sub(idle) as f64 / total as f64 * 100.0) as f32
+    }
+}
+
+/// Reads and parses `<proc_root>/stat`.
+pub fn read_cpu_times(proc_root: &Path) -> Option<CpuTimes> {
+    fs::read_to_string(proc_root.join("stat"))
+        .ok()
+        .and_then(|content| CpuTimes::parse(&content))
+}
+
+/// Reads the seconds since boot from `<proc_root>/uptime`.
+pub fn read_uptime(proc_root: &Path) -> Option<f64> {
+    fs::read_to_string(proc_root.join("uptime"))
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Reads an OOM killer value (`oom_score` or `oom_score_adj`) of a process.
+pub fn read_oom_value(proc_root: &Path, pid: u32, file: &str) -> Option<i32> {
+    read_pid_file(proc_root, pid, file)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Proportional and unique set sizes of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmapsRollup {
+    /// Resident memory with shared pages divided among the processes sharing them
+    pub pss_bytes: u64,
+    /// Resident memory private to this process (freed if it exits)
+    pub uss_bytes: u64,
+}
+
+impl SmapsRollup {
+    /// Parses the contents of `/proc/<pid>/smaps_rollup`.
+    pub fn parse(content: &str) -> Option<Self> {
+        let pss_bytes = parse_kb_field(content, "Pss")?;
+        let uss_bytes = parse_kb_field(content, "Private_Clean").unwrap_or(0)
+            + parse_kb_field(content, "Private_Dirty").unwrap_or(0)
+            + parse_kb_field(content, "Private_Hugetlb").unwrap_or(0);
+        Some(Self {
+            pss_bytes,
+            uss_bytes,
+        })
+    }
+}
+
+/// Reads PSS/USS for a process. The kernel walks every mapping to produce
+/// this file, so it is only read on request.
+pub fn read_smaps_rollup(proc_root: &Path, pid: u32) -> Option<SmapsRollup> {
+    read_pid_file(proc_root, pid, "smaps_rollup")
+        .ok()
+        .and_then(|content| SmapsRollup::parse(&content))
+}
+
+/// Huge page pool statistics (explicit hugetlbfs pages, not transparent huge pages).
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HugePages {
+    /// Pages in the pool
+    pub total: u64,
+    /// Pages not yet allocated
+    pub free: u64,
+    /// Pages reserved for allocation but not yet faulted in
+    pub reserved: u64,
+    /// Pages above the configured pool size
+    pub surplus: u64,
+    /// Size of one huge page in bytes
+    pub page_size_bytes: u64,
+}
+
+/// System memory breakdown from `/proc/meminfo`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    /// Usable RAM (`MemTotal`)
+    pub total: Option<u64>,
+    /// Memory available for new allocations without swapping (`MemAvailable`)
+    pub available: Option<u64>,
+    /// Page cache, including shmem (`Cached`)
+    pub cached: Option<u64>,
+    /// Block device buffers (`Buffers`)
+    pub buffers: Option<u64>,
+    /// Modified pages waiting to be written back (`Dirty`)
+    pub dirty: Option<u64>,
+    /// Shared memory and tmpfs pages (`Shmem`)
+    pub shmem: Option<u64>,
+    /// Huge page pool (`HugePages_*`, `Hugepagesize`)
+    pub hugepages: Option<HugePages>,
+}
+
+impl MemInfo {
+    /// Parses the contents of `/proc/meminfo`.
+    pub fn parse(content: &str) -> Self {
+        let hugepages = (|| {
+            Some(HugePages {
+                total: parse_u64_field(content, "HugePages_Total")?,
+                free: parse_u64_field(content, "HugePages_Free")?,
+                reserved: parse_u64_field(content, "HugePages_Rsvd")?,
+                surplus: parse_u64_field(content, "HugePages_Surp")?,
+                page_size_bytes: parse_kb_field(content, "Hugepagesize")?,
+            })
+        })();
+        Self {
+            total: parse_kb_field(content, "MemTotal"),
+            available: parse_kb_field(content, "MemAvailable"),
+            cached: parse_kb_field(content, "Cached"),
+            buffers: parse_kb_field(content, "Buffers"),
+            dirty: parse_kb_field(content, "Dirty"),
+            shmem: parse_kb_field(content, "Shmem"),
+            hugepages,
+        }
+    }
+}
+
+/// Reads and parses `<proc_root>/meminfo`; all fields are `None` if it cannot be read.
+pub fn read_meminfo(proc_root: &Path) -> MemInfo {
+    fs::read_to_string(proc_root.join("meminfo"))
+        .map(|content| MemInfo::parse(&content))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMAPS_ROLLUP: &str = "\
+55d0c7a00000-7ffc2a5f3000 ---p 00000000 00:00 0                          [rollup]
+Rss:               25344 kB
+Pss:               10731 kB
+Pss_Dirty:          6056 kB
+Pss_Anon:           5944 kB
+Pss_File:           4675 kB
+Pss_Shmem:           112 kB
+Shared_Clean:      15068 kB
+Shared_Dirty:        432 kB
+Private_Clean:      3952 kB
+Private_Dirty:      5892 kB
+Referenced:        25344 kB
+Anonymous:          5944 kB
+Swap:                  0 kB
+";
+
+    #[test]
+    fn test_parse_kb_field() {
+        assert_eq!(parse_kb_field(SMAPS_ROLLUP, "Rss"), Some(25344 * 1024));
+        // Must not match the Pss_* lines by prefix
+        assert_eq!(parse_kb_field(SMAPS_ROLLUP, "Pss"), Some(10731 * 1024));
+        assert_eq!(parse_kb_field(SMAPS_ROLLUP, "Missing"), None);
+    }
+
+    #[test]
+    fn test_parse_stat_with_tricky_comm() {
+        // Command names may contain spaces and parentheses
+        let stat = "1234 (Web (Content) 1) S 1 1234 1234 0 -1 4194560 5821 0 12 0 \
+                    250 75 0 0 20 0 31 0 4242 1048576 2048 18446744073709551615";
+        let parsed = ProcStat::parse(stat).unwrap();
+        assert_eq!(parsed.minor_faults, 5821);
+        assert_eq!(parsed.major_faults, 12);
+        assert!((parsed.user_seconds - 2.5).abs() < f64::EPSILON);
+        assert!((parsed.system_seconds - 0.75).abs() < f64::EPSILON);
+        assert_eq!(parsed.priority, 20);
+        assert_eq!(parsed.nice, 0);
+        // Truncated line lacks the policy field
+        assert_eq!(parsed.policy, SchedPolicy::Unknown);
+        assert_eq!(parsed.state, 'S');
+        assert!((parsed.start_seconds - 42.42).abs() < f64::EPSILON);
+        assert_eq!(parse_stat_comm(stat), Some("Web (Content) 1"));
+        assert!(ProcStat::parse("1234 (truncated").is_none());
+    }
+
+    #[test]
+    fn test_parse_stat_realtime() {
+        // A SCHED_FIFO process at rtprio 50 with all 52 fields present
+        let stat = "77 (irq/9-acpi) S 2 0 0 0 -1 2129984 0 0 0 0 0 3 0 0 -51 0 1 0 \
+                    30 0 0 18446744073709551615 0 0 0 0 0 0 0 2147483647 0 0 0 0 0 \
+                    0 50 1 0 0 0 0 0 0 0 0 0 0 0";
+        let parsed = ProcStat::parse(stat).unwrap();
+        assert_eq!(parsed.priority, -51);
+        assert_eq!(parsed.policy, SchedPolicy::Fifo);
+        assert_eq!(parsed.policy.name(), "fifo");
+        assert!(parsed.policy.is_realtime());
+        assert!(!SchedPolicy::Batch.is_realtime());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status = "\
+Name:\tnginx
+Uid:\t33\t33\t33\t33
+VmSize:\t   65536 kB
+VmRSS:\t    8192 kB
+RssAnon:\t    2048 kB
+RssFile:\t    5120 kB
+RssShmem:\t    1024 kB
+voluntary_ctxt_switches:\t1520
+Cpus_allowed_list:\t0-3,6
+nonvoluntary_ctxt_switches:\t37
+Threads:\t4
+";
+        let parsed = ProcStatus::parse(status);
+        assert_eq!(parsed.rss_bytes, Some(8192 * 1024));
+        assert_eq!(parsed.virtual_bytes, Some(65536 * 1024));
+        assert_eq!(parsed.uid, Some(33));
+        assert_eq!(parsed.threads, Some(4));
+        assert_eq!(parsed.shared_bytes, Some(6144 * 1024));
+        assert_eq!(parsed.voluntary_ctx_switches, Some(1520));
+        assert_eq!(parsed.involuntary_ctx_switches, Some(37));
+        assert_eq!(parsed.cpus_allowed_list.as_deref(), Some("0-3,6"));
+        assert_eq!(ProcStatus::parse(""), ProcStatus::default());
+    }
+
+    #[test]
+    fn test_parse_smaps_rollup() {
+        let rollup = SmapsRollup::parse(SMAPS_ROLLUP).unwrap();
+        assert_eq!(rollup.pss_bytes, 10731 * 1024);
+        assert_eq!(rollup.uss_bytes, (3952 + 5892) * 1024);
+        assert!(SmapsRollup::parse("").is_none());
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let meminfo = MemInfo::parse(
+            "MemTotal:        6147400 kB
+MemFree:          211548 kB
+MemAvailable:    5622188 kB
+Buffers:           66000 kB
+Cached:          1617780 kB
+SwapCached:            0 kB
+Dirty:             66776 kB
+Shmem:              9048 kB
+ShmemHugePages:        0 kB
+HugePages_Total:      64
+HugePages_Free:       60
+HugePages_Rsvd:        2
+HugePages_Surp:        0
+Hugepagesize:       2048 kB
+",
+        );
+        assert_eq!(meminfo.total, Some(6147400 * 1024));
+        assert_eq!(meminfo.available, Some(5622188 * 1024));
+        // SwapCached must not be mistaken for Cached
+        assert_eq!(meminfo.cached, Some(1617780 * 1024));
+        assert_eq!(meminfo.buffers, Some(66000 * 1024));
+        assert_eq!(meminfo.dirty, Some(66776 * 1024));
+        assert_eq!(meminfo.shmem, Some(9048 * 1024));
+        let hugepages = meminfo.hugepages.unwrap();
+        assert_eq!(
+            (hugepages.total, hugepages.free, hugepages.reserved),
+            (64, 60, 2)
+        );
+        assert_eq!(hugepages.page_size_bytes, 2 * 1024 * 1024);
+        assert_eq!(MemInfo::parse(""), MemInfo::default());
+    }
+
+    #[test]
+    fn test_parse_io_and_cmdline() {
+        let io = ProcIo::parse(
+            "rchar: 4096\nwchar: 512\nsyscr: 10\nsyscw: 2\n\
+             read_bytes: 8192\nwrite_bytes: 1024\ncancelled_write_bytes: 0\n",
+        )
+        .unwrap();
+        assert_eq!(
+            io,
+            ProcIo {
+                read_bytes: 8192,
+                write_bytes: 1024
+            }
+        );
+        assert_eq!(
+            parse_cmdline("nginx: worker\0-g\0daemon off;\0"),
+            "nginx: worker -g daemon off;"
+        );
+        assert_eq!(parse_cmdline(""), "");
+    }
+
+    #[test]
+    fn test_cpu_times_usage() {
+        let before =
+            CpuTimes::parse("cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 100 0 50 800 50 0 0 0 0 0\n")
+                .unwrap();
+        assert_eq!(
+            before,
+            CpuTimes {
+                total: 1000,
+                idle: 850
+            }
+        );
+        let after = CpuTimes {
+            total: 1200,
+            idle: 900,
+        };
+        assert_eq!(after.usage_since(&before), 75.0);
+        assert_eq!(before.usage_since(&before), 0.0);
+        assert_eq!(CpuTimes::parse("intr 1 2 3\n"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_own_memory() {
+        let root = Path::new(crate::PROC_ROOT);
+        let pid = std::process::id();
+        assert!(read_status(root, pid).unwrap().shared_bytes.is_some());
+        assert!(read_stat(root, pid).is_some());
+        assert!(read_oom_value(root, pid, "oom_score_adj").is_some());
+        if let Some(rollup) = read_smaps_rollup(root, pid) {
+            assert!(rollup.pss_bytes >= rollup.uss_bytes);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use crate::collector::{Collector, ProcessSample, SystemSample, parallel_map};
+use crate::procfs::{self, CpuTimes, ProcStat, ProcStatus};
+use crate::threads::ThreadInfo;
+use crate::{CPU_SAMPLE_INTERVAL_MS, CollectOptions};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// CPU times of the system and of every process and thread at one instant.
+struct CpuMeasurement {
+    at: Instant,
+    system: Option<CpuTimes>,
+    /// Process CPU seconds (user + system) by PID
+    processes: HashMap<u32, f64>,
+    /// Thread CPU seconds by TID, only tracked when threads are listed
+    threads: HashMap<u32, f64>,
+}
+
+/// Reference point CPU usage is measured against.
+enum Baseline {
+    /// CPU times of the previous sample of a live system
+    Previous(CpuMeasurement),
+    /// Seconds since boot of a captured tree; usage is averaged over each
+    /// process's lifetime, like `ps` does
+    Uptime(f64),
+}
+
+/// Collects base metrics by reading `/proc/<pid>/{stat,status,io,cmdline,fd}`
+/// directly, without going through sysinfo.
+pub struct ProcfsCollector {
+    proc_root: PathBuf,
+    /// Whether `proc_root` is a mounted procfs that changes between samples
+    live: bool,
+    previous: Option<CpuMeasurement>,
+}
+
+impl ProcfsCollector {
+    /// Creates a collector reading the proc filesystem mounted at `proc_root`.
+    pub fn new(proc_root: &Path) -> Self {
+        Self {
+            proc_root: proc_root.to_path_buf(),
+            live: true,
+            previous: None,
+        }
+    }
+
+    /// Creates a collector reading a `/proc` tree captured to disk.
+    ///
+    /// A capture is a single point in time, so CPU percentages are averaged
+    /// since process start (system CPU since boot) instead of sampled twice.
+    pub fn captured(proc_root: &Path) -> Self {
+        Self {
+            live: false,
+            ..Self::new(proc_root)
+        }
+    }
+
+    /// The baseline for this sample, waiting for a second measurement on
+    /// the first sample of a live system.
+    fn baseline(&mut self, threads: bool) -> Result<Baseline, Box<dyn Error>> {
+        if !self.live {
+            let uptime = procfs::read_uptime(&self.proc_root).ok_or_else(|| {
+                format!("cannot read {}", self.proc_root.join("uptime").display())
+            })?;
+            return Ok(Baseline::Uptime(uptime));
+        }
+        let previous = match self.previous.take() {
+            Some(previous) => previous,
+            None => {
+                let baseline = self.measure_cpu(threads)?;
+                std::thread::sleep(std::time::Duration::from_millis(CPU_SAMPLE_INTERVAL_MS));
+                baseline
+            }
+        };
+        Ok(Baseline::Previous(previous))
+    }
+
+    /// PIDs of all processes; threads are not listed at the top level of /proc.
+    fn pids(&self) -> Result<Vec<u32>, Box<dyn Error>> {
+        let entries = fs::read_dir(&self.proc_root)
+            .map_err(|e| format!("cannot read {}: {e}", self.proc_root.display()))?;
+        let mut pids: Vec<u32> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect();
+        pids.sort_unstable();
+        Ok(pids)
+    }
+
+    /// TIDs of a process's threads from `/proc/<pid>/task`.
+    fn tids(&self, pid: u32) -> Vec<u32> {
+        let Ok(entries) = fs::read_dir(self.proc_root.join(pid.to_string()).join("task")) else {
+            return Vec::new();
+        };
+        let mut tids: Vec<u32> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect();
+        tids.sort_unstable();
+        tids
+    }
+
+    /// Reads the CPU times only, as the baseline of the first sample.
+    fn measure_cpu(&self, threads: bool) -> Result<CpuMeasurement, Box<dyn Error>> {
+        let mut measurement = CpuMeasurement {
+            at: Instant::now(),
+            system: procfs::read_cpu_times(&self.proc_root),
+            processes: HashMap::new(),
+            threads: HashMap::new(),
+        };
+        for pid in self.pids()? {
+            if let Some(stat) = procfs::read_stat(&self.proc_root, pid) {
+                measurement.processes.insert(pid, cpu_seconds(&stat));
+            }
+            if threads {
+                for tid in self.tids(pid) {
+                    if let Some(stat) = read_task_stat(&self.proc_root, pid, tid) {
+                        measurement.threads.insert(tid, cpu_seconds(&stat.1));
+                    }
+                }
+            }
+        }
+        Ok(measurement)
+    }
+}
+
+fn cpu_seconds(stat: &ProcStat) -> f64 {
+    stat.user_seconds + stat.system_seconds
+}
+
+/// Reads `/proc/<pid>/task/<tid>/stat`, returning the thread name and parsed fields.
+fn read_task_stat(proc_root: &Path, pid: u32, tid: u32) -> Option<(String, ProcStat)> {
+    let content = procfs::read_pid_file(proc_root, pid, &format!("task/{tid}/stat")).ok()?;
+    let name = procfs::parse_stat_comm(&content)?.to_string();
+    Some((name, ProcStat::parse(&content)?))
+}
+
+impl Collector for ProcfsCollector {
+    fn sample(&mut self, options: &CollectOptions) -> Result<SystemSample, Box<dyn Error>> {
+        let baseline = self.baseline(options.threads)?;
+
+        let mut current = CpuMeasurement {
+            at: Instant::now(),
+            system: procfs::read_cpu_times(&self.proc_root),
+            processes: HashMap::new(),
+            threads: HashMap::new(),
+        };
+        let now = current.at;
+        // Processes that appeared since the previous sample have no baseline and report 0%
+        let percent = |stat: &ProcStat, before: Option<&f64>| {
+            let (used, elapsed) = match &baseline {
+                Baseline::Previous(previous) => match before {
+                    Some(before) => (
+                        cpu_seconds(stat) - before,
+                        now.duration_since(previous.at).as_secs_f64(),
+                    ),
+                    None => return 0.0,
+                },
+                Baseline::Uptime(uptime) => (cpu_seconds(stat), uptime - stat.start_seconds),
+            };
+            if elapsed > 0.0 {
+                (used.max(0.0) / elapsed * 100.0) as f32
+            } else {
+                0.0
+            }
+        };
+        let previous_processes = match &baseline {
+            Baseline::Previous(previous) => Some(&previous.processes),
+            Baseline::Uptime(_) => None,
+        };
+        let previous_threads = match &baseline {
+            Baseline::Previous(previous) => Some(&previous.threads),
+            Baseline::Uptime(_) => None,
+        };
+
+        // Reads one process, or `None` if it exited while being read
+        let read_process = |pid: u32| {
+            let stat_content = procfs::read_pid_file(&self.proc_root, pid, "stat").ok()?;
+            let stat = ProcStat::parse(&stat_content)?;
+            let status = procfs::read_status(&self.proc_root, pid).unwrap_or_default();
+            let io = procfs::read_io(&self.proc_root, pid).unwrap_or_default();
+
+            let mut thread_seconds = Vec::new();
+            let threads = options.threads.then(|| {
+                self.tids(pid)
+                    .into_iter()
+                    .filter_map(|tid| {
+                        let (name, stat) = read_task_stat(&self.proc_root, pid, tid)?;
+                        thread_seconds.push((tid, cpu_seconds(&stat)));
+                        Some(ThreadInfo {
+                            tid,
+                            name,
+                            state: sysinfo::ProcessStatus::from(stat.state).to_string(),
+                            cpu_percent: percent(
+                                &stat,
+                                previous_threads.and_then(|threads| threads.get(&tid)),
+                            ),
+                        })
+                    })
+                    .collect()
+            });
+
+            let sample = process_sample(
+                pid,
+                &stat_content,
+                &status,
+                io,
+                percent(
+                    &stat,
+                    previous_processes.and_then(|processes| processes.get(&pid)),
+                ),
+                threads,
+                &self.proc_root,
+            );
+            // stat and status are passed on so they are not read again
+            let sample = ProcessSample {
+                stat: Some(stat),
+                status: Some(status),
+                ..sample
+            };
+            Some((sample, cpu_seconds(&stat), thread_seconds))
+        };
+
+        let mut processes = Vec::new();
+        for (sample, seconds, thread_seconds) in
+            parallel_map(self.pids()?, options.jobs, read_process)
+                .into_iter()
+                .flatten()
+        {
+            current.processes.insert(sample.pid, seconds);
+            current.threads.extend(thread_seconds);
+            processes.push(sample);
+        }
+
+        let meminfo = procfs::read_meminfo(&self.proc_root);
+        let memory_total = meminfo.total.unwrap_or(0);
+        let cpu_usage = match (current.system, &baseline) {
+            (Some(now), Baseline::Previous(previous)) => previous
+                .system
+                .map_or(0.0, |before| now.usage_since(&before)),
+            (Some(now), Baseline::Uptime(_)) => now.usage_since(&CpuTimes::default()),
+            (None, _) => 0.0,
+        };
+        if self.live {
+            self.previous = Some(current);
+        }
+
+        Ok(SystemSample {
+            cpu_usage,
+            memory_total,
+            memory_used: memory_total.saturating_sub(meminfo.available.unwrap_or(0)),
+            processes,
+        })
+    }
+
+    fn proc_root(&self) -> &Path {
+        &self.proc_root
+    }
+}
+
+fn process_sample(
+    pid: u32,
+    stat_content: &str,
+    status: &ProcStatus,
+    io: procfs::ProcIo,
+    cpu_usage: f32,
+    threads: Option<Vec<ThreadInfo>>,
+    proc_root: &Path,
+) -> ProcessSample {
+    ProcessSample {
+        pid,
+        name: procfs::parse_stat_comm(stat_content)
+            .unwrap_or_default()
+            .to_string(),
+        cpu_usage,
+        memory_bytes: status.rss_bytes.unwrap_or(0),
+        virtual_bytes: status.virtual_bytes.unwrap_or(0),
+        user: status.uid.map(|uid| uid.to_string()),
+        command: procfs::read_pid_file(proc_root, pid, "cmdline")
+            .map(|content| procfs::parse_cmdline(&content))
+            .unwrap_or_default(),
+        thread_count: status.threads.unwrap_or(1) as usize,
+        disk_read_bytes: io.read_bytes,
+        disk_write_bytes: io.write_bytes,
+        open_files: fs::read_dir(proc_root.join(pid.to_string()).join("fd"))
+            .ok()
+            .map(|entries| entries.count()),
+        threads,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_sample_includes_own_process() {
+        let mut collector = ProcfsCollector::new(Path::new(crate::PROC_ROOT));
+        let options = CollectOptions {
+            threads: true,
+            ..Default::default()
+        };
+        let sample = collector.sample(&options).unwrap();
+        assert!(sample.memory_total > 0);
+        assert!(sample.memory_used <= sample.memory_total);
+
+        let own = sample
+            .processes
+            .iter()
+            .find(|p| p.pid == std::process::id())
+            .unwrap();
+        assert!(own.memory_bytes > 0);
+        assert!(own.command.contains("stop"));
+        assert!(own.open_files.unwrap() > 0);
+        // stat and status are passed on instead of being read again
+        assert!(own.stat.is_some() && own.status.is_some());
+        let threads = own.threads.as_ref().unwrap();
+        assert!(threads.iter().any(|t| t.tid == own.pid));
+
+        // Later samples measure against the previous one without sleeping again
+        let second = collector.sample(&options).unwrap();
+        assert!(second.processes.iter().any(|p| p.pid == own.pid));
+    }
+
+    #[test]
+    fn test_missing_proc_root() {
+        let mut collector = ProcfsCollector::new(Path::new("/nonexistent/proc"));
+        let error = collector.sample(&CollectOptions::default()).unwrap_err();
+        assert!(error.to_string().contains("/nonexistent/proc"));
+    }
+}
@@ -0,0 +1,660 @@
+use crate::{ProcessInfo, SystemMetrics, SystemSnapshot};
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+/// Content type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Gauge,
+    Counter,
+}
+
+/// One sample: label pairs and the formatted value.
+struct Sample<'a> {
+    labels: Vec<(&'static str, Cow<'a, str>)>,
+    value: String,
+}
+
+/// A metric family: HELP and TYPE lines followed by all of its samples.
+struct Family<'a> {
+    name: &'static str,
+    kind: Kind,
+    help: &'static str,
+    samples: Vec<Sample<'a>>,
+}
+
+impl<'a> Family<'a> {
+    fn new(name: &'static str, kind: Kind, help: &'static str) -> Self {
+        Self {
+            name,
+            kind,
+            help,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Adds a sample without labels, skipping unavailable values.
+    fn value(mut self, value: Option<String>) -> Self {
+        if let Some(value) = value {
+            self.samples.push(Sample {
+                labels: Vec::new(),
+                value,
+            });
+        }
+        self
+    }
+
+    /// Adds a labelled sample, skipping unavailable values.
+    fn push(&mut self, labels: Vec<(&'static str, Cow<'a, str>)>, value: Option<String>) {
+        if let Some(value) = value {
+            self.samples.push(Sample { labels, value });
+        }
+    }
+
+    /// Writes the family, or nothing if it has no samples.
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.samples.is_empty() {
+            return Ok(());
+        }
+        let kind = match self.kind {
+            Kind::Gauge => "gauge",
+            Kind::Counter => "counter",
+        };
+        writeln!(out, "# HELP {} {}", self.name, self.help)?;
+        writeln!(out, "# TYPE {} {kind}", self.name)?;
+        for sample in &self.samples {
+            write!(out, "{}", self.name)?;
+            if !sample.labels.is_empty() {
+                let labels: Vec<String> = sample
+                    .labels
+                    .iter()
+                    .map(|(name, value)| format!("{name}=\"{}\"", escape_label_value(value)))
+                    .collect();
+                write!(out, "{{{}}}", labels.join(","))?;
+            }
+            writeln!(out, " {}", sample.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a label value: backslash, double quote and line feed.
+pub fn escape_label_value(value: &str) -> Cow<'_, str> {
+    if !value.contains(['\\', '"', '\n']) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Formats a float the way the text format spells infinities (`+Inf`, `-Inf`).
+fn float<T: Copy + Into<f64> + Display>(value: T) -> String {
+    let f: f64 = value.into();
+    match f {
+        f if f == f64::INFINITY => "+Inf".to_string(),
+        f if f == f64::NEG_INFINITY => "-Inf".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn int(value: impl Into<u64>) -> String {
+    value.into().to_string()
+}
+
+fn system_families(system: &SystemMetrics) -> Vec<Family<'_>> {
+    let bytes =
+        |name, help, value: Option<u64>| Family::new(name, Kind::Gauge, help).value(value.map(int));
+    let mut families = vec![
+        Family::new(
+            "stop_system_cpu_usage_percent",
+            Kind::Gauge,
+            "Global CPU usage in percent (0-100).",
+        )
+        .value(Some(float(system.cpu_usage))),
+        bytes(
+            "stop_system_memory_total_bytes",
+            "Total system memory in bytes.",
+            Some(system.memory_total),
+        ),
+        bytes(
+            "stop_system_memory_used_bytes",
+            "Used system memory in bytes.",
+            Some(system.memory_used),
+        ),
+        Family::new(
+            "stop_system_memory_used_percent",
+            Kind::Gauge,
+            "Used system memory in percent (0-100).",
+        )
+        .value(Some(float(system.memory_percent))),
+        bytes(
+            "stop_system_memory_available_bytes",
+            "Memory available for new allocations without swapping, in bytes.",
+            system.memory_available,
+        ),
+        bytes(
+            "stop_system_memory_cached_bytes",
+            "Page cache in bytes.",
+            system.cached,
+        ),
+        bytes(
+            "stop_system_memory_buffers_bytes",
+            "Block device buffers in bytes.",
+            system.buffers,
+        ),
+        bytes(
+            "stop_system_memory_dirty_bytes",
+            "Dirty pages waiting for write-back, in bytes.",
+            system.dirty,
+        ),
+        bytes(
+            "stop_system_memory_shmem_bytes",
+            "Shared memory and tmpfs pages in bytes.",
+            system.shmem,
+        ),
+    ];
+
+    let mut hugepages = Family::new(
+        "stop_system_hugepages",
+        Kind::Gauge,
+        "Huge pages in the pool by state.",
+    );
+    if let Some(pool) = &system.hugepages {
+        for (state, pages) in [
+            ("total", pool.total),
+            ("free", pool.free),
+            ("reserved", pool.reserved),
+            ("surplus", pool.surplus),
+        ] {
+            hugepages.push(vec![("state", Cow::Borrowed(state))], Some(int(pages)));
+        }
+    }
+    families.push(hugepages);
+    families.push(bytes(
+        "stop_system_hugepage_size_bytes",
+        "Size of one huge page in bytes.",
+        system.hugepages.as_ref().map(|pool| pool.page_size_bytes),
+    ));
+
+    let mut pressure = Family::new(
+        "stop_system_pressure_percent",
+        Kind::Gauge,
+        "Share of wall time tasks were stalled on a resource, in percent (PSI).",
+    );
+    if let Some(stall) = &system.pressure {
+        for (resource, stats) in [
+            ("cpu", stall.cpu),
+            ("memory", stall.memory),
+            ("io", stall.io),
+        ] {
+            let Some(stats) = stats else { continue };
+            for (kind, averages) in [("some", Some(stats.some)), ("full", stats.full)] {
+                let Some(averages) = averages else { continue };
+                for (window, value) in [
+                    ("10s", averages.avg10),
+                    ("60s", averages.avg60),
+                    ("300s", averages.avg300),
+                ] {
+                    pressure.push(
+                        vec![
+                            ("resource", Cow::Borrowed(resource)),
+                            ("kind", Cow::Borrowed(kind)),
+                            ("window", Cow::Borrowed(window)),
+                        ],
+                        Some(float(value)),
+                    );
+                }
+            }
+        }
+    }
+    families.push(pressure);
+
+    let mut memory_limit = Family::new(
+        "stop_cgroup_memory_limit_bytes",
+        Kind::Gauge,
+        "Memory limit of the cgroup percentages are relative to, in bytes.",
+    );
+    let mut cpu_limit = Family::new(
+        "stop_cgroup_cpu_limit_cores",
+        Kind::Gauge,
+        "CPU quota of the cgroup percentages are relative to, in cores.",
+    );
+    if let Some(limits) = &system.limits {
+        let labels = || vec![("cgroup", Cow::Borrowed(limits.cgroup.as_str()))];
+        memory_limit.push(labels(), limits.memory_limit_bytes.map(int));
+        cpu_limit.push(labels(), limits.cpu_limit_cores.map(float));
+    }
+    families.push(memory_limit);
+    families.push(cpu_limit);
+    families
+}
+
+fn process_families(processes: &[ProcessInfo]) -> Vec<Family<'_>> {
+    type Value = fn(&ProcessInfo) -> Option<String>;
+    let simple: [(&'static str, Kind, &'static str, Value); 12] = [
+        (
+            "stop_process_cpu_percent",
+            Kind::Gauge,
+            "Process CPU usage in percent of one core.",
+            |p| Some(float(p.cpu_percent)),
+        ),
+        (
+            "stop_process_memory_percent",
+            Kind::Gauge,
+            "Process resident memory in percent of system memory.",
+            |p| Some(float(p.memory_percent)),
+        ),
+        (
+            "stop_process_resident_memory_bytes",
+            Kind::Gauge,
+            "Process resident memory (RSS) in bytes.",
+            |p| Some(int(p.memory_bytes)),
+        ),
+        (
+            "stop_process_virtual_memory_bytes",
+            Kind::Gauge,
+            "Process virtual memory size in bytes.",
+            |p| Some(int(p.virtual_bytes)),
+        ),
+        (
+            "stop_process_shared_memory_bytes",
+            Kind::Gauge,
+            "Process resident memory shared with other processes, in bytes.",
+            |p| p.shared_bytes.map(int),
+        ),
+        (
+            "stop_process_pss_bytes",
+            Kind::Gauge,
+            "Process proportional set size in bytes.",
+            |p| p.pss_bytes.map(int),
+        ),
+        (
+            "stop_process_uss_bytes",
+            Kind::Gauge,
+            "Process unique set size in bytes.",
+            |p| p.uss_bytes.map(int),
+        ),
+        (
+            "stop_process_threads",
+            Kind::Gauge,
+            "Number of threads of the process.",
+            |p| Some(p.thread_count.to_string()),
+        ),
+        (
+            "stop_process_open_fds",
+            Kind::Gauge,
+            "Number of open file descriptors of the process.",
+            |p| p.open_files.map(|n| n.to_string()),
+        ),
+        (
+            "stop_process_disk_read_bytes_total",
+            Kind::Counter,
+            "Total bytes the process read from disk.",
+            |p| Some(int(p.disk_read_bytes)),
+        ),
+        (
+            "stop_process_disk_written_bytes_total",
+            Kind::Counter,
+            "Total bytes the process wrote to disk.",
+            |p| Some(int(p.disk_write_bytes)),
+        ),
+        (
+            "stop_process_oom_score",
+            Kind::Gauge,
+            "OOM killer badness score of the process.",
+            |p| {
+                p.sched
+                    .as_ref()
+                    .and_then(|s| s.oom_score)
+                    .map(|s| s.to_string())
+            },
+        ),
+    ];
+    let mut families: Vec<Family> = simple
+        .iter()
+        .map(|&(name, kind, help, value)| {
+            let mut family = Family::new(name, kind, help);
+            for process in processes {
+                family.push(process_labels(process, None), value(process));
+            }
+            family
+        })
+        .collect();
+
+    type Pair = fn(&ProcessInfo) -> [Option<u64>; 2];
+    let split: [(&'static str, &'static str, [&'static str; 2], Pair); 2] = [
+        (
+            "stop_process_context_switches_total",
+            "Context switches of the process by kind.",
+            ["voluntary", "involuntary"],
+            |p| [p.voluntary_ctx_switches, p.involuntary_ctx_switches],
+        ),
+        (
+            "stop_process_page_faults_total",
+            "Page faults of the process by kind.",
+            ["minor", "major"],
+            |p| [p.minor_faults, p.major_faults],
+        ),
+    ];
+    let mut cpu_seconds = Family::new(
+        "stop_process_cpu_seconds_total",
+        Kind::Counter,
+        "CPU time of the process by mode, in seconds.",
+    );
+    for process in processes {
+        for (mode, seconds) in [
+            ("user", process.cpu_user_seconds),
+            ("system", process.cpu_system_seconds),
+        ] {
+            cpu_seconds.push(
+                process_labels(process, Some(("mode", mode))),
+                seconds.map(float),
+            );
+        }
+    }
+    families.push(cpu_seconds);
+    for (name, help, kinds, values) in split {
+        let mut family = Family::new(name, Kind::Counter, help);
+        for process in processes {
+            for (kind, value) in kinds.into_iter().zip(values(process)) {
+                family.push(
+                    process_labels(process, Some(("kind", kind))),
+                    value.map(int),
+                );
+            }
+        }
+        families.push(family);
+    }
+    families
+}
+
+/// The `pid`, `name` and `user` labels of a process, plus an optional extra label.
+fn process_labels<'a>(
+    process: &'a ProcessInfo,
+    extra: Option<(&'static str, &'static str)>,
+) -> Vec<(&'static str, Cow<'a, str>)> {
+    let mut labels = vec![
+        ("pid", Cow::Owned(process.pid.to_string())),
+        ("name", Cow::Borrowed(process.name.as_str())),
+        ("user", Cow::Borrowed(process.user.as_str())),
+    ];
+    if let Some((name, value)) = extra {
+        labels.push((name, Cow::Borrowed(value)));
+    }
+    labels
+}
+
+/// Writes a snapshot in the Prometheus text exposition format (0.0.4).
+///
+/// System metrics are unlabelled gauges; process metrics are labelled by
+/// `pid`, `name` and `user`, so the searched, filtered and `--top-n` limited
+/// process list bounds the number of series. Unavailable values are omitted,
+/// and families without samples are skipped entirely.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_metrics(out: &mut impl Write, snapshot: &SystemSnapshot) -> io::Result<()> {
+    for family in system_families(&snapshot.system)
+        .iter()
+        .chain(&process_families(&snapshot.processes))
+    {
+        family.write(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchedInfo;
+    use crate::procfs::{HugePages, SchedPolicy};
+    use crate::psi::{Pressure, PressureAverages, PressureStall};
+    use std::collections::HashSet;
+
+    fn snapshot() -> SystemSnapshot {
+        let averages = PressureAverages {
+            avg10: 1.5,
+            avg60: 1.0,
+            avg300: 0.5,
+        };
+        SystemSnapshot {
+            timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+            system: SystemMetrics {
+                cpu_usage: 30.0,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                memory_available: Some(6 << 30),
+                cached: None,
+                buffers: None,
+                dirty: None,
+                shmem: None,
+                hugepages: Some(HugePages {
+                    total: 0,
+                    free: 0,
+                    reserved: 0,
+                    surplus: 0,
+                    page_size_bytes: 2 << 20,
+                }),
+                pressure: Some(PressureStall {
+                    cpu: Some(Pressure {
+                        some: averages,
+                        full: None,
+                    }),
+                    memory: None,
+                    io: Some(Pressure {
+                        some: averages,
+                        full: Some(averages),
+                    }),
+                }),
+                limits: None,
+            },
+            processes: vec![
+                ProcessInfo {
+                    pid: 42,
+                    name: "we\"ird\\na\nme".to_string(),
+                    cpu_percent: 12.5,
+                    memory_bytes: 1 << 20,
+                    user: "1000".to_string(),
+                    thread_count: 4,
+                    cpu_user_seconds: Some(1.5),
+                    minor_faults: Some(10),
+                    major_faults: Some(0),
+                    ..Default::default()
+                },
+                ProcessInfo {
+                    pid: 7,
+                    name: "init".to_string(),
+                    cpu_percent: f32::INFINITY,
+                    open_files: Some(3),
+                    sched: Some(SchedInfo {
+                        nice: 0,
+                        priority: 20,
+                        policy: SchedPolicy::Other,
+                        cpus_allowed: Some("0-3".to_string()),
+                        oom_score: Some(667),
+                        oom_score_adj: Some(0),
+                    }),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn render() -> String {
+        let mut out = Vec::new();
+        write_metrics(&mut out, &snapshot()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    type Labels = Vec<(String, String)>;
+
+    fn is_metric_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    }
+
+    fn is_label_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn is_value(value: &str) -> bool {
+        matches!(value, "NaN" | "+Inf" | "-Inf") || value.parse::<f64>().is_ok()
+    }
+
+    /// Parses `{name="value",...}` and returns the unescaped label pairs and the rest.
+    fn parse_labels(s: &str) -> Result<(Labels, &str), String> {
+        let mut labels = Vec::new();
+        let mut rest = s.strip_prefix('{').ok_or("expected '{'")?;
+        loop {
+            if let Some(after) = rest.strip_prefix('}') {
+                return Ok((labels, after));
+            }
+            let (name, after) = rest.split_once("=\"").ok_or("expected '=\"'")?;
+            if !is_label_name(name) {
+                return Err(format!("invalid label name {name:?}"));
+            }
+            let mut value = String::new();
+            let mut chars = after.char_indices();
+            let end = loop {
+                match chars.next().ok_or("unterminated label value")? {
+                    (_, '\\') => match chars.next().ok_or("dangling escape")?.1 {
+                        '\\' => value.push('\\'),
+                        '"' => value.push('"'),
+                        'n' => value.push('\n'),
+                        c => return Err(format!("invalid escape \\{c}")),
+                    },
+                    (_, '\n') => return Err("raw line feed in label value".to_string()),
+                    (i, '"') => break i,
+                    (_, c) => value.push(c),
+                }
+            };
+            labels.push((name.to_string(), value));
+            rest = &after[end + 1..];
+            rest = rest.strip_prefix(',').unwrap_or(rest);
+        }
+    }
+
+    /// Checks the text format grammar: HELP and TYPE before a family's samples,
+    /// each family contiguous and declared once, valid names, escapes and values.
+    /// Returns the unescaped labels of every sample.
+    fn validate(text: &str) -> Result<Vec<(String, Labels)>, String> {
+        let mut declared = HashSet::new();
+        let mut current: Option<(String, bool)> = None;
+        let mut samples = Vec::new();
+        if !text.ends_with('\n') {
+            return Err("missing final line feed".to_string());
+        }
+        for line in text.lines() {
+            if let Some(help) = line.strip_prefix("# HELP ") {
+                let (name, _) = help.split_once(' ').ok_or("HELP without text")?;
+                if !is_metric_name(name) || !declared.insert(name.to_string()) {
+                    return Err(format!("invalid or repeated family {name}"));
+                }
+                current = Some((name.to_string(), false));
+            } else if let Some(kind) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = kind.split_once(' ').ok_or("TYPE without type")?;
+                if current.as_ref().map(|(n, _)| n.as_str()) != Some(name) {
+                    return Err(format!("TYPE {name} outside its family"));
+                }
+                if !matches!(kind, "counter" | "gauge") {
+                    return Err(format!("unexpected type {kind}"));
+                }
+                current = Some((name.to_string(), true));
+            } else {
+                let end = line.find(['{', ' ']).ok_or("sample without value")?;
+                let name = &line[..end];
+                match &current {
+                    Some((family, true)) if family == name => {}
+                    _ => return Err(format!("sample {name} outside its family")),
+                }
+                let (labels, rest) = if line[end..].starts_with('{') {
+                    parse_labels(&line[end..])?
+                } else {
+                    (Vec::new(), &line[end..])
+                };
+                let value = rest.strip_prefix(' ').ok_or("expected ' ' before value")?;
+                if !is_value(value) {
+                    return Err(format!("invalid value {value:?}"));
+                }
+                samples.push((name.to_string(), labels));
+            }
+        }
+        Ok(samples)
+    }
+
+    #[test]
+    fn test_output_matches_text_format_grammar() {
+        let text = render();
+        let samples = validate(&text).unwrap_or_else(|e| panic!("{e}\n{text}"));
+
+        let weird = samples
+            .iter()
+            .find(|(name, labels)| {
+                name == "stop_process_cpu_percent" && labels[0] == ("pid".into(), "42".into())
+            })
+            .unwrap();
+        assert_eq!(weird.1[1], ("name".into(), "we\"ird\\na\nme".into()));
+        assert_eq!(weird.1[2], ("user".into(), "1000".into()));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let text = render();
+        assert!(text.contains(
+            "# HELP stop_system_cpu_usage_percent Global CPU usage in percent (0-100).\n\
+             # TYPE stop_system_cpu_usage_percent gauge\n\
+             stop_system_cpu_usage_percent 30\n"
+        ));
+        assert!(text.contains("stop_system_memory_available_bytes 6442450944\n"));
+        assert!(text.contains(r#"stop_system_hugepages{state="free"} 0"#));
+        assert!(text.contains(
+            r#"stop_system_pressure_percent{resource="io",kind="full",window="10s"} 1.5"#
+        ));
+        assert!(!text.contains(r#"resource="cpu",kind="full""#));
+        assert!(text.contains(
+            r#"stop_process_cpu_percent{pid="42",name="we\"ird\\na\nme",user="1000"} 12.5"#
+        ));
+        assert!(text.contains(r#"stop_process_cpu_percent{pid="7",name="init",user=""} +Inf"#));
+        assert!(text.contains("# TYPE stop_process_cpu_seconds_total counter\n"));
+        assert!(text.contains(
+            r#"stop_process_cpu_seconds_total{pid="42",name="we\"ird\\na\nme",user="1000",mode="user"} 1.5"#
+        ));
+        assert!(text.contains(r#"stop_process_page_faults_total{pid="42","#));
+        assert!(text.contains(r#"stop_process_oom_score{pid="7",name="init",user=""} 667"#));
+
+        // Unavailable values and empty families are left out
+        assert!(!text.contains("stop_system_memory_cached_bytes"));
+        assert!(!text.contains("stop_process_pss_bytes"));
+        assert!(!text.contains("stop_cgroup_memory_limit_bytes"));
+        assert!(!text.contains(r#"mode="system""#));
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("nginx"), "nginx");
+        assert_eq!(escape_label_value(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape_label_value("a\nb"), "a\\nb");
+        assert!(matches!(escape_label_value("plain"), Cow::Borrowed(_)));
+    }
+}
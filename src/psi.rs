@@ -0,0 +1,142 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Share of wall time (0-100%) tasks were stalled, averaged over three windows.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureAverages {
+    /// Average over the last 10 seconds
+    pub avg10: f32,
+    /// Average over the last 60 seconds
+    pub avg60: f32,
+    /// Average over the last 300 seconds
+    pub avg300: f32,
+}
+
+/// Pressure stall information of one resource.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    /// Time at least one task was stalled on the resource
+    pub some: PressureAverages,
+    /// Time all non-idle tasks were stalled at once (None for CPU on kernels before 5.13)
+    pub full: Option<PressureAverages>,
+}
+
+/// Pressure stall information (PSI) for CPU, memory and I/O.
+///
+/// Unlike utilization, PSI shows contention: how long work waited for a resource.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureStall {
+    pub cpu: Option<Pressure>,
+    pub memory: Option<Pressure>,
+    pub io: Option<Pressure>,
+}
+
+impl PressureStall {
+    /// Reads system-wide PSI from `<proc_root>/pressure/{cpu,memory,io}`.
+    ///
+    /// Returns `None` on kernels without PSI support (or with `psi=0`).
+    pub fn read_system(proc_root: &Path) -> Option<Self> {
+        let dir = proc_root.join("pressure");
+        Self::read_files(|resource| dir.join(resource))
+    }
+
+    /// Reads the PSI of a cgroup v2 directory (`{cpu,memory,io}.pressure`).
+    pub fn read_cgroup(cgroup_dir: &Path) -> Option<Self> {
+        Self::read_files(|resource| cgroup_dir.join(format!("{resource}.pressure")))
+    }
+
+    fn read_files(path: impl Fn(&str) -> std::path::PathBuf) -> Option<Self> {
+        let read = |resource: &str| {
+            std::fs::read_to_string(path(resource))
+                .ok()
+                .and_then(|content| parse_pressure(&content))
+        };
+        let stall = Self {
+            cpu: read("cpu"),
+            memory: read("memory"),
+            io: read("io"),
+        };
+        (stall != Self::default()).then_some(stall)
+    }
+}
+
+/// Parses a PSI file:
+///
+/// ```text
+/// some avg10=2.38 avg60=2.39 avg300=2.09 total=34883664
+/// full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+/// ```
+pub fn parse_pressure(content: &str) -> Option<Pressure> {
+    let mut some = None;
+    let mut full = None;
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let mut averages = PressureAverages::default();
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            let target = match key {
+                "avg10" => &mut averages.avg10,
+                "avg60" => &mut averages.avg60,
+                "avg300" => &mut averages.avg300,
+                _ => continue,
+            };
+            *target = value.parse().ok()?;
+        }
+        match kind {
+            Some("some") => some = Some(averages),
+            Some("full") => full = Some(averages),
+            _ => {}
+        }
+    }
+    Some(Pressure { some: some?, full })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pressure() {
+        let pressure = parse_pressure(
+            "some avg10=2.38 avg60=2.39 avg300=2.09 total=34883664\n\
+             full avg10=0.50 avg60=0.25 avg300=0.00 total=120\n",
+        )
+        .unwrap();
+        assert_eq!(pressure.some.avg10, 2.38);
+        assert_eq!(pressure.some.avg300, 2.09);
+        assert_eq!(pressure.full.unwrap().avg60, 0.25);
+    }
+
+    #[test]
+    fn test_parse_pressure_without_full() {
+        // CPU pressure on kernels before 5.13 only reports "some"
+        let pressure =
+            parse_pressure("some avg10=81.00 avg60=55.10 avg300=12.00 total=987654\n").unwrap();
+        assert_eq!(pressure.some.avg10, 81.0);
+        assert_eq!(pressure.full, None);
+    }
+
+    #[test]
+    fn test_parse_pressure_invalid() {
+        assert_eq!(parse_pressure(""), None);
+        assert_eq!(
+            parse_pressure("some avg10=x avg60=0.00 avg300=0.00\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_cgroup_fixture() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/cgroupfs/v2/system.slice")
+            .join("docker-3f1a9c2b7d4e5f60718293a4b5c6d7e8f90123456789abcdef0123456789abcd.scope");
+        let stall = PressureStall::read_cgroup(&dir).unwrap();
+        assert_eq!(stall.cpu.unwrap().some.avg10, 63.5);
+        assert_eq!(stall.memory.unwrap().full.unwrap().avg10, 12.25);
+        assert_eq!(stall.io, None);
+    }
+}
@@ -0,0 +1,569 @@
+use crate::SystemSnapshot;
+use crate::columns::{COLUMNS, ColumnValue, SYSTEM_FIELDS};
+use crate::psi::{Pressure, PressureStall};
+use rusqlite::types::Value;
+use rusqlite::{Connection, params_from_iter};
+use std::error::Error;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Version of the database layout; bumped when tables change incompatibly.
+///
+/// New process columns are not a layout change: they are added to
+/// `process_samples` when an existing database is opened.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// Where `--record` writes watch intervals.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordTarget {
+    /// SQLite database file, created if missing
+    Sqlite(PathBuf),
+}
+
+impl RecordTarget {
+    /// Parses `sqlite:PATH`; the value parser of `--record`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for other schemes and empty paths.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.split_once(':') {
+            Some(("sqlite", path)) if !path.is_empty() => Ok(Self::Sqlite(PathBuf::from(path))),
+            Some(("sqlite", _)) => Err("missing database path (expected sqlite:PATH)".to_string()),
+            _ => Err(format!(
+                "unsupported record target '{value}' (expected sqlite:PATH)"
+            )),
+        }
+    }
+}
+
+/// Parses an age like `90s`, `30m`, `24h` or `7d`; the value parser of `--record-max-age`.
+///
+/// # Errors
+///
+/// Returns an error if the number or unit is invalid.
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid age '{value}' (expected e.g. 90s, 30m, 24h or 7d)");
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    number
+        .checked_mul(seconds)
+        .filter(|&s| s > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+/// Which recorded snapshots to keep; older ones are deleted at each commit.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Retention {
+    /// Delete snapshots older than this
+    pub max_age: Option<Duration>,
+    /// Keep only this many of the newest snapshots
+    pub max_snapshots: Option<NonZeroUsize>,
+}
+
+/// Resources of the `pressure_*` snapshot columns.
+const PRESSURE_RESOURCES: [&str; 3] = ["cpu", "memory", "io"];
+
+/// SQL type of a column value, from the variant its accessor returns.
+fn sql_type(value: &ColumnValue) -> &'static str {
+    match value {
+        ColumnValue::Unsigned(_) | ColumnValue::Signed(_) => "INTEGER",
+        ColumnValue::Percent(_) | ColumnValue::Float(_) => "REAL",
+        ColumnValue::Text(_) => "TEXT",
+    }
+}
+
+/// Converts a column value to an SQLite value; unavailable values are NULL.
+fn sql_value(value: ColumnValue) -> Value {
+    match value {
+        ColumnValue::Unsigned(Some(n)) => {
+            i64::try_from(n).map_or(Value::Real(n as f64), Value::Integer)
+        }
+        ColumnValue::Signed(Some(n)) => Value::Integer(n),
+        ColumnValue::Percent(v) => Value::Real(f64::from(v)),
+        ColumnValue::Float(Some(v)) => Value::Real(v),
+        ColumnValue::Text(Some(s)) => Value::Text(s.into_owned()),
+        ColumnValue::Unsigned(None)
+        | ColumnValue::Signed(None)
+        | ColumnValue::Float(None)
+        | ColumnValue::Text(None) => Value::Null,
+    }
+}
+
+/// The `snapshots` table: the timestamp, one column per system field and the
+/// "some" avg10 pressure of each resource.
+fn create_snapshots_sql() -> String {
+    let system = crate::SystemMetrics::default();
+    let mut columns = vec![
+        "id INTEGER PRIMARY KEY".to_string(),
+        "timestamp TEXT NOT NULL".to_string(),
+    ];
+    for field in SYSTEM_FIELDS {
+        columns.push(format!(
+            "{} {}",
+            field.key,
+            sql_type(&(field.value)(&system))
+        ));
+    }
+    for resource in PRESSURE_RESOURCES {
+        columns.push(format!("pressure_{resource}_some_avg10 REAL"));
+    }
+    format!("CREATE TABLE snapshots ({})", columns.join(", "))
+}
+
+/// Creates the tables and indexes of a new database.
+const CREATE_SCHEMA: &str = "
+CREATE TABLE process_samples (
+    snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE
+);
+CREATE INDEX snapshots_timestamp ON snapshots (timestamp);
+CREATE INDEX process_samples_snapshot ON process_samples (snapshot_id);
+";
+
+/// Indexes on process columns, created after the columns are added.
+const CREATE_PROCESS_INDEXES: &str = "
+CREATE INDEX IF NOT EXISTS process_samples_pid ON process_samples (pid);
+CREATE INDEX IF NOT EXISTS process_samples_name ON process_samples (name);
+";
+
+/// Writes watch intervals into a database: one `snapshots` row per interval
+/// and one `process_samples` row per process, with every registry column.
+///
+/// Intervals are written in transactions of `batch` snapshots; a pending
+/// transaction is committed when the recorder is dropped.
+pub struct Recorder {
+    connection: Connection,
+    retention: Retention,
+    batch: usize,
+    pending: usize,
+}
+
+impl Recorder {
+    /// Opens (or creates) the database and checks its schema version.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the database cannot be opened, was written by a newer
+    /// version of stop, or its schema cannot be created.
+    pub fn open(
+        target: &RecordTarget,
+        retention: Retention,
+        batch: NonZeroUsize,
+    ) -> Result<Self, Box<dyn Error>> {
+        let RecordTarget::Sqlite(path) = target;
+        let connection =
+            Connection::open(path).map_err(|e| format!("cannot open {}: {e}", path.display()))?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             PRAGMA foreign_keys = ON;",
+        )?;
+        migrate(&connection).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Self {
+            connection,
+            retention,
+            batch: batch.get(),
+            pending: 0,
+        })
+    }
+
+    /// Records one snapshot, committing when the batch is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if writing to the database fails.
+    pub fn record(&mut self, snapshot: &SystemSnapshot) -> rusqlite::Result<()> {
+        if self.pending == 0 {
+            self.connection.execute_batch("BEGIN")?;
+        }
+        let snapshot_id = insert_snapshot(&self.connection, snapshot)?;
+        insert_processes(&self.connection, snapshot_id, snapshot)?;
+        self.pending += 1;
+        if self.pending >= self.batch {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Applies the retention limits and commits the pending snapshots.
+    fn commit(&mut self) -> rusqlite::Result<()> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+        apply_retention(&self.connection, self.retention)?;
+        self.connection.execute_batch("COMMIT")?;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.commit() {
+            eprintln!("Warning: failed to save recorded snapshots: {e}");
+        }
+    }
+}
+
+/// Creates the schema of a new database, or brings an existing one up to date.
+fn migrate(connection: &Connection) -> Result<(), Box<dyn Error>> {
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+             version INTEGER NOT NULL,
+             applied_at TEXT NOT NULL
+         )",
+    )?;
+    let version: Option<i64> =
+        connection.query_row("SELECT max(version) FROM schema_version", [], |row| {
+            row.get(0)
+        })?;
+    match version {
+        None => {
+            connection.execute_batch(&format!(
+                "BEGIN; {}; {CREATE_SCHEMA}",
+                create_snapshots_sql()
+            ))?;
+            connection.execute(
+                "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+                (SCHEMA_VERSION, chrono::Utc::now().to_rfc3339()),
+            )?;
+        }
+        Some(SCHEMA_VERSION) => connection.execute_batch("BEGIN")?,
+        Some(version) => {
+            return Err(format!(
+                "database schema version {version} is not supported (this stop writes version {SCHEMA_VERSION})"
+            )
+            .into());
+        }
+    }
+    add_process_columns(connection)?;
+    connection.execute_batch(&format!("{CREATE_PROCESS_INDEXES} COMMIT"))?;
+    Ok(())
+}
+
+/// Adds registry columns missing from `process_samples`, so databases
+/// recorded by older versions gain the columns added since.
+fn add_process_columns(connection: &Connection) -> rusqlite::Result<()> {
+    let existing: Vec<String> = connection
+        .prepare("SELECT name FROM pragma_table_info('process_samples')")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let process = crate::ProcessInfo::default();
+    for column in COLUMNS {
+        if !existing.iter().any(|name| name == column.key) {
+            connection.execute_batch(&format!(
+                "ALTER TABLE process_samples ADD COLUMN \"{}\" {}",
+                column.key,
+                sql_type(&(column.value)(&process))
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Normalizes the snapshot timestamp to UTC with nanoseconds, so timestamps
+/// sort as text and work with SQLite's date functions.
+fn normalized_timestamp(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp).map_or_else(
+        |_| timestamp.to_string(),
+        |t| {
+            t.with_timezone(&chrono::Utc)
+                .to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)
+        },
+    )
+}
+
+fn insert_snapshot(connection: &Connection, snapshot: &SystemSnapshot) -> rusqlite::Result<i64> {
+    let system = &snapshot.system;
+    let pressure = |resource: fn(&PressureStall) -> Option<&Pressure>| {
+        system
+            .pressure
+            .as_ref()
+            .and_then(resource)
+            .map_or(Value::Null, |p| Value::Real(f64::from(p.some.avg10)))
+    };
+    let mut values = vec![Value::Text(normalized_timestamp(&snapshot.timestamp))];
+    values.extend(SYSTEM_FIELDS.iter().map(|f| sql_value((f.value)(system))));
+    values.push(pressure(|p| p.cpu.as_ref()));
+    values.push(pressure(|p| p.memory.as_ref()));
+    values.push(pressure(|p| p.io.as_ref()));
+
+    let names: Vec<String> = SYSTEM_FIELDS
+        .iter()
+        .map(|f| f.key.to_string())
+        .chain(
+            PRESSURE_RESOURCES
+                .iter()
+                .map(|r| format!("pressure_{r}_some_avg10")),
+        )
+        .collect();
+    let sql = format!(
+        "INSERT INTO snapshots (timestamp, {}) VALUES ({})",
+        names.join(", "),
+        placeholders(values.len())
+    );
+    connection
+        .prepare_cached(&sql)?
+        .execute(params_from_iter(values))?;
+    Ok(connection.last_insert_rowid())
+}
+
+fn insert_processes(
+    connection: &Connection,
+    snapshot_id: i64,
+    snapshot: &SystemSnapshot,
+) -> rusqlite::Result<()> {
+    let names: Vec<String> = COLUMNS.iter().map(|c| format!("\"{}\"", c.key)).collect();
+    let sql = format!(
+        "INSERT INTO process_samples (snapshot_id, {}) VALUES ({})",
+        names.join(", "),
+        placeholders(COLUMNS.len() + 1)
+    );
+    let mut statement = connection.prepare_cached(&sql)?;
+    for process in &snapshot.processes {
+        let values = std::iter::once(Value::Integer(snapshot_id))
+            .chain(COLUMNS.iter().map(|c| sql_value((c.value)(process))));
+        statement.execute(params_from_iter(values))?;
+    }
+    Ok(())
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// Deletes snapshots (and their process samples) beyond the retention limits.
+fn apply_retention(connection: &Connection, retention: Retention) -> rusqlite::Result<()> {
+    if let Some(max_age) = retention.max_age {
+        let cutoff = chrono::Utc::now()
+            - chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+        connection.execute(
+            "DELETE FROM snapshots WHERE timestamp < ?1",
+            [cutoff.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)],
+        )?;
+    }
+    if let Some(max_snapshots) = retention.max_snapshots {
+        connection.execute(
+            "DELETE FROM snapshots WHERE id <= (
+                 SELECT id FROM snapshots ORDER BY id DESC LIMIT 1 OFFSET ?1
+             )",
+            [max_snapshots.get() as i64],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProcessInfo, SystemMetrics};
+
+    fn snapshot(timestamp: &str, pids: &[u32]) -> SystemSnapshot {
+        SystemSnapshot {
+            timestamp: timestamp.to_string(),
+            system: SystemMetrics {
+                cpu_usage: 30.0,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                ..Default::default()
+            },
+            processes: pids
+                .iter()
+                .map(|&pid| ProcessInfo {
+                    pid,
+                    name: format!("proc{pid}"),
+                    cpu_percent: 1.5,
+                    memory_bytes: 1 << 20,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn memory_recorder(retention: Retention, batch: usize) -> Recorder {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch("PRAGMA foreign_keys = ON")
+            .unwrap();
+        migrate(&connection).unwrap();
+        Recorder {
+            connection,
+            retention,
+            batch,
+            pending: 0,
+        }
+    }
+
+    fn count(connection: &Connection, table: &str) -> i64 {
+        connection
+            .query_row(&format!("SELECT count(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            RecordTarget::parse("sqlite:/tmp/stop.db"),
+            Ok(RecordTarget::Sqlite(PathBuf::from("/tmp/stop.db")))
+        );
+        assert!(
+            RecordTarget::parse("sqlite:")
+                .unwrap_err()
+                .contains("missing")
+        );
+        assert!(
+            RecordTarget::parse("postgres://db")
+                .unwrap_err()
+                .contains("expected sqlite:PATH")
+        );
+        assert!(RecordTarget::parse("stop.db").is_err());
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_age("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_age("24h"), Ok(Duration::from_secs(86400)));
+        assert_eq!(parse_age("7d"), Ok(Duration::from_secs(604_800)));
+        for invalid in ["", "7", "d", "7w", "0h", "-1d", "1.5h"] {
+            assert!(parse_age(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let mut recorder = memory_recorder(Retention::default(), 2);
+        recorder
+            .record(&snapshot("2026-01-02T03:04:05+00:00", &[1, 42]))
+            .unwrap();
+        recorder
+            .record(&snapshot("2026-01-02T03:04:07.5+00:00", &[42]))
+            .unwrap();
+        let connection = &recorder.connection;
+        assert_eq!(count(connection, "snapshots"), 2);
+        assert_eq!(count(connection, "process_samples"), 3);
+        let (timestamp, cpu): (String, f64) = connection
+            .query_row(
+                "SELECT timestamp, cpu_usage FROM snapshots ORDER BY id DESC",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(timestamp, "2026-01-02T03:04:07.500000000Z");
+        assert_eq!(cpu, 30.0);
+        let (name, rss, files): (String, i64, Option<i64>) = connection
+            .query_row(
+                "SELECT name, memory_bytes, open_files FROM process_samples \
+                 WHERE pid = 42 ORDER BY snapshot_id LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((name.as_str(), rss, files), ("proc42", 1 << 20, None));
+    }
+
+    #[test]
+    fn test_batched_transactions() {
+        let mut recorder = memory_recorder(Retention::default(), 3);
+        for _ in 0..4 {
+            recorder
+                .record(&snapshot("2026-01-02T03:04:05+00:00", &[1]))
+                .unwrap();
+        }
+        // Three snapshots committed, the fourth pending until the next commit
+        assert_eq!(recorder.pending, 1);
+        assert!(!recorder.connection.is_autocommit());
+        recorder.commit().unwrap();
+        assert!(recorder.connection.is_autocommit());
+        assert_eq!(count(&recorder.connection, "snapshots"), 4);
+    }
+
+    #[test]
+    fn test_retention() {
+        let retention = Retention {
+            max_age: None,
+            max_snapshots: NonZeroUsize::new(2),
+        };
+        let mut recorder = memory_recorder(retention, 1);
+        for second in 1..=4 {
+            recorder
+                .record(&snapshot(
+                    &format!("2026-01-02T03:04:0{second}+00:00"),
+                    &[1, 2],
+                ))
+                .unwrap();
+        }
+        assert_eq!(count(&recorder.connection, "snapshots"), 2);
+        // Samples of deleted snapshots go with them
+        assert_eq!(count(&recorder.connection, "process_samples"), 4);
+
+        let retention = Retention {
+            max_age: Some(Duration::from_secs(3600)),
+            max_snapshots: None,
+        };
+        let mut recorder = memory_recorder(retention, 1);
+        recorder
+            .record(&snapshot("2020-01-01T00:00:00+00:00", &[1]))
+            .unwrap();
+        recorder
+            .record(&snapshot(&chrono::Utc::now().to_rfc3339(), &[1]))
+            .unwrap();
+        assert_eq!(count(&recorder.connection, "snapshots"), 1);
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let connection = Connection::open_in_memory().unwrap();
+        migrate(&connection).unwrap();
+        // Opening again keeps the version and the data layout
+        migrate(&connection).unwrap();
+        let versions: i64 = connection
+            .query_row("SELECT count(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(versions, 1);
+
+        connection
+            .execute("UPDATE schema_version SET version = 99", [])
+            .unwrap();
+        assert!(
+            migrate(&connection)
+                .unwrap_err()
+                .to_string()
+                .contains("schema version 99 is not supported")
+        );
+    }
+
+    #[test]
+    fn test_missing_columns_are_added() {
+        let connection = Connection::open_in_memory().unwrap();
+        migrate(&connection).unwrap();
+        connection
+            .execute_batch("ALTER TABLE process_samples DROP COLUMN open_files")
+            .unwrap();
+        migrate(&connection).unwrap();
+        let has_column: bool = connection
+            .query_row(
+                "SELECT count(*) FROM pragma_table_info('process_samples') WHERE name = 'open_files'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(has_column);
+    }
+}
@@ -0,0 +1,480 @@
+use crate::columns::{self, Align, Column, ColumnValue, Format, Level, SYSTEM_CPU, SYSTEM_MEMORY};
+use crate::psi::Pressure;
+use crate::{SystemMetrics, SystemSnapshot};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Columns of the process table unless `--columns` is given.
+const DEFAULT_COLUMNS: &[&str] = &[
+    "pid", "name", "cpu", "mem", "rss", "threads", "user", "command",
+];
+
+/// Stylesheet of the HTML report; levels use the terminal colors.
+const STYLE: &str = "body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; font-size: 0.9em; }
+th, td { padding: 0.25em 0.75em; border-bottom: 1px solid #ddd; text-align: left; }
+th { background: #f4f4f4; }
+.num { text-align: right; font-variant-numeric: tabular-nums; }
+.meta { color: #777; }
+.normal { color: #2a9d2a; }
+.warn { color: #b58900; }
+.high { color: #d0312d; font-weight: bold; }
+table.sortable th { cursor: pointer; user-select: none; }
+table.sortable th[aria-sort=ascending]::after { content: \" \\25B2\"; }
+table.sortable th[aria-sort=descending]::after { content: \" \\25BC\"; }
+";
+
+/// Sorts the process table by the clicked header, toggling the direction on
+/// repeated clicks. Cells with a `data-value` sort numerically, an empty one
+/// (unavailable) below every number; others sort as text with embedded
+/// numbers compared by value.
+const SCRIPT: &str = "function compareValues(x, y) {
+  if (x === '' || y === '') {
+    return (x === '' ? 0 : 1) - (y === '' ? 0 : 1);
+  }
+  return parseFloat(x) - parseFloat(y);
+}
+document.querySelectorAll('table.sortable').forEach(function (table) {
+  var headers = table.querySelectorAll('th');
+  headers.forEach(function (th, index) {
+    th.addEventListener('click', function () {
+      var ascending = th.getAttribute('aria-sort') !== 'ascending';
+      headers.forEach(function (h) { h.removeAttribute('aria-sort'); });
+      th.setAttribute('aria-sort', ascending ? 'ascending' : 'descending');
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      rows.sort(function (a, b) {
+        var x = a.cells[index], y = b.cells[index];
+        var order = 'value' in x.dataset && 'value' in y.dataset
+          ? compareValues(x.dataset.value, y.dataset.value)
+          : x.textContent.localeCompare(y.textContent, undefined, { numeric: true });
+        return ascending ? order : -order;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+";
+
+/// One row of the system summary: label, value and its level, if colored.
+struct SummaryRow {
+    label: &'static str,
+    value: String,
+    level: Option<Level>,
+}
+
+/// The system figures shown above the process table, as in the terminal header.
+fn summary(system: &SystemMetrics) -> Vec<SummaryRow> {
+    let limits = system.limits.as_ref();
+    let mut rows = vec![
+        SummaryRow {
+            label: "CPU",
+            value: match limits.and_then(|l| l.cpu_limit_cores) {
+                Some(cores) => format!(
+                    "{:.1}% of {cores:.2} cores (cgroup limit)",
+                    system.cpu_usage
+                ),
+                None => format!("{:.1}%", system.cpu_usage),
+            },
+            level: Some(SYSTEM_CPU.level(system.cpu_usage)),
+        },
+        SummaryRow {
+            label: "Memory",
+            value: format!(
+                "{:.1}% ({} / {} MB){}",
+                system.memory_percent,
+                system.memory_used / 1024 / 1024,
+                system.memory_total / 1024 / 1024,
+                if limits.is_some_and(|l| l.memory_limit_bytes.is_some()) {
+                    " (cgroup limit)"
+                } else {
+                    ""
+                }
+            ),
+            level: Some(SYSTEM_MEMORY.level(system.memory_percent)),
+        },
+    ];
+    if let Some(available) = system.memory_available {
+        let cache = system.cached.unwrap_or(0) + system.buffers.unwrap_or(0);
+        rows.push(SummaryRow {
+            label: "Available",
+            value: format!(
+                "{} MB (cache {} MB, dirty {} MB)",
+                available / 1024 / 1024,
+                cache / 1024 / 1024,
+                system.dirty.unwrap_or(0) / 1024 / 1024
+            ),
+            level: None,
+        });
+    }
+    if let Some(pressure) = &system.pressure {
+        let resources = [
+            ("Pressure CPU", pressure.cpu.as_ref()),
+            ("Pressure memory", pressure.memory.as_ref()),
+            ("Pressure I/O", pressure.io.as_ref()),
+        ];
+        for (label, resource) in resources {
+            rows.push(SummaryRow {
+                label,
+                value: resource.map_or_else(
+                    || "-".to_string(),
+                    |p: &Pressure| format!("{:.1}% (some, avg10)", p.some.avg10),
+                ),
+                level: resource.map(|p| SYSTEM_CPU.level(p.some.avg10)),
+            });
+        }
+    }
+    rows
+}
+
+/// The selected columns, or the report's default set.
+pub fn report_columns(columns: Option<&[&'static Column]>) -> Vec<&'static Column> {
+    match columns {
+        Some(columns) => columns.to_vec(),
+        None => DEFAULT_COLUMNS
+            .iter()
+            .filter_map(|name| columns::parse_column(name).ok())
+            .collect(),
+    }
+}
+
+/// Line describing the process selection.
+fn selection(snapshot: &SystemSnapshot, sort_by: &str) -> String {
+    let count = snapshot.processes.len();
+    format!(
+        "Sorted by {sort_by}, showing {count} process{}.",
+        if count == 1 { "" } else { "es" }
+    )
+}
+
+/// Escapes a Markdown table cell: pipes are backslash-escaped, `<` and `&`
+/// written as entities so cells cannot open HTML tags, and line breaks
+/// replaced by spaces so the row stays on one line.
+pub fn escape_markdown_cell(value: &str) -> Cow<'_, str> {
+    if !value.contains(['|', '<', '&', '\n', '\r']) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '|' => escaped.push_str("\\|"),
+            '<' => escaped.push_str("&lt;"),
+            '&' => escaped.push_str("&amp;"),
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Escapes text and attribute values for HTML.
+pub fn escape_html(value: &str) -> Cow<'_, str> {
+    if !value.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// GitHub-flavored Markdown alignment marker of a column.
+fn markdown_alignment(column: &Column) -> &'static str {
+    match column.align {
+        Align::Left => ":--",
+        Align::Right => "--:",
+    }
+}
+
+/// Writes a snapshot as GitHub-flavored Markdown: a system summary table
+/// followed by a process table with the selected columns.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_markdown(
+    out: &mut impl Write,
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+    sort_by: &str,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "# stop report\n\nSnapshot taken {} by stop v{}.\n",
+        snapshot.timestamp,
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    writeln!(out, "## System\n\n| Metric | Value |\n| :-- | :-- |")?;
+    for row in summary(&snapshot.system) {
+        writeln!(
+            out,
+            "| {} | {} |",
+            row.label,
+            escape_markdown_cell(&row.value)
+        )?;
+    }
+
+    let columns = report_columns(columns);
+    writeln!(out, "\n## Processes\n\n{}\n", selection(snapshot, sort_by))?;
+    let titles: Vec<&str> = columns.iter().map(|c| c.title).collect();
+    let alignments: Vec<&str> = columns.iter().map(|c| markdown_alignment(c)).collect();
+    writeln!(out, "| {} |", titles.join(" | "))?;
+    writeln!(out, "| {} |", alignments.join(" | "))?;
+    for process in &snapshot.processes {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|c| escape_markdown_cell(&columns::plain_cell(c, process)).into_owned())
+            .collect();
+        writeln!(out, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// CSS class of a level; normal process values are left uncolored.
+fn level_class(level: Level) -> &'static str {
+    match level {
+        Level::Normal => "normal",
+        Level::Warn => "warn",
+        Level::High => "high",
+    }
+}
+
+/// Opening `<td>` of a process cell: right-aligned columns get the `num`
+/// class, percentages are colored by the column's thresholds and numeric
+/// values are carried in `data-value` for sorting.
+fn html_cell_open(column: &Column, value: &ColumnValue) -> String {
+    let mut classes = Vec::new();
+    if column.align == Align::Right {
+        classes.push("num");
+    }
+    if let (Format::Percent(thresholds), Some(v)) = (column.format, value.as_f64()) {
+        match thresholds.level(v as f32) {
+            Level::Normal => {}
+            level => classes.push(level_class(level)),
+        }
+    }
+    let mut td = String::from("<td");
+    if !classes.is_empty() {
+        td.push_str(&format!(" class=\"{}\"", classes.join(" ")));
+    }
+    if !matches!(value, ColumnValue::Text(_)) {
+        // Unavailable values get an empty value, sorted below every number
+        let sort_value = value.as_f64().map_or_else(String::new, |v| v.to_string());
+        td.push_str(&format!(" data-value=\"{sort_value}\""));
+    }
+    td.push('>');
+    td
+}
+
+/// Writes a snapshot as a self-contained HTML page: the system summary and a
+/// process table sortable by clicking its headers, colored with the same
+/// thresholds as the terminal output.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_html(
+    out: &mut impl Write,
+    snapshot: &SystemSnapshot,
+    columns: Option<&[&'static Column]>,
+    sort_by: &str,
+) -> io::Result<()> {
+    let timestamp = escape_html(&snapshot.timestamp);
+    writeln!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>stop report {timestamp}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>"
+    )?;
+    writeln!(
+        out,
+        "<h1>stop report</h1>\n<p class=\"meta\">Snapshot taken {timestamp} by stop v{}.</p>",
+        env!("CARGO_PKG_VERSION")
+    )?;
+
+    writeln!(out, "<h2>System</h2>\n<table>\n<tbody>")?;
+    for row in summary(&snapshot.system) {
+        let class = row
+            .level
+            .map(|level| format!(" class=\"{}\"", level_class(level)))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "<tr><th>{}</th><td{class}>{}</td></tr>",
+            row.label,
+            escape_html(&row.value)
+        )?;
+    }
+    writeln!(out, "</tbody>\n</table>")?;
+
+    let columns = report_columns(columns);
+    writeln!(
+        out,
+        "<h2>Processes</h2>\n<p class=\"meta\">{} Click a column header to sort.</p>",
+        selection(snapshot, &escape_html(sort_by))
+    )?;
+    writeln!(out, "<table class=\"sortable\">\n<thead>\n<tr>")?;
+    for column in &columns {
+        let class = match column.align {
+            Align::Left => "",
+            Align::Right => " class=\"num\"",
+        };
+        writeln!(out, "<th{class}>{}</th>", escape_html(column.title))?;
+    }
+    writeln!(out, "</tr>\n</thead>\n<tbody>")?;
+    for process in &snapshot.processes {
+        write!(out, "<tr>")?;
+        for column in &columns {
+            let value = (column.value)(process);
+            write!(
+                out,
+                "{}{}</td>",
+                html_cell_open(column, &value),
+                escape_html(&columns::plain_cell(column, process))
+            )?;
+        }
+        writeln!(out, "</tr>")?;
+    }
+    writeln!(
+        out,
+        "</tbody>\n</table>\n<script>\n{SCRIPT}</script>\n</body>\n</html>"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProcessInfo;
+
+    fn snapshot() -> SystemSnapshot {
+        SystemSnapshot {
+            timestamp: "2026-01-02T03:04:05+00:00".to_string(),
+            system: SystemMetrics {
+                cpu_usage: 85.0,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                ..Default::default()
+            },
+            processes: vec![
+                ProcessInfo {
+                    pid: 42,
+                    name: "web".to_string(),
+                    cpu_percent: 30.0,
+                    memory_percent: 1.0,
+                    memory_bytes: 3 << 20,
+                    command: "web --motd 'a|b' <x>".to_string(),
+                    ..Default::default()
+                },
+                ProcessInfo {
+                    pid: 7,
+                    name: "idle".to_string(),
+                    memory_percent: 6.0,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn columns(names: &[&str]) -> Vec<&'static Column> {
+        names
+            .iter()
+            .map(|n| columns::parse_column(n).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_escape_markdown_cell() {
+        assert!(matches!(escape_markdown_cell("nginx"), Cow::Borrowed(_)));
+        assert_eq!(escape_markdown_cell("a|b"), r"a\|b");
+        assert_eq!(escape_markdown_cell("<b>&"), "&lt;b>&amp;");
+        assert_eq!(escape_markdown_cell("two\nlines"), "two lines");
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert!(matches!(escape_html("nginx"), Cow::Borrowed(_)));
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        let mut out = Vec::new();
+        write_markdown(
+            &mut out,
+            &snapshot(),
+            Some(&columns(&["pid", "name", "cpu", "rss", "command"])),
+            "cpu",
+        )
+        .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("# stop report\n"));
+        assert!(text.contains("| CPU | 85.0% |\n| Memory | 25.0% (2048 / 8192 MB) |\n"));
+        assert!(text.contains("Sorted by cpu, showing 2 processes."));
+        assert!(
+            text.contains(
+                "| PID | Name | CPU% | RSS | Command |\n| :-- | :-- | --: | --: | :-- |\n"
+            )
+        );
+        assert!(text.contains(r"| 42 | web | 30.0% | 3.0 M | web --motd 'a\|b' &lt;x> |"));
+        assert!(text.ends_with("| 7 | idle | 0.0% | 0 B | - |\n"));
+    }
+
+    #[test]
+    fn test_write_markdown_default_columns() {
+        let mut out = Vec::new();
+        write_markdown(&mut out, &snapshot(), None, "mem").unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("| PID | Name | CPU% | Mem% | RSS | Threads | User | Command |"));
+        assert!(text.contains("Sorted by mem"));
+    }
+
+    #[test]
+    fn test_write_html() {
+        let mut out = Vec::new();
+        write_html(
+            &mut out,
+            &snapshot(),
+            Some(&columns(&["pid", "name", "cpu", "mem", "command"])),
+            "cpu",
+        )
+        .unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.trim_end().ends_with("</html>"));
+        // System CPU above 80% is high, memory at 25% normal
+        assert!(html.contains("<tr><th>CPU</th><td class=\"high\">85.0%</td></tr>"));
+        assert!(html.contains("<td class=\"normal\">25.0% (2048 / 8192 MB)</td>"));
+        assert!(html.contains("<th class=\"num\">CPU%</th>"));
+        assert!(html.contains("<tr><td data-value=\"42\">42</td><td>web</td>"));
+        // 30% of a core is a warning, 6% of memory high, normal values uncolored
+        assert!(html.contains("<td class=\"num warn\" data-value=\"30\">30.0%</td>"));
+        assert!(html.contains("<td class=\"num high\" data-value=\"6\">6.0%</td>"));
+        assert!(html.contains("<td class=\"num\" data-value=\"1\">1.0%</td>"));
+        assert!(html.contains("<td>web --motd &#39;a|b&#39; &lt;x&gt;</td>"));
+        assert!(html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_unavailable_values_have_empty_sort_value() {
+        let process = ProcessInfo::default();
+        let column = columns::parse_column("files").unwrap();
+        let value = (column.value)(&process);
+        assert_eq!(
+            html_cell_open(column, &value),
+            "<td class=\"num\" data-value=\"\">"
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use crate::columns::SelectedSnapshot;
+use crate::filter::FilterExpr;
+use crate::{
+    Args, CollectOptions, DEFAULT_TOP_N, SORT_KEYS, SystemSnapshot, collector, is_sort_key,
+    matches_search, prometheus, sort_processes,
+};
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How long a client may take to send its request line and headers in total.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long writing a response may block on a client that does not read.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Size limit of the request line and headers.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Connections handled at once; further ones are closed right away.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Arguments of `stop serve`.
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1:9256",
+        help = "Address to listen on (port 0 picks a free port)"
+    )]
+    pub listen: String,
+}
+
+/// Response of one request.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: impl std::fmt::Display) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{message}\n"),
+        }
+    }
+}
+
+/// Process selection from the query string, defaulting to the command line.
+#[derive(Debug, PartialEq)]
+struct Query {
+    filter: Option<String>,
+    sort_by: Option<String>,
+    top_n: Option<usize>,
+}
+
+impl Query {
+    /// Parses `filter`, `sort_by` and `top_n` from a URL query string.
+    fn parse(query: &str) -> Result<Self, String> {
+        let mut parsed = Self {
+            filter: None,
+            sort_by: None,
+            top_n: None,
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match percent_decode(key)?.as_str() {
+                "filter" => parsed.filter = Some(value),
+                "sort_by" if is_sort_key(&value) => parsed.sort_by = Some(value),
+                "sort_by" => {
+                    return Err(format!(
+                        "invalid sort_by '{value}' (expected {})",
+                        SORT_KEYS.join(", ")
+                    ));
+                }
+                "top_n" => {
+                    parsed.top_n = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid top_n '{value}'"))?,
+                    );
+                }
+                key => {
+                    return Err(format!(
+                        "unknown query parameter '{key}' (expected filter, sort_by, top_n)"
+                    ));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Decodes `%XX` escapes and `+` (space) in a query string component.
+fn percent_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let byte = s
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("invalid percent escape in '{s}'"))?;
+                decoded.push(byte);
+                i += 2;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| format!("invalid UTF-8 in '{s}'"))
+}
+
+/// Serves metrics over HTTP until the process is stopped.
+///
+/// One collector is kept for the lifetime of the server, so CPU usage on each
+/// scrape covers the time since the previous one. Each connection is handled
+/// on its own thread, within `REQUEST_TIMEOUT` and `MAX_REQUEST_BYTES`, so a
+/// slow client cannot hold up others; `/metrics` and `/snapshot` requests
+/// take turns collecting a fresh snapshot.
+///
+/// # Errors
+///
+/// Returns error if the address cannot be bound.
+pub fn serve_mode(args: &Args, serve: &ServeArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(filter) = &args.filter {
+        FilterExpr::parse(filter).map_err(|e| format!("invalid --filter: {e}"))?;
+    }
+    if let Some(sort_by) = args.sort_by.as_deref().filter(|s| !is_sort_key(s)) {
+        return Err(format!(
+            "invalid --sort-by '{sort_by}' (expected {})",
+            SORT_KEYS.join(", ")
+        )
+        .into());
+    }
+
+    let listener = TcpListener::bind(&serve.listen)
+        .map_err(|e| format!("cannot listen on {}: {e}", serve.listen))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    let server = Server {
+        args,
+        collector: Mutex::new(collector::from_args(args)),
+        connections: AtomicUsize::new(0),
+    };
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Warning: connection failed: {e}");
+                    continue;
+                }
+            };
+            if server.connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS {
+                server.connections.fetch_sub(1, Ordering::Relaxed);
+                eprintln!("Warning: more than {MAX_CONNECTIONS} connections, closing one");
+                continue;
+            }
+            let server = &server;
+            scope.spawn(move || {
+                if let Err(e) = server.handle(stream) {
+                    eprintln!("Warning: connection failed: {e}");
+                }
+                server.connections.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Reads the request head (request line and headers) within `REQUEST_TIMEOUT`
+/// and returns the request line, or `None` if the head exceeds `MAX_REQUEST_BYTES`.
+///
+/// The body of GET requests is ignored.
+fn read_request_line(mut stream: &TcpStream) -> io::Result<Option<String>> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !(head.windows(4).any(|w| w == b"\r\n\r\n") || head.windows(2).any(|w| w == b"\n\n")) {
+        if head.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        // Each read may only wait for what is left of the deadline, so a
+        // client sending a byte at a time cannot keep the connection open
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "request not received in time",
+            ));
+        }
+        stream.set_read_timeout(Some(remaining))?;
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed before the end of the request",
+            ));
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let line_end = head.iter().position(|&b| b == b'\n').unwrap_or(head.len());
+    Ok(Some(
+        String::from_utf8_lossy(&head[..line_end]).into_owned(),
+    ))
+}
+
+/// HTTP server state, shared by the connection threads.
+struct Server<'a> {
+    args: &'a Args,
+    /// Collector reused across requests, so CPU usage covers the time between scrapes
+    collector: Mutex<Box<dyn collector::Collector>>,
+    /// Connections being handled
+    connections: AtomicUsize,
+}
+
+impl Server<'_> {
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let request_line = read_request_line(&stream)?;
+
+        let mut parts = request_line.as_deref().unwrap_or("").split_whitespace();
+        let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let response = match method {
+            _ if request_line.is_none() => Response::error(
+                "431 Request Header Fields Too Large",
+                format!("request line and headers are limited to {MAX_REQUEST_BYTES} bytes"),
+            ),
+            "GET" | "HEAD" => self.route(target),
+            _ => Response::error("405 Method Not Allowed", "only GET and HEAD are supported"),
+        };
+
+        let mut head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            response.status,
+            response.content_type,
+            response.body.len()
+        );
+        if response.status.starts_with("405") {
+            head.push_str("Allow: GET, HEAD\r\n");
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if method != "HEAD" {
+            stream.write_all(response.body.as_bytes())?;
+        }
+        stream.flush()?;
+        if request_line.is_none() {
+            // Closing with unread request data resets the connection before
+            // the client reads the response, so read a bounded rest first
+            stream.shutdown(Shutdown::Write)?;
+            stream.set_read_timeout(Some(Duration::from_millis(500)))?;
+            let _ = io::copy(
+                &mut (&stream).take(MAX_REQUEST_BYTES as u64 * 8),
+                &mut io::sink(),
+            );
+        }
+        Ok(())
+    }
+
+    fn route(&self, target: &str) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        match path {
+            "/healthz" => Response::ok("text/plain; charset=utf-8", "ok\n".to_string()),
+            "/metrics" | "/snapshot" => {
+                let snapshot = match Query::parse(query)
+                    .map_err(|e| Response::error("400 Bad Request", e))
+                    .and_then(|query| self.snapshot(&query))
+                {
+                    Ok(snapshot) => snapshot,
+                    Err(response) => return response,
+                };
+                if path == "/metrics" {
+                    let mut body = Vec::new();
+                    // Writing to a Vec cannot fail
+                    let _ = prometheus::write_metrics(&mut body, &snapshot);
+                    Response::ok(
+                        prometheus::CONTENT_TYPE,
+                        String::from_utf8_lossy(&body).into_owned(),
+                    )
+                } else {
+                    let json = match self.args.columns.as_deref() {
+                        Some(columns) => {
+                            serde_json::to_string_pretty(&SelectedSnapshot::new(&snapshot, columns))
+                        }
+                        None => serde_json::to_string_pretty(&snapshot),
+                    };
+                    match json {
+                        Ok(json) => Response::ok("application/json", json + "\n"),
+                        Err(e) => Response::error("500 Internal Server Error", e),
+                    }
+                }
+            }
+            _ => Response::error("404 Not Found", "endpoints: /metrics, /snapshot, /healthz"),
+        }
+    }
+
+    /// Collects a snapshot and applies the search, filter, sort and limit.
+    fn snapshot(&self, query: &Query) -> Result<SystemSnapshot, Response> {
+        let filter = match query.filter.as_deref().or(self.args.filter.as_deref()) {
+            Some(expression) => Some(
+                FilterExpr::parse(expression).map_err(|e| Response::error("400 Bad Request", e))?,
+            ),
+            None => None,
+        };
+        let sort_by = query
+            .sort_by
+            .as_deref()
+            .or(self.args.sort_by.as_deref())
+            .unwrap_or("cpu");
+        let limit = query.top_n.or(self.args.top_n).unwrap_or(DEFAULT_TOP_N);
+
+        let options = CollectOptions::from_args(self.args).selecting(filter.as_ref(), sort_by);
+        let mut snapshot = self
+            .collector
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .collect(&options)
+            .map_err(|e| Response::error("500 Internal Server Error", e))?;
+        let search = self.args.search.as_deref().map(str::to_lowercase);
+        let own_pid = std::process::id();
+        snapshot.processes.retain(|p| {
+            search
+                .as_deref()
+                .is_none_or(|term| p.pid != own_pid && matches_search(p, term))
+                && filter.as_ref().is_none_or(|f| f.matches(p))
+        });
+        sort_processes(&mut snapshot.processes, sort_by);
+        snapshot.processes.truncate(limit);
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Query::parse("filter=cpu+%3E+10%20and%20name%20%3D%3D%20nginx&sort_by=mem&top_n=5"),
+            Ok(Query {
+                filter: Some("cpu > 10 and name == nginx".to_string()),
+                sort_by: Some("mem".to_string()),
+                top_n: Some(5),
+            })
+        );
+        assert_eq!(
+            Query::parse(""),
+            Ok(Query {
+                filter: None,
+                sort_by: None,
+                top_n: None,
+            })
+        );
+        assert!(Query::parse("top_n=ten").unwrap_err().contains("top_n"));
+        assert!(Query::parse("limit=5").unwrap_err().contains("'limit'"));
+        assert_eq!(
+            Query::parse("sort_by=MEM").unwrap().sort_by.as_deref(),
+            Some("MEM")
+        );
+        let error = Query::parse("sort_by=bogus").unwrap_err();
+        assert!(
+            error.contains("'bogus'") && error.contains("cputime"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%2Fb+c").unwrap(), "a/b c");
+        assert_eq!(percent_decode("%C3%A9").unwrap(), "é");
+        assert!(percent_decode("%4").is_err());
+        assert!(percent_decode("%zz").is_err());
+        assert!(percent_decode("%FF").is_err());
+    }
+}
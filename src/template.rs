@@ -0,0 +1,513 @@
+use crate::columns::{self, COLUMNS, Column, ColumnValue, SYSTEM_FIELDS, SystemField};
+use crate::{ProcessInfo, SystemSnapshot, format_bytes_parts};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Unit helper applied with `{field|helper}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Helper {
+    /// Human-readable size, e.g. `1.5G`
+    Bytes,
+    Kib,
+    Mib,
+    Gib,
+}
+
+impl Helper {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "bytes" => Ok(Self::Bytes),
+            "kib" => Ok(Self::Kib),
+            "mib" => Ok(Self::Mib),
+            "gib" => Ok(Self::Gib),
+            _ => Err(format!(
+                "unknown helper '{name}' (available: bytes, kib, mib, gib)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Format spec after `:`, a subset of Rust's: `[[fill]align][width][.precision]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Spec {
+    fill: char,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            width: 0,
+            precision: None,
+        }
+    }
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+        let mut parsed = Self::default();
+        let mut rest = spec;
+        let mut chars = spec.chars();
+        match (chars.next(), chars.next()) {
+            (Some(fill), Some(c)) if align(c).is_some() => {
+                parsed.fill = fill;
+                parsed.align = align(c);
+                rest = &spec[fill.len_utf8() + 1..];
+            }
+            (Some(c), _) if align(c).is_some() => {
+                parsed.align = align(c);
+                rest = &spec[1..];
+            }
+            _ => {}
+        }
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest, None),
+        };
+        let invalid = || format!("invalid format spec ':{spec}'");
+        if !width.is_empty() {
+            parsed.width = width.parse().map_err(|_| invalid())?;
+        }
+        if let Some(precision) = precision {
+            parsed.precision = Some(precision.parse().map_err(|_| invalid())?);
+        }
+        Ok(parsed)
+    }
+
+    /// Pads `value` to the width; numbers align right and text left by default.
+    fn pad(&self, value: &str, numeric: bool) -> String {
+        let len = value.chars().count();
+        if len >= self.width {
+            return value.to_string();
+        }
+        let padding = self.width - len;
+        let fill = |n: usize| self.fill.to_string().repeat(n);
+        let align = self
+            .align
+            .unwrap_or(if numeric { Align::Right } else { Align::Left });
+        match align {
+            Align::Left => format!("{value}{}", fill(padding)),
+            Align::Right => format!("{}{value}", fill(padding)),
+            Align::Center => format!(
+                "{}{value}{}",
+                fill(padding / 2),
+                fill(padding - padding / 2)
+            ),
+        }
+    }
+}
+
+/// What a placeholder refers to.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Process(&'static Column),
+    System(&'static SystemField),
+    /// Snapshot timestamp (RFC3339)
+    Timestamp,
+    /// Number of processes in the output
+    Count,
+}
+
+#[derive(Debug, Clone)]
+struct Placeholder {
+    field: Field,
+    helper: Option<Helper>,
+    spec: Spec,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed output template such as `{pid}\t{name}\t{cpu:.1}`.
+///
+/// Placeholders are `{field}`, `{field:spec}`, `{field|helper}` or
+/// `{field|helper:spec}`:
+/// - fields are column names or keys (`cpu`, `cpu_percent`), `system.<key>`
+///   (`system.cpu_usage`), `timestamp` and `count`
+/// - helpers convert byte counts: `bytes` (human-readable), `kib`, `mib`, `gib`
+/// - specs are `[[fill]align][width][.precision]` with `<`, `>` or `^`
+///
+/// `{{` and `}}` are literal braces; `\t`, `\n` and `\\` are tab, line feed
+/// and backslash.
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parses a template, rejecting unknown fields, helpers and specs.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invalid placeholder.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err("unmatched '}' in template (use '}}' for a brace)".to_string()),
+                '\\' => match chars.peek() {
+                    Some('t') => {
+                        chars.next();
+                        literal.push('\t');
+                    }
+                    Some('n') => {
+                        chars.next();
+                        literal.push('\n');
+                    }
+                    Some('\\') => {
+                        chars.next();
+                        literal.push('\\');
+                    }
+                    _ => literal.push('\\'),
+                },
+                '{' => {
+                    let mut content = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => content.push(c),
+                            None => {
+                                return Err(
+                                    "unclosed '{' in template (use '{{' for a brace)".to_string()
+                                );
+                            }
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(parse_placeholder(&content)?));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Parses a header or footer template, which cannot use process fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template is invalid or references a process field.
+    pub fn parse_summary(template: &str) -> Result<Self, String> {
+        let parsed = Self::parse(template)?;
+        if let Some(column) = parsed.columns().next() {
+            return Err(format!(
+                "'{}' is a process field; header and footer templates can use system.<key>, timestamp and count",
+                column.name
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Process columns the template references.
+    pub fn columns(&self) -> impl Iterator<Item = &'static Column> + '_ {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Placeholder(Placeholder {
+                field: Field::Process(column),
+                ..
+            }) => Some(*column),
+            _ => None,
+        })
+    }
+
+    /// Renders the template for one process, or for the snapshot as a whole.
+    fn render(&self, snapshot: &SystemSnapshot, process: Option<&ProcessInfo>) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    let value = match placeholder.field {
+                        // Header and footer templates have no process fields
+                        Field::Process(column) => {
+                            process.map_or(ColumnValue::Text(None), |p| (column.value)(p))
+                        }
+                        Field::System(field) => (field.value)(&snapshot.system),
+                        Field::Timestamp => {
+                            ColumnValue::Text(Some(Cow::Borrowed(snapshot.timestamp.as_str())))
+                        }
+                        Field::Count => {
+                            ColumnValue::Unsigned(Some(snapshot.processes.len() as u64))
+                        }
+                    };
+                    rendered.push_str(&format_value(&value, placeholder));
+                }
+            }
+        }
+        rendered
+    }
+}
+
+fn parse_placeholder(content: &str) -> Result<Placeholder, String> {
+    let (head, spec) = content.split_once(':').unwrap_or((content, ""));
+    let (name, helper) = match head.split_once('|') {
+        Some((name, helper)) => (name.trim(), Some(Helper::parse(helper.trim())?)),
+        None => (head.trim(), None),
+    };
+    let field = parse_field(name)?;
+    let numeric = match field {
+        Field::Process(column) => !matches!(
+            (column.value)(&ProcessInfo::default()),
+            ColumnValue::Text(_)
+        ),
+        Field::System(_) | Field::Count => true,
+        Field::Timestamp => false,
+    };
+    if helper.is_some() && !numeric {
+        return Err(format!(
+            "helper '{}' needs a numeric field, not '{name}'",
+            head.trim()
+        ));
+    }
+    Ok(Placeholder {
+        field,
+        helper,
+        spec: Spec::parse(spec)?,
+    })
+}
+
+fn parse_field(name: &str) -> Result<Field, String> {
+    match name {
+        "timestamp" => return Ok(Field::Timestamp),
+        "count" => return Ok(Field::Count),
+        _ => {}
+    }
+    if let Some(key) = name.strip_prefix("system.") {
+        return SYSTEM_FIELDS
+            .iter()
+            .find(|field| field.key == key)
+            .map(Field::System)
+            .ok_or_else(|| {
+                let keys: Vec<&str> = SYSTEM_FIELDS.iter().map(|f| f.key).collect();
+                format!(
+                    "unknown system field '{key}' (available: {})",
+                    keys.join(", ")
+                )
+            });
+    }
+    COLUMNS
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(name) || c.key == name)
+        .map(Field::Process)
+        .ok_or_else(|| {
+            format!(
+                "unknown field '{name}' in template (available: {}, system.<key>, timestamp, count)",
+                columns::column_names()
+            )
+        })
+}
+
+/// Formats a value with the placeholder's helper and spec; `-` if unavailable.
+fn format_value(value: &ColumnValue, placeholder: &Placeholder) -> String {
+    let spec = &placeholder.spec;
+    let number = value.as_f64();
+    let float = |f: f64| match spec.precision {
+        Some(precision) => format!("{f:.precision$}"),
+        None => f.to_string(),
+    };
+    let formatted = match (placeholder.helper, value) {
+        (_, ColumnValue::Text(Some(text))) => match spec.precision {
+            Some(precision) => text.chars().take(precision).collect(),
+            None => text.to_string(),
+        },
+        (Some(helper), _) => match number {
+            Some(n) => match helper {
+                Helper::Bytes => {
+                    let (value, unit) = format_bytes_parts(n as u64);
+                    format!("{value}{unit}")
+                }
+                Helper::Kib => float(n / 1024.0),
+                Helper::Mib => float(n / (1024.0 * 1024.0)),
+                Helper::Gib => float(n / (1024.0 * 1024.0 * 1024.0)),
+            },
+            None => "-".to_string(),
+        },
+        (None, ColumnValue::Unsigned(Some(n))) => n.to_string(),
+        (None, ColumnValue::Signed(Some(n))) => n.to_string(),
+        (None, ColumnValue::Percent(v)) => match spec.precision {
+            Some(precision) => format!("{v:.precision$}"),
+            None => v.to_string(),
+        },
+        (None, ColumnValue::Float(Some(f))) => float(*f),
+        (None, _) => "-".to_string(),
+    };
+    spec.pad(&formatted, !matches!(value, ColumnValue::Text(_)))
+}
+
+/// Writes the header, one rendered line per process and the footer.
+///
+/// # Errors
+///
+/// Returns error if writing fails.
+pub fn write_snapshot(
+    out: &mut impl Write,
+    snapshot: &SystemSnapshot,
+    template: &Template,
+    header: Option<&Template>,
+    footer: Option<&Template>,
+) -> io::Result<()> {
+    if let Some(header) = header {
+        writeln!(out, "{}", header.render(snapshot, None))?;
+    }
+    for process in &snapshot.processes {
+        writeln!(out, "{}", template.render(snapshot, Some(process)))?;
+    }
+    if let Some(footer) = footer {
+        writeln!(out, "{}", footer.render(snapshot, None))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SystemMetrics;
+
+    fn snapshot() -> SystemSnapshot {
+        SystemSnapshot {
+            timestamp: "2026-01-02T03:04:05+00:00".to_string(),
+            system: SystemMetrics {
+                cpu_usage: 30.25,
+                memory_total: 8 << 30,
+                memory_used: 2 << 30,
+                memory_percent: 25.0,
+                ..Default::default()
+            },
+            processes: vec![
+                ProcessInfo {
+                    pid: 42,
+                    name: "web".to_string(),
+                    cpu_percent: 12.345,
+                    memory_bytes: 3 << 29,
+                    user: "1000".to_string(),
+                    ..Default::default()
+                },
+                ProcessInfo {
+                    pid: 7,
+                    name: "init".to_string(),
+                    memory_bytes: 512,
+                    open_files: Some(3),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn render(template: &str) -> Vec<String> {
+        let template = Template::parse(template).unwrap();
+        let snapshot = snapshot();
+        snapshot
+            .processes
+            .iter()
+            .map(|p| template.render(&snapshot, Some(p)))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_fields_and_specs() {
+        assert_eq!(
+            render(r"{pid}\t{name}\t{cpu:.1}"),
+            ["42\tweb\t12.3", "7\tinit\t0.0"]
+        );
+        assert_eq!(
+            render("{pid:>4}|{name:<6}|"),
+            ["  42|web   |", "   7|init  |"]
+        );
+        assert_eq!(render("{name:*^7}"), ["**web**", "*init**"]);
+        assert_eq!(
+            render("{cpu_percent} {memory_bytes}"),
+            ["12.345 1610612736", "0 512"]
+        );
+        assert_eq!(render("{files}"), ["-", "3"]);
+        assert_eq!(render("{name:.2}"), ["we", "in"]);
+        assert_eq!(render("{{{pid}}}"), ["{42}", "{7}"]);
+        assert_eq!(render(r"a\\b\q"), [r"a\b\q", r"a\b\q"]);
+    }
+
+    #[test]
+    fn test_render_helpers() {
+        assert_eq!(render("{rss|bytes}"), ["1.5G", "512B"]);
+        assert_eq!(render("{rss|mib:.1}"), ["1536.0", "0.0"]);
+        assert_eq!(render("{rss|gib:>6.2}"), ["  1.50", "  0.00"]);
+        assert_eq!(render("{files|kib}"), ["-", "0.0029296875"]);
+    }
+
+    #[test]
+    fn test_render_system_fields() {
+        assert_eq!(
+            render("{system.cpu_usage:.2} {system.memory_total|gib} {count} {timestamp}")[0],
+            "30.25 8 2 2026-01-02T03:04:05+00:00"
+        );
+    }
+
+    #[test]
+    fn test_write_snapshot_with_header_and_footer() {
+        let mut out = Vec::new();
+        write_snapshot(
+            &mut out,
+            &snapshot(),
+            &Template::parse(r"{pid}\t{name}").unwrap(),
+            Some(&Template::parse_summary(r"PID\tNAME").unwrap()),
+            Some(
+                &Template::parse_summary("{count} processes, CPU {system.cpu_usage:.0}%").unwrap(),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "PID\tNAME\n42\tweb\n7\tinit\n2 processes, CPU 30%\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |template: &str| Template::parse(template).unwrap_err();
+        assert!(error("{bogus}").contains("unknown field 'bogus'"));
+        assert!(error("{system.bogus}").contains("unknown system field 'bogus'"));
+        assert!(error("{rss|furlongs}").contains("unknown helper 'furlongs'"));
+        assert!(error("{name|bytes}").contains("needs a numeric field"));
+        assert!(error("{cpu:x}").contains("invalid format spec"));
+        assert!(error("{pid").contains("unclosed"));
+        assert!(error("pid}").contains("unmatched"));
+        assert!(
+            Template::parse_summary("{pid}")
+                .unwrap_err()
+                .contains("'pid' is a process field")
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use crate::collector::Collector;
+use crate::columns::{Level, PROCESS_CPU};
+use crate::procfs_collector::ProcfsCollector;
+use crate::{Args, CPU_SAMPLE_INTERVAL_MS, CollectOptions, escape_csv_field, json};
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+use sysinfo::{Pid, Process, System};
+
+/// A single thread (task) of a process.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ThreadInfo {
+    /// Thread ID
+    pub tid: u32,
+    /// Thread name (as set with `pthread_setname_np` / `prctl`)
+    pub name: String,
+    /// Scheduler state (e.g. "Runnable", "Sleeping")
+    pub state: String,
+    /// CPU usage percentage of this thread (0-100)
+    pub cpu_percent: f32,
+}
+
+/// Lists the threads of a process from an already refreshed `System`.
+///
+/// sysinfo reports the main thread (TID == PID) only as the process itself,
+/// whose CPU usage covers all threads, so the main thread's share is derived
+/// by subtracting the other threads' usage.
+///
+/// Returns `None` on platforms where sysinfo does not expose tasks (non-Linux).
+pub fn collect_threads(sys: &System, process: &Process) -> Option<Vec<ThreadInfo>> {
+    let tasks = process.tasks()?;
+    let mut threads: Vec<ThreadInfo> = tasks
+        .iter()
+        .filter(|tid| **tid != process.pid())
+        .filter_map(|tid| sys.process(*tid))
+        .map(|task| ThreadInfo {
+            tid: task.pid().as_u32(),
+            name: task.name().to_string_lossy().into_owned(),
+            state: task.status().to_string(),
+            cpu_percent: task.cpu_usage(),
+        })
+        .collect();
+
+    // Kernel threads have no tasks of their own and are not listed
+    if !tasks.is_empty() {
+        let others: f32 = threads.iter().map(|t| t.cpu_percent).sum();
+        threads.push(ThreadInfo {
+            tid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+            state: process.status().to_string(),
+            cpu_percent: (process.cpu_usage() - others).max(0.0),
+        });
+    }
+
+    threads.sort_by_key(|t| t.tid);
+    Some(threads)
+}
+
+/// Sorts threads in-place: "cpu" (descending, default), "pid"/"tid", or "name".
+fn sort_threads(threads: &mut [ThreadInfo], sort_by: &str) {
+    match sort_by.to_lowercase().as_str() {
+        "pid" | "tid" => threads.sort_by_key(|t| t.tid),
+        "name" => threads.sort_by_cached_key(|t| t.name.to_lowercase()),
+        _ => threads.sort_by(|a, b| {
+            b.cpu_percent
+                .partial_cmp(&a.cpu_percent)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }),
+    }
+}
+
+/// Per-thread breakdown of a single process.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ThreadsReport {
+    /// ISO 8601 timestamp (RFC3339)
+    pub timestamp: String,
+    /// Process ID
+    pub pid: u32,
+    /// Process name
+    pub name: String,
+    /// CPU usage percentage of the whole process (0-100+)
+    pub cpu_percent: f32,
+    /// Total number of threads
+    pub thread_count: usize,
+    /// Threads, sorted by `--sort-by` (CPU descending by default)
+    pub threads: Vec<ThreadInfo>,
+}
+
+/// Runs the `--threads <PID>` detail mode, listing every thread of one process.
+///
+/// Samples twice, like the snapshot collectors, so per-thread CPU percentages are accurate.
+///
+/// # Errors
+///
+/// Returns error if output fails. A missing process is reported to the user
+/// (as JSON in `--json` mode) and exits with status 1.
+pub fn threads_mode(args: &Args, pid: u32) -> Result<(), Box<dyn Error>> {
+    let found = match &args.proc_root {
+        Some(proc_root) => captured_threads(proc_root, pid)?,
+        None => live_threads(pid),
+    };
+    let Some((name, cpu_percent, threads)) = found else {
+        let message = if cfg!(target_os = "linux") {
+            format!("Process {pid} not found")
+        } else {
+            "Thread listing is only supported on Linux".to_string()
+        };
+        if args.json {
+            let error_json = serde_json::json!({
+                "error": "ThreadsError",
+                "message": message,
+                "pid": pid,
+            });
+            let _ = writeln!(
+                io::stdout(),
+                "{}",
+                serde_json::to_string_pretty(&error_json)?
+            );
+        } else {
+            eprintln!("Error: {message}");
+        }
+        std::process::exit(1);
+    };
+
+    let mut report = ThreadsReport {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        pid,
+        name,
+        cpu_percent,
+        thread_count: threads.len(),
+        threads,
+    };
+    sort_threads(
+        &mut report.threads,
+        args.sort_by.as_deref().unwrap_or("cpu"),
+    );
+    if let Some(limit) = args.top_n {
+        report.threads.truncate(limit);
+    }
+
+    let result = if args.json {
+        let mut out = io::BufWriter::new(io::stdout().lock());
+        json::write_value(&mut out, &report, args.json_compact).and_then(|_| out.flush())
+    } else if args.csv {
+        output_threads_csv(&report)
+    } else {
+        output_threads_human(&report)
+    };
+
+    if let Err(e) = result {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            return Ok(());
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Name, CPU usage and threads of one process.
+type ProcessThreads = (String, f32, Vec<ThreadInfo>);
+
+/// Samples the running system twice and lists the threads of `pid`.
+fn live_threads(pid: u32) -> Option<ProcessThreads> {
+    let mut sys = System::new_all();
+    std::thread::sleep(std::time::Duration::from_millis(CPU_SAMPLE_INTERVAL_MS));
+    sys.refresh_all();
+
+    let process = sys.process(Pid::from_u32(pid))?;
+    let threads = collect_threads(&sys, process)?;
+    Some((
+        process.name().to_string_lossy().into_owned(),
+        process.cpu_usage(),
+        threads,
+    ))
+}
+
+/// Lists the threads of `pid` from a captured `/proc` tree, with CPU
+/// percentages averaged since process start like `--proc-root` snapshots.
+fn captured_threads(proc_root: &Path, pid: u32) -> Result<Option<ProcessThreads>, Box<dyn Error>> {
+    let options = CollectOptions {
+        threads: true,
+        ..Default::default()
+    };
+    let sample = ProcfsCollector::captured(proc_root).sample(&options)?;
+    Ok(sample
+        .processes
+        .into_iter()
+        .find(|process| process.pid == pid)
+        .and_then(|process| Some((process.name, process.cpu_usage, process.threads?))))
+}
+
+fn output_threads_csv(report: &ThreadsReport) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    writeln!(
+        stdout,
+        "timestamp,pid,name,tid,thread_name,state,cpu_percent"
+    )?;
+    for thread in &report.threads {
+        writeln!(
+            stdout,
+            "{},{},{},{},{},{},{}",
+            escape_csv_field(&report.timestamp),
+            report.pid,
+            escape_csv_field(&report.name),
+            thread.tid,
+            escape_csv_field(&thread.name),
+            escape_csv_field(&thread.state),
+            thread.cpu_percent
+        )?;
+    }
+    stdout.flush()
+}
+
+fn output_threads_human(report: &ThreadsReport) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    writeln!(
+        stdout,
+        "{} {}",
+        "stop".bold().cyan(),
+        format!("v{}", env!("CARGO_PKG_VERSION")).dimmed()
+    )?;
+    writeln!(stdout)?;
+    writeln!(
+        stdout,
+        "{} {} ({}) {} {:.1}%",
+        "Process:".bold(),
+        report.pid.to_string().cyan(),
+        report.name,
+        "CPU:".bold(),
+        report.cpu_percent
+    )?;
+    writeln!(
+        stdout,
+        "{} {} {}",
+        "Threads:".bold(),
+        report.thread_count.to_string().green(),
+        format!("(showing {})", report.threads.len()).dimmed()
+    )?;
+    writeln!(stdout)?;
+
+    writeln!(
+        stdout,
+        "{:<8} {:<20} {:<12} {:>8}",
+        "TID".bold(),
+        "Name".bold(),
+        "State".bold(),
+        "CPU%".bold()
+    )?;
+    writeln!(stdout, "{}", "─".repeat(51).dimmed())?;
+
+    for thread in &report.threads {
+        // Same thresholds as the per-process table
+        let cpu_str = format!("{:>7.1}%", thread.cpu_percent);
+        let cpu_display = match PROCESS_CPU.level(thread.cpu_percent) {
+            Level::High => cpu_str.red().to_string(),
+            Level::Warn => cpu_str.yellow().to_string(),
+            Level::Normal => cpu_str,
+        };
+        writeln!(
+            stdout,
+            "{:<8} {:<20} {:<12} {}",
+            thread.tid.to_string().cyan(),
+            &thread.name[..thread.name.len().min(20)],
+            thread.state,
+            cpu_display
+        )?;
+    }
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(tid: u32, name: &str, cpu_percent: f32) -> ThreadInfo {
+        ThreadInfo {
+            tid,
+            name: name.to_string(),
+            state: "Sleeping".to_string(),
+            cpu_percent,
+        }
+    }
+
+    #[test]
+    fn test_sort_threads() {
+        let mut threads = vec![
+            thread(12, "tokio-rt-worker", 2.0),
+            thread(10, "main", 0.5),
+            thread(11, "GC Thread#0", 95.0),
+        ];
+
+        sort_threads(&mut threads, "cpu");
+        assert_eq!(threads[0].name, "GC Thread#0");
+
+        sort_threads(&mut threads, "tid");
+        assert_eq!(
+            threads.iter().map(|t| t.tid).collect::<Vec<_>>(),
+            [10, 11, 12]
+        );
+
+        sort_threads(&mut threads, "name");
+        assert_eq!(threads[0].name, "GC Thread#0");
+        assert_eq!(threads[2].name, "tokio-rt-worker");
+    }
+}
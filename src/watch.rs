@@ -1,15 +1,27 @@
+#[cfg(feature = "columnar")]
+use crate::OutputFormat;
+#[cfg(feature = "columnar")]
+use crate::columnar::ArrowStream;
+use crate::group::{self, GroupedSnapshot};
+#[cfg(feature = "record")]
+use crate::record::{Recorder, Retention};
 use crate::{
-    Args, DEFAULT_TOP_N, collect_snapshot, filter::FilterExpr, output_csv_header, output_csv_rows,
-    output_human_readable, sort_processes,
+    Args, CollectOptions, DEFAULT_TOP_N, ProcessCounters, collector, filter::FilterExpr, json,
+    output_csv_header, output_csv_rows, output_format, output_human_readable, sort_processes,
 };
 use crossterm::{ExecutableCommand, cursor, terminal};
+use std::collections::HashMap;
 use std::error::Error;
-use std::io::{Write, stdout};
-use std::time::Duration;
+use std::io::{BufWriter, Write, stdout};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 /// Runs continuous monitoring mode, refreshing data at the specified interval.
 ///
 /// Outputs in NDJSON format for JSON mode, or clears screen for human-readable.
+/// From the second interval on, each process carries `deltas` of its cumulative
+/// counters (CPU time, context switches, page faults) since the previous one.
 /// Gracefully exits on broken pipe (e.g., when piping to `head`).
 ///
 /// # Errors
@@ -39,29 +51,100 @@ pub fn watch_mode(args: &Args) -> Result<(), Box<dyn Error>> {
         None
     };
 
+    let options = CollectOptions::from_args(args);
+    // One collector for all iterations, so CPU usage covers the whole interval
+    let mut collector = collector::from_args(args);
     let mut first_iteration = true;
+    let mut previous_counters: HashMap<u32, ProcessCounters> = HashMap::new();
+    // Arrow output is one stream: the schema once, then a batch per interval
+    #[cfg(feature = "columnar")]
+    let mut arrow_stream = match args.format {
+        Some(OutputFormat::Arrow) => Some(ArrowStream::new(stdout(), args.columns.as_deref())?),
+        _ => None,
+    };
+    // Recorded intervals are committed in batches; on Ctrl-C the loop stops
+    // at the next interval so the pending batch is saved
+    #[cfg(feature = "record")]
+    let mut recorder = match &args.record {
+        Some(target) => {
+            let retention = Retention {
+                max_age: args.record_max_age,
+                max_snapshots: args.record_max_snapshots,
+            };
+            Some(Recorder::open(target, retention, args.record_batch)?)
+        }
+        None => None,
+    };
+    let interrupted = Arc::new(AtomicBool::new(false));
+    #[cfg(feature = "record")]
+    if recorder.is_some() {
+        let interrupted = Arc::clone(&interrupted);
+        ctrlc::set_handler(move || interrupted.store(true, Ordering::Relaxed))?;
+    }
 
     loop {
-        let mut snapshot = collect_snapshot()?;
+        let mut snapshot = collector.collect(&options)?;
+
+        // Compute per-interval deltas against the previous (unfiltered) snapshot
+        let mut current_counters = HashMap::with_capacity(snapshot.processes.len());
+        for process in &mut snapshot.processes {
+            if let Some(counters) = ProcessCounters::of(process) {
+                process.deltas = previous_counters
+                    .get(&process.pid)
+                    .map(|previous| counters.since(previous));
+                current_counters.insert(process.pid, counters);
+            }
+        }
+        previous_counters = current_counters;
 
         // Apply filter
         if let Some(ref f) = filter {
             snapshot.processes.retain(|p| f.matches(p));
         }
 
-        // Apply sorting
         let sort_by = args.sort_by.as_deref().unwrap_or("cpu");
+        let limit = args.top_n.unwrap_or(DEFAULT_TOP_N);
+
+        if let Some(group_by) = args.group_by {
+            let mut grouped =
+                GroupedSnapshot::from_snapshot(snapshot, group_by, collector::cgroup_root(args));
+            group::sort_groups(&mut grouped.groups, sort_by);
+            grouped.groups.truncate(limit);
+            if let Err(e) = output_grouped_interval(args, &grouped, sort_by, first_iteration) {
+                if e.kind() == std::io::ErrorKind::BrokenPipe {
+                    return Ok(()); // Graceful exit when output is closed
+                }
+                return Err(e.into());
+            }
+            first_iteration = false;
+            std::thread::sleep(Duration::from_secs_f64(args.interval));
+            continue;
+        }
+
+        // Apply sorting
         sort_processes(&mut snapshot.processes, sort_by);
 
         // Apply top-n limit
-        let limit = args.top_n.unwrap_or(DEFAULT_TOP_N);
         snapshot.processes.truncate(limit);
 
+        #[cfg(feature = "record")]
+        if let Some(recorder) = &mut recorder {
+            recorder
+                .record(&snapshot)
+                .map_err(|e| format!("cannot record snapshot: {e}"))?;
+        }
+
         // Output based on mode
         if args.json {
-            // NDJSON: one JSON object per line
-            println!("{}", serde_json::to_string(&snapshot)?);
-            if let Err(e) = stdout().flush() {
+            // NDJSON: one JSON object per line, or one per process with --json-lines
+            let mut out = BufWriter::new(stdout().lock());
+            let columns = args.columns.as_deref();
+            let result = if args.json_lines {
+                json::write_lines(&mut out, &snapshot, columns)
+            } else {
+                json::write_snapshot(&mut out, &snapshot, columns, true)
+            };
+            if let Err(e) = result.and_then(|_| out.flush()) {
                 if e.kind() == std::io::ErrorKind::BrokenPipe {
                     return Ok(()); // Graceful exit when output is closed
                 }
@@ -70,7 +153,7 @@ pub fn watch_mode(args: &Args) -> Result<(), Box<dyn Error>> {
         } else if args.csv {
             // CSV: header once, then rows
             if first_iteration {
-                if let Err(e) = output_csv_header() {
+                if let Err(e) = output_csv_header(args.columns.as_deref()) {
                     if e.kind() == std::io::ErrorKind::BrokenPipe {
                         return Ok(()); // Graceful exit when output is closed
                     }
@@ -78,7 +161,22 @@ pub fn watch_mode(args: &Args) -> Result<(), Box<dyn Error>> {
                 }
                 first_iteration = false;
             }
-            if let Err(e) = output_csv_rows(&snapshot) {
+            if let Err(e) = output_csv_rows(&snapshot, args.columns.as_deref()) {
+                if e.kind() == std::io::ErrorKind::BrokenPipe {
+                    return Ok(()); // Graceful exit when output is closed
+                }
+                return Err(e.into());
+            }
+        } else if let Some(format) = &args.format {
+            // Line-oriented formats: one batch of lines per interval
+            #[cfg(feature = "columnar")]
+            let result = match &mut arrow_stream {
+                Some(stream) => stream.write(&snapshot),
+                None => output_format(args, format, &snapshot),
+            };
+            #[cfg(not(feature = "columnar"))]
+            let result = output_format(args, format, &snapshot);
+            if let Err(e) = result {
                 if e.kind() == std::io::ErrorKind::BrokenPipe {
                     return Ok(()); // Graceful exit when output is closed
                 }
@@ -96,6 +194,7 @@ pub fn watch_mode(args: &Args) -> Result<(), Box<dyn Error>> {
                 sort_by,
                 limit,
                 args.verbose,
+                args.columns.as_deref(),
             ) {
                 if e.kind() == std::io::ErrorKind::BrokenPipe {
                     return Ok(()); // Graceful exit when output is closed
@@ -105,6 +204,46 @@ pub fn watch_mode(args: &Args) -> Result<(), Box<dyn Error>> {
         }
 
         // Sleep before next iteration
-        std::thread::sleep(Duration::from_secs_f64(args.interval));
+        sleep_interval(args.interval, &interrupted);
+        if interrupted.load(Ordering::Relaxed) {
+            return Ok(()); // Dropping the recorder saves the pending batch
+        }
+    }
+}
+
+/// Sleeps for the watch interval, waking early once `interrupted` is set.
+fn sleep_interval(seconds: f64, interrupted: &AtomicBool) {
+    let deadline = Instant::now() + Duration::from_secs_f64(seconds);
+    while !interrupted.load(Ordering::Relaxed) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(Duration::from_millis(100)));
+    }
+}
+
+/// Writes one interval of grouped output: NDJSON, CSV rows (header on the
+/// first interval), or a redrawn table.
+fn output_grouped_interval(
+    args: &Args,
+    grouped: &GroupedSnapshot,
+    sort_by: &str,
+    first_iteration: bool,
+) -> std::io::Result<()> {
+    if args.json {
+        let mut out = stdout().lock();
+        json::write_value(&mut out, grouped, true)?;
+        out.flush()
+    } else if args.csv {
+        if first_iteration {
+            group::output_groups_csv_header()?;
+        }
+        group::output_groups_csv_rows(grouped)
+    } else {
+        stdout()
+            .execute(terminal::Clear(terminal::ClearType::All))?
+            .execute(cursor::MoveTo(0, 0))?;
+        group::output_groups_human(grouped, args.filter.as_ref(), sort_by)
     }
 }
@@ -310,8 +310,8 @@ fn test_broken_pipe_handling_json() {
     // Test that piping to head doesn't cause panic (broken pipe handling)
     use std::process::{Command, Stdio};
 
-    let mut stop_child = Command::new("cargo")
-        .args(["run", "--", "--json"])
+    let mut stop_child = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .arg("--json")
         .stdout(Stdio::piped())
         .spawn()
         .expect("Failed to start stop");
@@ -338,13 +338,45 @@ fn test_broken_pipe_handling_json() {
     assert!(!stdout.is_empty(), "Expected at least one line of output");
 }
 
+#[test]
+fn test_broken_pipe_handling_grouped_watch_json() {
+    // Grouped watch output ends cleanly once the reader goes away
+    use std::process::{Command, Stdio};
+
+    let mut stop_child = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .args([
+            "--watch",
+            "--group-by",
+            "netns",
+            "--json",
+            "--interval",
+            "0.2",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start stop");
+
+    let stop_stdout = stop_child.stdout.take().unwrap();
+    let head_output = Command::new("head")
+        .arg("-1")
+        .stdin(stop_stdout)
+        .output()
+        .expect("Failed to run head");
+
+    let status = stop_child.wait().unwrap();
+    assert!(status.success(), "stop exited with {status}");
+    let stdout = String::from_utf8_lossy(&head_output.stdout);
+    assert!(stdout.trim_start().starts_with('{'));
+}
+
 #[test]
 fn test_broken_pipe_handling_csv() {
     // Test CSV output with broken pipe
     use std::process::{Command, Stdio};
 
-    let mut stop_child = Command::new("cargo")
-        .args(["run", "--", "--csv"])
+    let mut stop_child = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .arg("--csv")
         .stdout(Stdio::piped())
         .spawn()
         .expect("Failed to start stop");
@@ -371,3 +403,552 @@ fn test_broken_pipe_handling_csv() {
     let lines: Vec<&str> = stdout.lines().collect();
     assert!(lines.len() >= 2, "Expected header + data row");
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_files_mode_json() {
+    // The test process itself always has stdin/stdout/stderr open
+    let pid = std::process::id();
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--files")
+        .arg(pid.to_string())
+        .arg("--json")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON output");
+
+    assert_eq!(json["pid"].as_u64(), Some(pid as u64));
+    let files = json["files"].as_array().unwrap();
+    assert!(!files.is_empty(), "Expected at least one open file");
+    for file in files {
+        assert!(file["fd"].is_number());
+        assert!(file["path"].is_string());
+        let kind = file["type"].as_str().unwrap();
+        assert!(
+            ["regular", "socket", "pipe", "anon_inode", "device", "other"].contains(&kind),
+            "Unexpected descriptor type {kind}"
+        );
+    }
+
+    let counts = &json["counts"];
+    let total: u64 = counts
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|v| v.as_u64().unwrap())
+        .sum();
+    assert_eq!(total, files.len() as u64);
+}
+
+#[test]
+fn test_files_mode_missing_pid() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("--files")
+        .arg("4294967295")
+        .arg("--json")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("FilesError"));
+}
+
+#[test]
+fn test_verbose_json_fd_types() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--verbose")
+        .arg("--top-n")
+        .arg("5")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON output");
+
+    // fd_types is only present where descriptors could be read
+    for process in json["processes"].as_array().unwrap() {
+        if let Some(fd_types) = process.get("fd_types") {
+            assert!(fd_types["socket"].is_number());
+            assert!(fd_types["regular"].is_number());
+        }
+    }
+}
+
+#[test]
+fn test_memory_breakdown_fields() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd.arg("--json").arg("--top-n").arg("5").assert().success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON output");
+
+    for process in json["processes"].as_array().unwrap() {
+        assert!(process["virtual_bytes"].is_number());
+        assert!(process["shared_bytes"].is_null() || process["shared_bytes"].is_number());
+        // PSS/USS are opt-in
+        assert!(process.get("pss_bytes").is_none());
+        assert!(process.get("uss_bytes").is_none());
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_sort_by_pss() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--sort-by")
+        .arg("pss")
+        .arg("--json")
+        .arg("--top-n")
+        .arg("10")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON");
+
+    let pss: Vec<u64> = json["processes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|p| p.get("pss_bytes").and_then(Value::as_u64))
+        .collect();
+    assert!(!pss.is_empty(), "Expected PSS data when sorting by pss");
+    assert!(
+        pss.windows(2).all(|w| w[0] >= w[1]),
+        "PSS should be sorted descending"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cpu_time_and_fault_counters() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--sort-by")
+        .arg("cputime")
+        .arg("--filter")
+        .arg("minflt >= 0")
+        .arg("--top-n")
+        .arg("5")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON");
+
+    let processes = json["processes"].as_array().unwrap();
+    assert!(!processes.is_empty());
+    for process in processes {
+        assert!(process["cpu_user_seconds"].is_number());
+        assert!(process["cpu_system_seconds"].is_number());
+        assert!(process["voluntary_ctx_switches"].is_number());
+        assert!(process["involuntary_ctx_switches"].is_number());
+        assert!(process["minor_faults"].is_number());
+        assert!(process["major_faults"].is_number());
+        // Deltas are only computed between watch intervals
+        assert!(process.get("deltas").is_none());
+    }
+
+    let total = |p: &Value| {
+        p["cpu_user_seconds"].as_f64().unwrap() + p["cpu_system_seconds"].as_f64().unwrap()
+    };
+    assert!(
+        processes.windows(2).all(|w| total(&w[0]) >= total(&w[1])),
+        "CPU time should be sorted descending"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_watch_mode_deltas() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .args(["--watch", "--json", "--interval", "0.2", "--top-n", "3"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to start stop");
+
+    let reader = BufReader::new(child.stdout.take().unwrap());
+    let lines: Vec<String> = reader.lines().take(2).map(|l| l.unwrap()).collect();
+    let _ = child.kill();
+    let _ = child.wait();
+
+    assert_eq!(lines.len(), 2, "Expected two watch intervals");
+
+    let first: Value = serde_json::from_str(&lines[0]).unwrap();
+    for process in first["processes"].as_array().unwrap() {
+        assert!(
+            process.get("deltas").is_none(),
+            "No deltas on first interval"
+        );
+    }
+
+    let second: Value = serde_json::from_str(&lines[1]).unwrap();
+    let with_deltas = second["processes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|p| p.get("deltas"))
+        .count();
+    assert!(with_deltas > 0, "Expected deltas on second interval");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_sched_details_opt_in() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd.arg("--json").arg("--top-n").arg("3").assert().success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON");
+    for process in json["processes"].as_array().unwrap() {
+        assert!(process.get("sched").is_none(), "sched should be opt-in");
+    }
+
+    // Filtering on a scheduling field collects the section implicitly
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--filter")
+        .arg("oom_score >= 0 and nice > -21")
+        .arg("--top-n")
+        .arg("3")
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON");
+
+    let processes = json["processes"].as_array().unwrap();
+    assert!(!processes.is_empty());
+    for process in processes {
+        let sched = &process["sched"];
+        assert!(sched["nice"].is_i64());
+        assert!(sched["priority"].is_i64());
+        assert!(sched["policy"].is_string());
+        assert!(sched["oom_score"].as_i64().unwrap() >= 0);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_threads_mode_json() {
+    // Name a thread in this test process so it can be found in the listing
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+    let worker = std::thread::Builder::new()
+        .name("stop-test-wrk".to_string())
+        .spawn(move || {
+            ready_tx.send(()).unwrap();
+            let _ = done_rx.recv();
+        })
+        .unwrap();
+    ready_rx.recv().unwrap();
+
+    let pid = std::process::id();
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--threads")
+        .arg(pid.to_string())
+        .arg("--json")
+        .assert()
+        .success();
+
+    done_tx.send(()).unwrap();
+    worker.join().unwrap();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).expect("Valid JSON output");
+
+    assert_eq!(json["pid"].as_u64(), Some(pid as u64));
+    let threads = json["threads"].as_array().unwrap();
+    assert_eq!(json["thread_count"].as_u64(), Some(threads.len() as u64));
+    assert!(
+        threads
+            .iter()
+            .any(|t| t["tid"].as_u64() == Some(pid as u64)),
+        "Main thread should be listed"
+    );
+    assert!(
+        threads.iter().any(|t| t["name"] == "stop-test-wrk"),
+        "Named worker thread should be listed"
+    );
+    for thread in threads {
+        assert!(thread["state"].is_string());
+        assert!(thread["cpu_percent"].is_number());
+    }
+}
+
+#[test]
+fn test_threads_mode_missing_pid() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("--threads")
+        .arg("4294967295")
+        .arg("--json")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("ThreadsError"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cgroup_fields_in_json() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd.arg("--json").arg("--top-n").arg("5").assert().success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    for process in json["processes"].as_array().unwrap() {
+        assert!(process.get("cgroup").is_some(), "Missing cgroup field");
+        assert!(
+            process.get("container").is_some(),
+            "Missing container field"
+        );
+    }
+}
+
+#[test]
+fn test_group_by_container_json() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--group-by")
+        .arg("container")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["group_by"], "container");
+    let groups = json["groups"].as_array().unwrap();
+    assert!(!groups.is_empty(), "Expected at least one group");
+    for group in groups {
+        assert!(group["process_count"].as_u64().unwrap() > 0);
+        assert!(group["cpu_percent"].is_number());
+        assert!(group["memory_bytes"].is_number());
+        assert!(group["thread_count"].is_number());
+    }
+}
+
+#[test]
+fn test_group_by_invalid_key() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("--group-by")
+        .arg("planet")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cgroup_limits_mode() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--cgroup-limits")
+        .arg("--top-n")
+        .arg("3")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    let limits = &json["system"]["limits"];
+    assert!(limits["cgroup"].is_string(), "Expected own cgroup limits");
+    assert!(limits.get("memory_limit_bytes").is_some());
+    assert!(limits.get("cpu_limit_cores").is_some());
+
+    // Without the flag, limits are not reported
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd.arg("--json").arg("--top-n").arg("1").assert().success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    assert!(json["system"].get("limits").is_none());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_group_by_unit_csv() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--csv")
+        .arg("--group-by")
+        .arg("unit")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let mut lines = stdout.lines();
+    let header = lines.next().unwrap();
+    assert!(header.contains("group_by,group,process_count"));
+    let columns = header.split(',').count();
+    for line in lines {
+        assert_eq!(line.split(',').count(), columns, "Row: {line}");
+        assert!(line.contains(",unit,"));
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_namespaces_opt_in() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--namespaces")
+        .arg("--filter")
+        .arg(format!("pid == {}", std::process::id()))
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    let processes = json["processes"].as_array().unwrap();
+    assert_eq!(processes.len(), 1, "Expected the test process itself");
+    let namespaces = &processes[0]["namespaces"];
+    assert!(namespaces["net"].as_u64().is_some());
+    assert!(namespaces["pid"].as_u64().is_some());
+
+    // Filtering on a namespace field collects namespaces implicitly
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--filter")
+        .arg(format!("netns == {}", namespaces["net"]))
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    assert!(!json["processes"].as_array().unwrap().is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_group_by_netns_json() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--json")
+        .arg("--group-by")
+        .arg("netns")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["group_by"], "netns");
+    let groups = json["groups"].as_array().unwrap();
+    assert!(
+        groups.iter().any(|g| g["group"]
+            .as_str()
+            .is_some_and(|k| k.parse::<u64>().is_ok())),
+        "Expected at least one readable network namespace"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_system_pressure() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd.arg("--json").arg("--top-n").arg("1").assert().success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    let pressure = &json["system"]["pressure"];
+    if std::path::Path::new("/proc/pressure/cpu").exists() {
+        let some = &pressure["cpu"]["some"];
+        for window in ["avg10", "avg60", "avg300"] {
+            assert!(some[window].is_number(), "Missing cpu some {window}");
+        }
+        assert!(pressure["memory"]["full"]["avg10"].is_number());
+    } else {
+        assert!(pressure.is_null(), "No PSI without kernel support");
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_system_memory_breakdown() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd.arg("--json").arg("--top-n").arg("1").assert().success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    let system = &json["system"];
+    let total = system["memory_total"].as_u64().unwrap();
+    let available = system["memory_available"].as_u64().unwrap();
+    assert!(available <= total, "Available memory exceeds total");
+    for field in ["cached", "buffers", "dirty", "shmem"] {
+        assert!(system[field].is_u64(), "Missing {field}");
+    }
+    assert!(system["hugepages"]["page_size_bytes"].as_u64().unwrap() > 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_procfs_backend() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--backend")
+        .arg("procfs")
+        .arg("--json")
+        .arg("--sort-by")
+        .arg("pid")
+        .arg("--top-n")
+        .arg("1000")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let json: Value = serde_json::from_str(&stdout).unwrap();
+    assert!(json["system"]["memory_total"].as_u64().unwrap() > 0);
+    let processes = json["processes"].as_array().unwrap();
+    let init = processes.iter().find(|p| p["pid"] == 1).unwrap();
+    assert!(init["name"].as_str().is_some_and(|name| !name.is_empty()));
+    assert!(init["thread_count"].as_u64().unwrap() >= 1);
+    assert!(init["cpu_user_seconds"].is_number());
+}
+
+#[test]
+fn test_invalid_backend() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("--backend")
+        .arg("kvm")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("possible values: sysinfo, procfs"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_capture_to_stdout() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .args(["capture", "--out", "-", "--filter", "pid == 1"])
+        .assert()
+        .success();
+
+    let mut archive = tar::Archive::new(&output.get_output().stdout[..]);
+    let paths: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+        .collect();
+    for path in [
+        "capture.json",
+        "proc/meminfo",
+        "proc/loadavg",
+        "proc/1/stat",
+        "proc/1/cmdline",
+    ] {
+        assert!(paths.iter().any(|p| p == path), "Missing {path}");
+    }
+    assert!(
+        !paths.iter().any(|p| p.ends_with("/environ")),
+        "Environment captured by default"
+    );
+}
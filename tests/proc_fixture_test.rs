@@ -0,0 +1,669 @@
+#![allow(deprecated)]
+
+//! Deterministic tests against the captured `/proc` tree in `tests/fixtures/proc/host`.
+//!
+//! The fixture holds six processes: systemd (1), kthreadd (2), an nginx master (412)
+//! and worker (413) in nginx.service, postgres (1021) in a Docker container and a
+//! multi-threaded python3 (2400) in a login session. With `--proc-root`, CPU% is
+//! averaged since process start, so every value below is exact.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+
+const PROC_ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc/host");
+
+fn stop(args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("--proc-root").arg(PROC_ROOT).args(args);
+    cmd
+}
+
+fn json(args: &[&str]) -> Value {
+    let output = stop(&[&["--json"], args].concat()).assert().success();
+    serde_json::from_slice(&output.get_output().stdout).unwrap()
+}
+
+fn pids(json: &Value) -> Vec<u64> {
+    json["processes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["pid"].as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_fixture_system_metrics() {
+    let json = json(&[]);
+    let system = &json["system"];
+    assert_eq!(system["cpu_usage"], 30.0);
+    assert_eq!(system["memory_total"], 8u64 << 30);
+    assert_eq!(system["memory_used"], 2u64 << 30);
+    assert_eq!(system["memory_percent"], 25.0);
+    assert_eq!(system["memory_available"], 6u64 << 30);
+    assert_eq!(system["pressure"]["io"]["some"]["avg10"], 55.0);
+}
+
+#[test]
+fn test_fixture_process_fields() {
+    let json = json(&["--filter", "pid == 413"]);
+    let nginx = &json["processes"][0];
+    assert_eq!(nginx["name"], "nginx");
+    assert_eq!(nginx["user"], "33");
+    assert_eq!(nginx["command"], "nginx: worker process");
+    assert_eq!(nginx["cpu_percent"], 10.0);
+    assert_eq!(nginx["memory_bytes"], 16 << 20);
+    assert_eq!(nginx["thread_count"], 1);
+    assert_eq!(nginx["disk_write_bytes"], 1 << 20);
+    assert_eq!(nginx["open_files"], 8);
+    assert_eq!(nginx["cpu_user_seconds"], 400.0);
+    assert_eq!(nginx["voluntary_ctx_switches"], 800000);
+    assert_eq!(nginx["unit"], "nginx.service");
+}
+
+#[test]
+fn test_fixture_sort_by_cpu() {
+    assert_eq!(pids(&json(&[])), vec![2400, 1021, 413, 412, 1, 2]);
+}
+
+#[test]
+fn test_fixture_sort_by_mem_and_top_n() {
+    let json = json(&["--sort-by", "mem", "--top-n", "3"]);
+    assert_eq!(pids(&json), vec![2400, 1021, 413]);
+}
+
+#[test]
+fn test_fixture_filters() {
+    assert_eq!(
+        pids(&json(&["--filter", "cpu > 5 and mem < 5"])),
+        vec![1021, 413]
+    );
+    assert_eq!(
+        pids(&json(&["--filter", "container == docker"])),
+        vec![1021]
+    );
+    assert_eq!(
+        pids(&json(&["--filter", "unit == nginx", "--sort-by", "pid"])),
+        vec![412, 413]
+    );
+    assert_eq!(
+        pids(&json(&["--filter", "user == 999 or nice > 0"])),
+        vec![2400, 1021]
+    );
+    assert_eq!(
+        pids(&json(&["--filter", "netns != 4026531840"])),
+        vec![1021]
+    );
+    assert_eq!(pids(&json(&["--search", "stress"])), vec![2400]);
+}
+
+#[test]
+fn test_fixture_threads_and_fd_types() {
+    let json = json(&["--verbose", "--filter", "pid == 2400"]);
+    let python = &json["processes"][0];
+    assert_eq!(python["fd_types"]["device"], 3);
+    let threads = python["threads"].as_array().unwrap();
+    let tids: Vec<u64> = threads.iter().map(|t| t["tid"].as_u64().unwrap()).collect();
+    assert_eq!(tids, vec![2400, 2401, 2402, 2403]);
+    assert_eq!(threads[1]["name"], "worker-1");
+    assert_eq!(threads[1]["state"], "Runnable");
+    assert_eq!(threads[1]["cpu_percent"], 48.0);
+}
+
+#[test]
+fn test_fixture_sched_and_namespaces() {
+    let json = json(&["--sched", "--namespaces", "--filter", "pid == 1021"]);
+    let postgres = &json["processes"][0];
+    assert_eq!(postgres["sched"]["oom_score"], 667);
+    assert_eq!(postgres["sched"]["cpus_allowed"], "0-1");
+    assert_eq!(postgres["namespaces"]["net"], 4026532300u64);
+    assert_eq!(postgres["container"]["runtime"], "docker");
+}
+
+#[test]
+fn test_fixture_group_by_unit() {
+    let json = json(&["--group-by", "unit"]);
+    let nginx = json["groups"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|g| g["group"] == "nginx.service")
+        .unwrap();
+    assert_eq!(nginx["process_count"], 2);
+    assert_eq!(nginx["cpu_percent"], 10.5);
+    assert_eq!(nginx["memory_bytes"], 20 << 20);
+}
+
+#[test]
+fn test_fixture_csv() {
+    let output = stop(&["--csv", "--filter", "pid == 412"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let mut lines = stdout.lines();
+    // The original columns keep their names and order; later fields follow
+    assert!(lines.next().unwrap().starts_with(
+        "timestamp,cpu_usage,memory_total,memory_used,memory_percent,pid,name,cpu_percent,\
+         memory_bytes,memory_percent_process,user,command,thread_count,disk_read_bytes,\
+         disk_write_bytes,open_files,memory_available,cached,buffers,dirty,shmem,virtual_bytes,"
+    ));
+    // Skip the timestamp column
+    let (_, row) = lines.next().unwrap().split_once(',').unwrap();
+    assert!(row.starts_with(
+        "30,8589934592,2147483648,25,412,nginx,0.5,4194304,0.048828125,0,\
+         nginx: master process /usr/sbin/nginx -g daemon on; master_process on;,1,8192,4096,5,\
+         6442450944,1610612736,134217728,2097152,67108864,56623104,"
+    ));
+}
+
+#[test]
+fn test_fixture_human_readable() {
+    stop(&["--top-n", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("python3"))
+        .stdout(predicate::str::contains("postgres"))
+        .stdout(predicate::str::contains("nginx").not())
+        .stdout(predicate::str::contains("2048 / 8192 MB"));
+}
+
+#[test]
+fn test_missing_proc_root() {
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("--proc-root")
+        .arg("/nonexistent/proc")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("/nonexistent/proc"));
+}
+
+/// Captures the fixture into a bundle, extracts it and returns the extraction directory.
+fn capture_and_extract(name: &str, args: &[&str]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("stop-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let bundle = dir.join("bundle.tar");
+    stop(&[&["capture", "--out", bundle.to_str().unwrap()], args].concat())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Captured"));
+    tar::Archive::new(std::fs::File::open(&bundle).unwrap())
+        .unpack(&dir)
+        .unwrap();
+    dir
+}
+
+#[test]
+fn test_capture_round_trip() {
+    let dir = capture_and_extract("capture", &["--filter", "unit == nginx or pid == 1021"]);
+    let manifest: Value =
+        serde_json::from_slice(&std::fs::read(dir.join("capture.json")).unwrap()).unwrap();
+    assert_eq!(manifest["pids"], serde_json::json!([412, 413, 1021]));
+    assert_eq!(manifest["proc_root"], "proc");
+
+    // The extracted tree reads back exactly like the original
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    let output = cmd
+        .arg("--proc-root")
+        .arg(dir.join("proc"))
+        .args(["--json", "--verbose", "--namespaces"])
+        .assert()
+        .success();
+    let captured: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    let original = json(&[
+        "--verbose",
+        "--namespaces",
+        "--filter",
+        "unit == nginx or pid == 1021",
+    ]);
+    assert_eq!(pids(&captured), vec![1021, 413, 412]);
+    assert_eq!(captured["system"], original["system"]);
+    assert_eq!(captured["processes"], original["processes"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_capture_redaction() {
+    let dir = capture_and_extract(
+        "redact",
+        &[
+            "--search",
+            "stress",
+            "--redact-cmdline",
+            "secrets",
+            "--environ",
+            "keys",
+        ],
+    );
+    let cmdline = std::fs::read(dir.join("proc/2400/cmdline")).unwrap();
+    assert_eq!(
+        cmdline,
+        b"python3\0stress.py\0--workers\x003\0--token=***\0".to_vec()
+    );
+    let environ = std::fs::read(dir.join("proc/2400/environ")).unwrap();
+    assert_eq!(environ, b"HOME=***\0LANG=***\0API_TOKEN=***\0".to_vec());
+    assert!(!dir.join("proc/1").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_jobs_do_not_change_results() {
+    let serial = json(&["--jobs", "1", "--verbose", "--sched", "--namespaces"]);
+    let parallel = json(&["--jobs", "8", "--verbose", "--sched", "--namespaces"]);
+    assert_eq!(serial["processes"], parallel["processes"]);
+
+    stop(&["--jobs", "0"]).assert().failure();
+}
+
+#[test]
+fn test_fixture_table_collects_filtered_and_sorted_fields() {
+    // The default table shows no counters or cgroups, but filters, sort keys
+    // and selected columns still get them collected
+    stop(&["--filter", "unit == nginx and minflt < 1000"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("412"))
+        .stdout(predicate::str::contains("413").not());
+
+    let output = stop(&["--sort-by", "majflt", "--columns", "pid,majflt,unit"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let first = stdout.lines().find(|l| l.contains("docker-")).unwrap();
+    assert!(first.contains("1500"));
+}
+
+#[test]
+fn test_fixture_columns() {
+    let json = json(&["--columns", "pid,name,cpu,rss", "--top-n", "1"]);
+    assert_eq!(
+        json["processes"][0],
+        serde_json::json!({"pid": 2400, "name": "python3", "cpu_percent": 150.0, "memory_bytes": 1u64 << 30})
+    );
+
+    let output = stop(&[
+        "--csv",
+        "--columns",
+        "pid,nice,netns",
+        "--filter",
+        "pid == 1021",
+    ])
+    .assert()
+    .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next().unwrap(), "timestamp,pid,nice,net_ns");
+    // nice and netns collect their details without --sched or --namespaces
+    assert!(lines.next().unwrap().ends_with(",1021,0,4026532300"));
+
+    stop(&["--columns", "pid,threads,command", "--top-n", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Threads"))
+        .stdout(predicate::str::contains("python3 stress.py"))
+        .stdout(predicate::str::contains("CPU%").not());
+
+    stop(&["--columns", "pid,bogus"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown column 'bogus'"));
+}
+
+#[test]
+fn test_fixture_prometheus() {
+    stop(&["--format", "prometheus", "--filter", "unit == nginx"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "# HELP stop_system_cpu_usage_percent Global CPU usage in percent (0-100).\n\
+             # TYPE stop_system_cpu_usage_percent gauge\n\
+             stop_system_cpu_usage_percent 30\n",
+        ))
+        .stdout(predicate::str::contains(
+            "stop_process_resident_memory_bytes{pid=\"413\",name=\"nginx\",user=\"33\"} 16777216\n",
+        ))
+        .stdout(predicate::str::contains(
+            "stop_process_cpu_seconds_total{pid=\"413\",name=\"nginx\",user=\"33\",mode=\"user\"} 400\n",
+        ))
+        .stdout(predicate::str::contains("stop_system_pressure_percent{resource=\"io\",kind=\"some\",window=\"10s\"} 55\n"))
+        .stdout(predicate::str::contains("pid=\"1021\"").not());
+
+    stop(&["--format", "prometheus", "--json"])
+        .assert()
+        .failure();
+    stop(&["--format", "prometheus", "--watch"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be combined with --watch"));
+}
+
+#[test]
+fn test_fixture_influx() {
+    let output = stop(&["--format", "influx", "--filter", "pid == 413"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("stop_system cpu_usage=30,memory_total=8589934592i,"));
+    assert!(lines[1].starts_with(
+        "stop_process,pid=413,name=nginx,user=33 cpu_percent=10,memory_percent=0.1953125,\
+         memory_bytes=16777216i,"
+    ));
+    assert!(lines[1].contains(",command=\"nginx: worker process\","));
+    assert!(lines[1].contains(",unit=\"nginx.service\" "));
+
+    // Both points carry the same nanosecond timestamp
+    let nanos: Vec<&str> = lines
+        .iter()
+        .map(|l| l.rsplit(' ').next().unwrap())
+        .collect();
+    assert_eq!(nanos[0], nanos[1]);
+    assert_eq!(nanos[0].len(), 19);
+}
+
+#[test]
+fn test_fixture_logfmt() {
+    let output = stop(&[
+        "--format",
+        "logfmt",
+        "--columns",
+        "pid,name,cpu,command",
+        "--filter",
+        "pid == 413",
+    ])
+    .assert()
+    .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].starts_with("time="));
+    assert!(lines[0].contains(" type=system cpu_usage=30 memory_total=8589934592 "));
+    assert!(lines[1].ends_with(
+        " type=process pid=413 name=nginx cpu_percent=10 command=\"nginx: worker process\""
+    ));
+}
+
+#[test]
+fn test_fixture_markdown() {
+    let output = stop(&["--format", "markdown", "--columns", "pid,name,cpu,rss"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert!(stdout.contains("| CPU | 30.0% |\n"));
+    assert!(stdout.contains("| Pressure I/O | 55.0% (some, avg10) |\n"));
+    assert!(stdout.contains("| PID | Name | CPU% | RSS |\n| :-- | :-- | --: | --: |\n"));
+    assert!(stdout.contains("| 2400 | python3 | 150.0% | 1.0 G |\n"));
+}
+
+#[test]
+fn test_fixture_html() {
+    let output = stop(&["--format", "html", "--filter", "name == nginx"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert!(stdout.starts_with("<!DOCTYPE html>"));
+    // I/O pressure of 55% is above the warning threshold
+    assert!(
+        stdout
+            .contains("<tr><th>Pressure I/O</th><td class=\"warn\">55.0% (some, avg10)</td></tr>")
+    );
+    assert!(stdout.contains("<td data-value=\"413\">413</td><td>nginx</td>"));
+    assert!(!stdout.contains("python3"));
+
+    stop(&["--format", "html", "--watch"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot be combined with --watch"));
+}
+
+#[cfg(feature = "columnar")]
+#[test]
+fn test_fixture_arrow_to_parquet() {
+    let dir = std::env::temp_dir().join(format!("stop-arrow-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = stop(&["--format", "arrow", "--columns", "pid,name,cpu,rss"])
+        .assert()
+        .success();
+    let stream = output.get_output().stdout.clone();
+    // Arrow IPC stream: continuation marker, then the schema message
+    assert!(stream.starts_with(&[0xff, 0xff, 0xff, 0xff]));
+    let input = dir.join("session.arrows");
+    std::fs::write(&input, stream).unwrap();
+
+    let out = dir.join("session.parquet");
+    let mut cmd = Command::cargo_bin("stop").unwrap();
+    cmd.arg("parquet")
+        .arg(&input)
+        .arg("--out")
+        .arg(&out)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Wrote 6 rows"));
+    assert!(std::fs::read(&out).unwrap().starts_with(b"PAR1"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(not(feature = "columnar"))]
+#[test]
+fn test_fixture_arrow_needs_feature() {
+    stop(&["--format", "arrow"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--features columnar"));
+}
+
+#[cfg(not(feature = "record"))]
+#[test]
+fn test_fixture_record_needs_feature() {
+    stop(&["--watch", "--record", "sqlite:stop.db"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--features record"));
+}
+
+#[cfg(feature = "record")]
+#[test]
+fn test_fixture_record_sqlite() {
+    use std::process::{Command, Stdio};
+
+    let dir = std::env::temp_dir().join(format!("stop-record-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let db = dir.join("session.db");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .args([
+            "--proc-root",
+            PROC_ROOT,
+            "--watch",
+            "--interval",
+            "0.1",
+            "--json",
+        ])
+        .args(["--top-n", "2", "--record-batch", "100"])
+        .arg("--record")
+        .arg(format!("sqlite:{}", db.display()))
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(800));
+    // Ctrl-C saves the pending batch, although it never filled up
+    Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let connection = rusqlite::Connection::open(&db).unwrap();
+    let snapshots: i64 = connection
+        .query_row("SELECT count(*) FROM snapshots", [], |row| row.get(0))
+        .unwrap();
+    assert!(snapshots >= 2, "{snapshots} snapshots");
+    let pids: Vec<i64> = connection
+        .prepare("SELECT pid FROM process_samples WHERE snapshot_id = 1 ORDER BY cpu_percent DESC")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(pids, vec![2400, 1021]);
+    let version: i64 = connection
+        .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 1);
+    drop(connection);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fixture_line_formats_in_watch_mode() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    for (format, prefix) in [("influx", "stop_system "), ("logfmt", "time=")] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_stop"))
+            .args(["--proc-root", PROC_ROOT, "--watch", "--interval", "0.2"])
+            .args(["--format", format, "--top-n", "1"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let reader = BufReader::new(child.stdout.take().unwrap());
+        // Two intervals of one system and one process line each
+        let lines: Vec<String> = reader.lines().take(4).map(|l| l.unwrap()).collect();
+        let _ = child.kill();
+        let _ = child.wait();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with(prefix), "{format}: {}", lines[0]);
+        assert!(lines[2].starts_with(prefix), "{format}: {}", lines[2]);
+    }
+}
+
+#[test]
+fn test_fixture_template() {
+    stop(&[
+        "--format",
+        r"{pid}\t{name}\t{cpu:.1}\t{rss|bytes}\t{nice}",
+        "--header-template",
+        r"PID\tNAME\tCPU\tRSS\tNICE",
+        "--footer-template",
+        "{count} processes, CPU {system.cpu_usage:.0}%",
+        "--top-n",
+        "3",
+    ])
+    .assert()
+    .success()
+    .stdout(
+        "PID\tNAME\tCPU\tRSS\tNICE\n\
+         2400\tpython3\t150.0\t1.0G\t10\n\
+         1021\tpostgres\t20.0\t256.0M\t0\n\
+         413\tnginx\t10.0\t16.0M\t0\n\
+         3 processes, CPU 30%\n",
+    );
+
+    stop(&["--format", "{pid} {bogus}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown field 'bogus'"));
+    stop(&["--format", "{pid}", "--footer-template", "{name}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'name' is a process field"));
+    stop(&["--format", "yaml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown format 'yaml'"));
+}
+
+#[test]
+fn test_fixture_json_compact() {
+    let output = stop(&["--json-compact", "--top-n", "3"]).assert().success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    let compact: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(pids(&compact), vec![2400, 1021, 413]);
+    assert_eq!(compact["system"], json(&[])["system"]);
+}
+
+#[test]
+fn test_fixture_json_lines() {
+    use std::io::BufRead;
+
+    let output = stop(&[
+        "--json-lines",
+        "--sort-by",
+        "pid",
+        "--columns",
+        "pid,name,cpu",
+    ])
+    .assert()
+    .success();
+    let lines: Vec<Value> = output
+        .get_output()
+        .stdout
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+        .collect();
+    let pids: Vec<u64> = lines.iter().map(|l| l["pid"].as_u64().unwrap()).collect();
+    assert_eq!(pids, vec![1, 2, 412, 413, 1021, 2400]);
+    // Every line carries the same snapshot timestamp and no system metrics
+    assert!(
+        lines
+            .iter()
+            .all(|l| l["timestamp"] == lines[0]["timestamp"])
+    );
+    assert_eq!(lines[5]["cpu_percent"], 150.0);
+    assert!(lines[0].get("system").is_none());
+}
+
+#[test]
+fn test_fixture_json_lines_conflicts() {
+    stop(&["--json-lines", "--group-by", "unit"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    stop(&["--json-lines", "--json-compact"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_fixture_files_mode() {
+    let output = stop(&["--files", "1021", "--json"]).assert().success();
+    let report: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(report["name"], "postgres");
+    assert_eq!(report["counts"]["pipe"], 2);
+    assert_eq!(report["files"].as_array().unwrap().len(), 6);
+}
+
+#[test]
+fn test_fixture_threads_mode() {
+    let output = stop(&["--threads", "2400", "--json"]).assert().success();
+    let report: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(report["name"], "python3");
+    assert_eq!(report["cpu_percent"], 150.0);
+    assert_eq!(report["thread_count"], 4);
+    assert_eq!(report["threads"][0]["cpu_percent"], 48.0);
+}
+
+#[test]
+fn test_fixture_rejects_live_host_options() {
+    // Captures hold no cgroup filesystem, and are always read with procfs
+    stop(&["--cgroup-limits"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    stop(&["--backend", "sysinfo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--backend sysinfo"));
+    stop(&["--backend", "procfs", "--json"]).assert().success();
+}
+
+#[test]
+fn test_fixture_group_pressure_not_read_from_host() {
+    let json = json(&["--group-by", "unit"]);
+    for group in json["groups"].as_array().unwrap() {
+        assert!(group.get("pressure").is_none(), "{group}");
+    }
+}
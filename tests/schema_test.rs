@@ -0,0 +1,111 @@
+//! Tests that keep `--json` output and the published `schema/snapshot.schema.json` in sync.
+//!
+//! After an intended change to the snapshot layout, regenerate the schema with
+//! `cargo run -- --print-schema > schema/snapshot.schema.json`.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+const PROC_ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc/host");
+const PUBLISHED_SCHEMA: &str = include_str!("../schema/snapshot.schema.json");
+
+fn stop(args: &[&str]) -> Vec<u8> {
+    let output = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .arg("--proc-root")
+        .arg(PROC_ROOT)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+fn assert_valid(snapshot: &Value) {
+    let schema: Value = serde_json::from_str(PUBLISHED_SCHEMA).unwrap();
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    let errors: Vec<String> = validator
+        .iter_errors(snapshot)
+        .map(|e| format!("{}: {e}", e.instance_path))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "snapshot drifted from the published schema:\n{}",
+        errors.join("\n")
+    );
+}
+
+#[test]
+fn test_print_schema_matches_published() {
+    let printed: Value = serde_json::from_slice(&stop(&["--print-schema"])).unwrap();
+    let published: Value = serde_json::from_str(PUBLISHED_SCHEMA).unwrap();
+    assert_eq!(
+        printed, published,
+        "schema/snapshot.schema.json is out of date, regenerate it with --print-schema"
+    );
+}
+
+#[test]
+fn test_schema_version_is_pinned() {
+    let schema: Value = serde_json::from_str(PUBLISHED_SCHEMA).unwrap();
+    let snapshot: Value = serde_json::from_slice(&stop(&["--json"])).unwrap();
+    assert_eq!(
+        schema["properties"]["schema_version"]["const"],
+        snapshot["schema_version"]
+    );
+}
+
+#[test]
+fn test_snapshot_meta() {
+    let snapshot: Value = serde_json::from_slice(&stop(&["--json"])).unwrap();
+    let meta = &snapshot["meta"];
+    assert_eq!(meta["tool"], "stop");
+    assert_eq!(meta["tool_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(meta["hostname"], "fixture-host");
+    assert_eq!(meta["os"], "Linux");
+    assert_eq!(meta["kernel"], "6.8.0-45-generic");
+    assert!(meta["collection_ms"].as_f64().unwrap() > 0.0);
+}
+
+#[test]
+fn test_json_output_matches_schema() {
+    for args in [
+        &["--json"][..],
+        &["--json", "--verbose"],
+        &["--json", "--sched", "--namespaces", "--smaps"],
+        &["--json", "--verbose", "--sched", "--namespaces", "--smaps"],
+    ] {
+        let snapshot: Value = serde_json::from_slice(&stop(args)).unwrap();
+        assert_valid(&snapshot);
+    }
+}
+
+#[test]
+fn test_watch_output_matches_schema() {
+    // The second interval carries per-process deltas
+    let mut child = Command::new(env!("CARGO_BIN_EXE_stop"))
+        .args([
+            "--proc-root",
+            PROC_ROOT,
+            "--watch",
+            "--interval",
+            "0.2",
+            "--json",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let reader = BufReader::new(child.stdout.take().unwrap());
+    let lines: Vec<String> = reader.lines().take(2).map(|l| l.unwrap()).collect();
+    let _ = child.kill();
+    let _ = child.wait();
+    assert_eq!(lines.len(), 2);
+    for line in &lines {
+        assert_valid(&serde_json::from_str(line).unwrap());
+    }
+}
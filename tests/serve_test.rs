@@ -0,0 +1,214 @@
+#![allow(deprecated)]
+
+//! Tests of `stop serve` over loopback, collecting from the fixture `/proc` tree.
+
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+const PROC_ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc/host");
+
+/// A running server, killed when dropped.
+struct Server {
+    child: Child,
+    addr: String,
+}
+
+impl Server {
+    fn start(args: &[&str]) -> Self {
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin("stop"))
+            .arg("--proc-root")
+            .arg(PROC_ROOT)
+            .args(args)
+            .args(["serve", "--listen", "127.0.0.1:0"])
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stderr.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let addr = line
+            .trim()
+            .strip_prefix("Listening on http://")
+            .unwrap_or_else(|| panic!("unexpected output: {line}"))
+            .to_string();
+        Self { child, addr }
+    }
+
+    /// Sends a request and returns the status line, headers and body.
+    fn request(&self, method: &str, target: &str) -> (String, String, String) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        write!(
+            stream,
+            "{method} {target} HTTP/1.1\r\nHost: {}\r\n\r\n",
+            self.addr
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let (status, headers) = head.split_once("\r\n").unwrap();
+        (status.to_string(), headers.to_string(), body.to_string())
+    }
+
+    fn get(&self, target: &str) -> (String, String) {
+        let (status, _, body) = self.request("GET", target);
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn pids(json: &Value) -> Vec<u64> {
+    json["processes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["pid"].as_u64().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_serve_healthz() {
+    let server = Server::start(&[]);
+    let (status, headers, body) = server.request("GET", "/healthz");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains("Content-Length: 3"));
+    assert_eq!(body, "ok\n");
+}
+
+#[test]
+fn test_serve_metrics() {
+    let server = Server::start(&[]);
+    let (status, headers, body) = server.request("GET", "/metrics?top_n=1");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains("Content-Type: text/plain; version=0.0.4; charset=utf-8"));
+    assert!(body.starts_with("# HELP stop_system_cpu_usage_percent"));
+    assert!(
+        body.contains(
+            "stop_process_cpu_percent{pid=\"2400\",name=\"python3\",user=\"1000\"} 150\n"
+        )
+    );
+    assert!(!body.contains("pid=\"1021\""));
+}
+
+#[test]
+fn test_serve_snapshot_query() {
+    let server = Server::start(&[]);
+    let (status, body) = server.get("/snapshot");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(pids(&json), vec![2400, 1021, 413, 412, 1, 2]);
+    assert_eq!(json["system"]["cpu_usage"], 30.0);
+
+    let (_, body) = server.get("/snapshot?filter=unit+%3D%3D+nginx&sort_by=pid");
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(pids(&json), vec![412, 413]);
+
+    // Filters on scheduling details collect them for that request
+    let (_, body) = server.get("/snapshot?filter=oom_score%20%3E%20700");
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(pids(&json), vec![2400]);
+}
+
+#[test]
+fn test_serve_command_line_defaults() {
+    let server = Server::start(&["--filter", "cpu > 5", "--top-n", "2"]);
+    let (_, body) = server.get("/snapshot");
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(pids(&json), vec![2400, 1021]);
+
+    // Query parameters override the command line
+    let (_, body) = server.get("/snapshot?top_n=3&sort_by=mem");
+    let json: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(pids(&json), vec![2400, 1021, 413]);
+}
+
+#[test]
+fn test_serve_errors() {
+    let server = Server::start(&[]);
+    let (status, body) = server.get("/snapshot?filter=cpu+%3E");
+    assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    assert!(!body.is_empty());
+
+    let (status, body) = server.get("/metrics?limit=5");
+    assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    assert!(body.contains("unknown query parameter 'limit'"));
+
+    let (status, body) = server.get("/snapshot?sort_by=bogus");
+    assert_eq!(status, "HTTP/1.1 400 Bad Request");
+    assert!(body.contains("invalid sort_by 'bogus'") && body.contains("cputime"));
+
+    let (status, _) = server.get("/nope");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+    let (status, headers, _) = server.request("POST", "/metrics");
+    assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+    assert!(headers.contains("Allow: GET, HEAD"));
+
+    let (status, headers, body) = server.request("HEAD", "/healthz");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains("Content-Length: 3"));
+    assert!(body.is_empty());
+
+    // The server keeps running after bad requests
+    assert_eq!(server.get("/healthz").1, "ok\n");
+}
+
+#[test]
+fn test_serve_invalid_listen_address() {
+    assert_cmd::Command::cargo_bin("stop")
+        .unwrap()
+        .args(["serve", "--listen", "not-an-address"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot listen on not-an-address"));
+}
+
+#[test]
+fn test_serve_slow_clients_do_not_block() {
+    let server = Server::start(&[]);
+    // An idle connection and one sending a partial request stay open
+    let _idle = TcpStream::connect(&server.addr).unwrap();
+    let mut partial = TcpStream::connect(&server.addr).unwrap();
+    write!(partial, "GET /metrics HTTP/1.1\r\nHost: x").unwrap();
+
+    let started = std::time::Instant::now();
+    assert_eq!(server.get("/healthz").1, "ok\n");
+    assert!(started.elapsed() < std::time::Duration::from_secs(1));
+}
+
+#[test]
+fn test_serve_request_too_large() {
+    let server = Server::start(&[]);
+    let mut stream = TcpStream::connect(&server.addr).unwrap();
+    write!(
+        stream,
+        "GET /healthz HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+        "a".repeat(16 * 1024)
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 431 Request Header Fields Too Large"),
+        "{response}"
+    );
+}
+
+#[test]
+fn test_serve_invalid_sort_by() {
+    assert_cmd::Command::cargo_bin("stop")
+        .unwrap()
+        .args(["--sort-by", "bogus", "serve", "--listen", "127.0.0.1:0"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid --sort-by 'bogus'"));
+}